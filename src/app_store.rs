@@ -0,0 +1,292 @@
+use crate::kernel_types::Erc721Metadata;
+use crate::{Message, NodeId, PackageId, Request};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+
+//
+// Types and functions for interacting with the app store
+//
+
+/// IPC body format for local requests sent to `main:app-store:sys`.
+///
+/// Sending these requires messaging capabilities to `main:app-store:sys`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LocalRequest {
+    /// Install a package that has already been downloaded by `downloads:app-store:sys`.
+    Install(InstallPackageRequest),
+    /// Uninstall a package, killing its processes and removing its drives.
+    Uninstall(PackageId),
+    /// List the installed packages along with their installed versions.
+    ListInstalled,
+    /// List the packages that have published an API.
+    ListApis,
+    /// Get the API of a package. The API zip is returned in the blob.
+    GetApi(PackageId),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstallPackageRequest {
+    pub package_id: PackageId,
+    /// Onchain metadata of the package, if known. Used to verify the package.
+    pub metadata: Option<Erc721Metadata>,
+    /// SHA-256 hash of the package zip to install, i.e. the version.
+    pub version_hash: String,
+}
+
+/// IPC body format for responses from `main:app-store:sys` to a [`LocalRequest`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LocalResponse {
+    InstallResponse(InstallResponse),
+    UninstallResponse(UninstallResponse),
+    ListInstalledResponse(Vec<InstalledPackage>),
+    ListApisResponse(Vec<PackageId>),
+    /// Whether the package has an API. If so, the API zip is in the blob.
+    GetApiResponse(bool),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstallResponse {
+    Success,
+    Failure,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UninstallResponse {
+    Success,
+    Failure,
+}
+
+/// A package installed on this node.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstalledPackage {
+    pub package_id: PackageId,
+    /// SHA-256 hash of the installed package zip, i.e. the installed version.
+    pub version_hash: String,
+    /// Whether the installed version matches a hash published onchain.
+    pub verified: bool,
+    /// Whether new versions published onchain are automatically installed.
+    pub auto_update: bool,
+}
+
+/// IPC body format for requests sent to `chain:app-store:sys`, which indexes
+/// packages published onchain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ChainRequest {
+    GetApp(PackageId),
+    GetApps,
+    /// Get the apps published by our node.
+    GetOurApps,
+    StartAutoUpdate(PackageId),
+    StopAutoUpdate(PackageId),
+}
+
+/// IPC body format for responses from `chain:app-store:sys` to a [`ChainRequest`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ChainResponse {
+    GetApp(Option<OnchainApp>),
+    GetApps(Vec<OnchainApp>),
+    GetOurApps(Vec<OnchainApp>),
+    AutoUpdateStarted,
+    AutoUpdateStopped,
+    Err(String),
+}
+
+/// A package as published onchain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnchainApp {
+    pub package_id: PackageId,
+    /// Token-bound account of the package entry in kimap.
+    pub tba: String,
+    pub metadata_uri: String,
+    pub metadata_hash: String,
+    /// The metadata found at `metadata_uri`, if it has been fetched and matches `metadata_hash`.
+    pub metadata: Option<Erc721Metadata>,
+    pub auto_update: bool,
+}
+
+/// IPC body format for requests sent to `downloads:app-store:sys`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DownloadRequest {
+    /// Download a version of a package from a mirror, ready to install.
+    LocalDownload(LocalDownloadRequest),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalDownloadRequest {
+    pub package_id: PackageId,
+    pub download_from: NodeId,
+    pub desired_version_hash: String,
+}
+
+/// IPC body format for responses from `downloads:app-store:sys`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DownloadResponse {
+    /// The download was accepted and has started.
+    Success,
+    Err(String),
+}
+
+/// An installed package for which a newer version has been published onchain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AvailableUpdate {
+    pub package_id: PackageId,
+    pub installed_version_hash: String,
+    pub latest_version: String,
+    pub latest_version_hash: String,
+    /// Nodes the new version can be downloaded from.
+    pub mirrors: Vec<NodeId>,
+}
+
+fn send<B, R>(process: &'static str, body: &B, timeout: u64) -> anyhow::Result<R>
+where
+    B: Serialize,
+    R: DeserializeOwned,
+{
+    let res = Request::to(("our", process, "app-store", "sys"))
+        .body(serde_json::to_vec(body)?)
+        .send_and_await_response(timeout)?;
+
+    match res {
+        Ok(Message::Response { body, .. }) => Ok(serde_json::from_slice::<R>(&body)?),
+        _ => Err(anyhow::anyhow!("app-store: unexpected message: {:?}", res)),
+    }
+}
+
+/// List the packages installed on this node, with their installed versions.
+pub fn installed_packages() -> anyhow::Result<Vec<InstalledPackage>> {
+    match send("main", &LocalRequest::ListInstalled, 5)? {
+        LocalResponse::ListInstalledResponse(packages) => Ok(packages),
+        response => Err(anyhow::anyhow!(
+            "app-store: unexpected response {:?}",
+            response
+        )),
+    }
+}
+
+/// Get the installed version of a package, or `None` if it is not installed.
+pub fn installed_package(package_id: &PackageId) -> anyhow::Result<Option<InstalledPackage>> {
+    Ok(installed_packages()?
+        .into_iter()
+        .find(|package| &package.package_id == package_id))
+}
+
+/// Get a package as published onchain, or `None` if it has not been published.
+pub fn get_app(package_id: &PackageId) -> anyhow::Result<Option<OnchainApp>> {
+    match send("chain", &ChainRequest::GetApp(package_id.clone()), 5)? {
+        ChainResponse::GetApp(app) => Ok(app),
+        ChainResponse::Err(e) => Err(anyhow::anyhow!("app-store: {}", e)),
+        response => Err(anyhow::anyhow!(
+            "app-store: unexpected response {:?}",
+            response
+        )),
+    }
+}
+
+/// Get every package published onchain.
+pub fn get_apps() -> anyhow::Result<Vec<OnchainApp>> {
+    match send("chain", &ChainRequest::GetApps, 5)? {
+        ChainResponse::GetApps(apps) => Ok(apps),
+        ChainResponse::Err(e) => Err(anyhow::anyhow!("app-store: {}", e)),
+        response => Err(anyhow::anyhow!(
+            "app-store: unexpected response {:?}",
+            response
+        )),
+    }
+}
+
+/// Check every installed package against its onchain metadata, returning those
+/// whose current published version differs from the installed one. Takes two
+/// messages, whatever the number of packages installed.
+pub fn available_updates() -> anyhow::Result<Vec<AvailableUpdate>> {
+    let mut metadata_by_package: HashMap<PackageId, Erc721Metadata> = get_apps()?
+        .into_iter()
+        .filter_map(|app| Some((app.package_id, app.metadata?)))
+        .collect();
+    let mut updates = vec![];
+    for installed in installed_packages()? {
+        let Some(metadata) = metadata_by_package.remove(&installed.package_id) else {
+            continue;
+        };
+        let Some(latest_hash) = metadata.current_version_hash() else {
+            continue;
+        };
+        if latest_hash != installed.version_hash {
+            updates.push(AvailableUpdate {
+                package_id: installed.package_id,
+                installed_version_hash: installed.version_hash,
+                latest_version: metadata.properties.current_version.clone(),
+                latest_version_hash: latest_hash.to_string(),
+                mirrors: metadata.properties.mirrors.clone(),
+            });
+        }
+    }
+    Ok(updates)
+}
+
+/// Start downloading a version of a package from a mirror. Once the download
+/// completes, the package can be installed with [`install()`].
+pub fn download(package_id: &PackageId, from: &str, version_hash: &str) -> anyhow::Result<()> {
+    let request = DownloadRequest::LocalDownload(LocalDownloadRequest {
+        package_id: package_id.clone(),
+        download_from: from.to_string(),
+        desired_version_hash: version_hash.to_string(),
+    });
+    match send("downloads", &request, 5)? {
+        DownloadResponse::Success => Ok(()),
+        DownloadResponse::Err(e) => Err(anyhow::anyhow!("app-store: {}", e)),
+    }
+}
+
+/// Install a downloaded version of a package. Installing can take some time,
+/// so this waits up to 60 seconds for `main:app-store:sys` to respond.
+pub fn install(package_id: &PackageId, version_hash: &str) -> anyhow::Result<()> {
+    let metadata = get_app(package_id)?.and_then(|app| app.metadata);
+    let request = LocalRequest::Install(InstallPackageRequest {
+        package_id: package_id.clone(),
+        metadata,
+        version_hash: version_hash.to_string(),
+    });
+    match send("main", &request, 60)? {
+        LocalResponse::InstallResponse(InstallResponse::Success) => Ok(()),
+        LocalResponse::InstallResponse(InstallResponse::Failure) => Err(anyhow::anyhow!(
+            "app-store: failed to install {}",
+            package_id
+        )),
+        response => Err(anyhow::anyhow!(
+            "app-store: unexpected response {:?}",
+            response
+        )),
+    }
+}
+
+/// Uninstall a package.
+pub fn uninstall(package_id: &PackageId) -> anyhow::Result<()> {
+    match send("main", &LocalRequest::Uninstall(package_id.clone()), 30)? {
+        LocalResponse::UninstallResponse(UninstallResponse::Success) => Ok(()),
+        LocalResponse::UninstallResponse(UninstallResponse::Failure) => Err(anyhow::anyhow!(
+            "app-store: failed to uninstall {}",
+            package_id
+        )),
+        response => Err(anyhow::anyhow!(
+            "app-store: unexpected response {:?}",
+            response
+        )),
+    }
+}
+
+/// Turn automatic installation of new published versions of a package on or off.
+pub fn set_auto_update(package_id: &PackageId, auto_update: bool) -> anyhow::Result<()> {
+    let request = if auto_update {
+        ChainRequest::StartAutoUpdate(package_id.clone())
+    } else {
+        ChainRequest::StopAutoUpdate(package_id.clone())
+    };
+    match send("chain", &request, 5)? {
+        ChainResponse::AutoUpdateStarted | ChainResponse::AutoUpdateStopped => Ok(()),
+        ChainResponse::Err(e) => Err(anyhow::anyhow!("app-store: {}", e)),
+        response => Err(anyhow::anyhow!(
+            "app-store: unexpected response {:?}",
+            response
+        )),
+    }
+}
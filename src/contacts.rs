@@ -0,0 +1,154 @@
+use crate::kv::{self, Kv, KvError};
+use crate::{Address, AddressParseError, NodeId, PackageId, ProcessId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+//
+// A local address book mapping aliases to node names, persisted in kv
+//
+
+/// The kv key the address book is stored under.
+const CONTACTS_KEY: &str = "contacts";
+
+/// A peer in the address book.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub alias: String,
+    pub node: NodeId,
+    pub note: Option<String>,
+}
+
+/// An address book mapping human-friendly aliases, like `alice`, to node names,
+/// like `alice-node.os`, persisted in a kv database owned by the calling package.
+///
+/// The whole address book is held in memory and written back on every change,
+/// so it is meant for a modest number of contacts.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{contacts::Contacts, our};
+///
+/// let mut contacts = Contacts::open(our().package_id(), None).unwrap();
+/// contacts.insert("alice", "alice-node.os", None).unwrap();
+/// let address = contacts.address("alice", ("chat", "chat", "template.os"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Contacts {
+    kv: Kv<String, BTreeMap<String, Contact>>,
+    contacts: BTreeMap<String, Contact>,
+}
+
+impl Contacts {
+    /// Open the address book stored in the kv database `db` (default `contacts`)
+    /// of the given package, creating it if it does not exist.
+    pub fn open(package_id: PackageId, db: Option<&str>) -> anyhow::Result<Self> {
+        let kv = kv::open(package_id, db.unwrap_or("contacts"), None)?;
+        let contacts = match kv.get(&CONTACTS_KEY.to_string()) {
+            Ok(contacts) => contacts,
+            Err(e) if matches!(e.downcast_ref::<KvError>(), Some(KvError::KeyNotFound)) => {
+                BTreeMap::new()
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Contacts { kv, contacts })
+    }
+    fn save(&self) -> anyhow::Result<()> {
+        self.kv.set(&CONTACTS_KEY.to_string(), &self.contacts, None)
+    }
+    /// Add a contact, or update the node and note of an existing one.
+    /// Returns the previous contact under `alias`, if any.
+    ///
+    /// Aliases may not be empty or contain `@`, so they cannot be confused with addresses.
+    pub fn insert(
+        &mut self,
+        alias: &str,
+        node: &str,
+        note: Option<&str>,
+    ) -> anyhow::Result<Option<Contact>> {
+        if alias.is_empty() || alias.contains('@') {
+            return Err(anyhow::anyhow!("contacts: invalid alias {:?}", alias));
+        }
+        if node.is_empty() || node.contains('@') {
+            return Err(anyhow::anyhow!("contacts: invalid node name {:?}", node));
+        }
+        let previous = self.contacts.insert(
+            alias.to_string(),
+            Contact {
+                alias: alias.to_string(),
+                node: node.to_string(),
+                note: note.map(|note| note.to_string()),
+            },
+        );
+        self.save()?;
+        Ok(previous)
+    }
+    /// Remove a contact, returning it if it existed.
+    pub fn remove(&mut self, alias: &str) -> anyhow::Result<Option<Contact>> {
+        let removed = self.contacts.remove(alias);
+        if removed.is_some() {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+    /// Change the alias of a contact. Fails if `new_alias` is already taken.
+    pub fn rename(&mut self, alias: &str, new_alias: &str) -> anyhow::Result<()> {
+        if self.contacts.contains_key(new_alias) {
+            return Err(anyhow::anyhow!(
+                "contacts: alias {} already exists",
+                new_alias
+            ));
+        }
+        let Some(contact) = self.contacts.remove(alias) else {
+            return Err(anyhow::anyhow!("contacts: no contact {}", alias));
+        };
+        if let Err(e) = self.insert(new_alias, &contact.node, contact.note.as_deref()) {
+            self.contacts.remove(new_alias);
+            self.contacts.insert(alias.to_string(), contact);
+            return Err(e);
+        }
+        Ok(())
+    }
+    pub fn get(&self, alias: &str) -> Option<&Contact> {
+        self.contacts.get(alias)
+    }
+    /// All contacts, ordered by alias.
+    pub fn list(&self) -> impl Iterator<Item = &Contact> {
+        self.contacts.values()
+    }
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+    /// The alias of a node, if it is in the address book.
+    pub fn alias_of(&self, node: &str) -> Option<&str> {
+        self.contacts
+            .values()
+            .find(|contact| contact.node == node)
+            .map(|contact| contact.alias.as_str())
+    }
+    /// Resolve an alias to its node name. Anything that is not a known alias
+    /// is assumed to already be a node name, and is returned unchanged.
+    pub fn resolve(&self, alias_or_node: &str) -> NodeId {
+        match self.contacts.get(alias_or_node) {
+            Some(contact) => contact.node.clone(),
+            None => alias_or_node.to_string(),
+        }
+    }
+    /// Build the [`Address`] of a process on the node of a contact (or on a node
+    /// given by name).
+    pub fn address<T>(&self, alias_or_node: &str, process: T) -> Address
+    where
+        T: Into<ProcessId>,
+    {
+        Address::new(self.resolve(alias_or_node), process)
+    }
+    /// Parse an [`Address`] string whose node may be an alias,
+    /// e.g. `alice@chat:chat:template.os`.
+    pub fn parse_address(&self, input: &str) -> Result<Address, AddressParseError> {
+        let mut address: Address = input.parse()?;
+        address.node = self.resolve(&address.node);
+        Ok(address)
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{net, our, Address, LazyLoadBlob, SendError};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+//
+// Signatures made with the node's networking key
+//
+
+/// A message along with a signature over it, made with the networking key of the
+/// signer's node. Serializable, so it can be handed to other parties and checked
+/// with [`SignedMessage::verify()`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    /// The process that made the signature. The runtime prepends this address to
+    /// the message before signing, so a signature is only valid for its signer.
+    pub signer: Address,
+    pub message: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedMessage {
+    /// Verify the signature against the networking key of the signer's node.
+    /// Returns `Ok(false)` if the signature is invalid, or if the signer's node
+    /// cannot be found in our view of the PKI.
+    pub fn verify(&self) -> Result<bool, SendError> {
+        verify_from(
+            self.signer.clone(),
+            self.message.clone(),
+            self.signature.clone(),
+        )
+    }
+}
+
+/// Sign bytes with our node's networking key, producing a node-attributable
+/// signature, e.g. for an off-chain attestation.
+///
+/// The signature covers our [`Address`] along with the given bytes, so a signature
+/// made by one process cannot be passed off as being made by another. Verify it
+/// with [`verify()`] from the same process on any node, or with [`verify_from()`].
+///
+/// Uses a 30-second timeout to reach `net:distro:sys`.
+pub fn sign<T>(bytes: T) -> Result<Vec<u8>, SendError>
+where
+    T: Into<Vec<u8>>,
+{
+    net::sign(bytes)
+}
+
+/// Sign bytes with our node's networking key, returning a [`SignedMessage`]
+/// that carries everything needed to verify it.
+///
+/// Uses a 30-second timeout to reach `net:distro:sys`.
+pub fn sign_message<T>(bytes: T) -> Result<SignedMessage, SendError>
+where
+    T: Into<Vec<u8>>,
+{
+    let message = bytes.into();
+    let signature = net::sign(message.clone())?;
+    Ok(SignedMessage {
+        signer: our(),
+        message,
+        signature,
+    })
+}
+
+/// Verify a signature made with [`sign()`] by a process with the same
+/// [`crate::ProcessId`] as ours, running on `node`. This is the common case
+/// of one instance of an app checking an attestation made by another.
+///
+/// Returns `Ok(false)` if the signature is invalid, or if `node` cannot be
+/// found in our view of the PKI. Uses a 30-second timeout to reach `net:distro:sys`.
+pub fn verify<T, U>(node: &str, bytes: T, signature: U) -> Result<bool, SendError>
+where
+    T: Into<Vec<u8>>,
+    U: Into<Vec<u8>>,
+{
+    verify_from(Address::new(node, our().process), bytes, signature)
+}
+
+/// Verify a signature made with [`sign()`] by the process at `signer`.
+///
+/// Returns `Ok(false)` if the signature is invalid, or if the signer's node
+/// cannot be found in our view of the PKI. Uses a 30-second timeout to reach
+/// `net:distro:sys`.
+pub fn verify_from<T, U, V>(signer: T, bytes: U, signature: V) -> Result<bool, SendError>
+where
+    T: Into<Address>,
+    U: Into<Vec<u8>>,
+    V: Into<Vec<u8>>,
+{
+    net::verify(signer, bytes, signature)
+}
+
+//
+// Symmetric encryption of blobs
+//
+
+/// Length in bytes of an [`EncryptionKey`].
+pub const KEY_LEN: usize = 32;
+/// Length in bytes of the random nonce prepended to each ciphertext.
+pub const NONCE_LEN: usize = 24;
+
+/// A 256-bit key for XChaCha20-Poly1305 authenticated encryption.
+///
+/// Keys should either be generated randomly with [`EncryptionKey::generate()`] and
+/// stored somewhere safe, or derived from another secret with [`EncryptionKey::derive()`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    /// Generate a new random key.
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut key);
+        EncryptionKey(key)
+    }
+    pub fn from_bytes(bytes: [u8; KEY_LEN]) -> Self {
+        EncryptionKey(bytes)
+    }
+    /// Derive a key from input keying material using HKDF-SHA256.
+    ///
+    /// `secret` should have high entropy: this is not a password hash. `salt` may be
+    /// empty, and `info` binds the key to a purpose, so that one secret can be used
+    /// to derive several independent keys, e.g. `b"my-app:kv-encryption"`.
+    pub fn derive(secret: &[u8], salt: &[u8], info: &[u8]) -> Self {
+        let mut key = [0u8; KEY_LEN];
+        Hkdf::<Sha256>::new(Some(salt), secret)
+            .expand(info, &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        EncryptionKey(key)
+    }
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptionKey(..)")
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum CryptoError {
+    #[error("ciphertext too short to contain a nonce")]
+    Malformed,
+    #[error("decryption failed: wrong key, or ciphertext was tampered with")]
+    DecryptionFailed,
+    #[error("encryption failed")]
+    EncryptionFailed,
+}
+
+/// Encrypt bytes with XChaCha20-Poly1305 under a random nonce, returning the nonce
+/// followed by the ciphertext. `associated_data` is authenticated but not encrypted:
+/// the same value must be given to [`decrypt()`].
+pub fn encrypt(
+    key: &EncryptionKey,
+    plaintext: &[u8],
+    associated_data: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = XChaCha20Poly1305::new(key.as_bytes().into())
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| CryptoError::EncryptionFailed)?;
+    let mut output = nonce.to_vec();
+    output.extend(ciphertext);
+    Ok(output)
+}
+
+/// Decrypt bytes produced by [`encrypt()`].
+pub fn decrypt(
+    key: &EncryptionKey,
+    ciphertext: &[u8],
+    associated_data: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    if ciphertext.len() < NONCE_LEN {
+        return Err(CryptoError::Malformed);
+    }
+    let (nonce, ciphertext) = ciphertext.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.as_bytes().into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| CryptoError::DecryptionFailed)
+}
+
+/// Encrypt the bytes of a [`LazyLoadBlob`], e.g. before writing it to the VFS or KV
+/// or sending it to another node.
+///
+/// The mime type is left in the clear, but is authenticated: decryption fails if it
+/// is changed.
+pub fn encrypt_blob(key: &EncryptionKey, blob: &LazyLoadBlob) -> Result<LazyLoadBlob, CryptoError> {
+    let mime = blob.mime.clone().unwrap_or_default();
+    Ok(LazyLoadBlob {
+        mime: blob.mime.clone(),
+        bytes: encrypt(key, &blob.bytes, mime.as_bytes())?,
+    })
+}
+
+/// Decrypt a [`LazyLoadBlob`] produced by [`encrypt_blob()`].
+pub fn decrypt_blob(key: &EncryptionKey, blob: &LazyLoadBlob) -> Result<LazyLoadBlob, CryptoError> {
+    let mime = blob.mime.clone().unwrap_or_default();
+    Ok(LazyLoadBlob {
+        mime: blob.mime.clone(),
+        bytes: decrypt(key, &blob.bytes, mime.as_bytes())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_round_trip() {
+        let key = EncryptionKey::derive(b"secret", b"salt", b"test");
+        let blob = LazyLoadBlob::new(Some("text/plain"), b"hello".to_vec());
+        let encrypted = encrypt_blob(&key, &blob).unwrap();
+        assert_ne!(encrypted.bytes, blob.bytes);
+        assert_eq!(decrypt_blob(&key, &encrypted).unwrap(), blob);
+
+        let wrong_key = EncryptionKey::generate();
+        assert_eq!(
+            decrypt_blob(&wrong_key, &encrypted),
+            Err(CryptoError::DecryptionFailed)
+        );
+        let tampered = LazyLoadBlob::new(Some("text/html"), encrypted.bytes.clone());
+        assert_eq!(
+            decrypt_blob(&key, &tampered),
+            Err(CryptoError::DecryptionFailed)
+        );
+        assert_eq!(decrypt(&key, &[0u8; 3], b""), Err(CryptoError::Malformed));
+    }
+}
@@ -0,0 +1,101 @@
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use bech32::{Bech32, Hrp};
+use thiserror::Error;
+
+//
+// Conversions between bytes and common text encodings
+//
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum EncodingError {
+    #[error("invalid hex: {0}")]
+    Hex(String),
+    #[error("invalid base64: {0}")]
+    Base64(String),
+    #[error("invalid bech32: {0}")]
+    Bech32(String),
+}
+
+/// Encode bytes as lowercase hex, without a `0x` prefix.
+pub fn hex_encode<T>(bytes: T) -> String
+where
+    T: AsRef<[u8]>,
+{
+    hex::encode(bytes)
+}
+
+/// Decode hex, with or without a `0x` prefix, in either case.
+pub fn hex_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    hex::decode(s).map_err(|e| EncodingError::Hex(e.to_string()))
+}
+
+/// Encode bytes as standard, padded base64.
+pub fn base64_encode<T>(bytes: T) -> String
+where
+    T: AsRef<[u8]>,
+{
+    STANDARD.encode(bytes)
+}
+
+/// Decode standard, padded base64.
+pub fn base64_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    STANDARD
+        .decode(s)
+        .map_err(|e| EncodingError::Base64(e.to_string()))
+}
+
+/// Encode bytes as URL-safe base64 without padding, suitable for use in
+/// URLs, headers, and paths.
+pub fn base64url_encode<T>(bytes: T) -> String
+where
+    T: AsRef<[u8]>,
+{
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decode URL-safe base64 without padding.
+pub fn base64url_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    URL_SAFE_NO_PAD
+        .decode(s)
+        .map_err(|e| EncodingError::Base64(e.to_string()))
+}
+
+/// Encode bytes as bech32 (BIP-173) with the given human-readable part.
+pub fn bech32_encode<T>(hrp: &str, bytes: T) -> Result<String, EncodingError>
+where
+    T: AsRef<[u8]>,
+{
+    let hrp = Hrp::parse(hrp).map_err(|e| EncodingError::Bech32(e.to_string()))?;
+    bech32::encode::<Bech32>(hrp, bytes.as_ref()).map_err(|e| EncodingError::Bech32(e.to_string()))
+}
+
+/// Decode bech32 or bech32m, returning the human-readable part and the bytes.
+pub fn bech32_decode(s: &str) -> Result<(String, Vec<u8>), EncodingError> {
+    let (hrp, bytes) = bech32::decode(s).map_err(|e| EncodingError::Bech32(e.to_string()))?;
+    Ok((hrp.to_string(), bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips() {
+        let bytes = b"\x00\xffkinode";
+        assert_eq!(hex_decode(&hex_encode(bytes)).unwrap(), bytes);
+        assert_eq!(hex_decode("0xFF00").unwrap(), vec![0xff, 0x00]);
+        assert_eq!(base64_decode(&base64_encode(bytes)).unwrap(), bytes);
+        assert_eq!(base64url_decode(&base64url_encode(bytes)).unwrap(), bytes);
+        let encoded = bech32_encode("kin", bytes).unwrap();
+        assert!(encoded.starts_with("kin1"));
+        assert_eq!(
+            bech32_decode(&encoded).unwrap(),
+            ("kin".to_string(), bytes.to_vec())
+        );
+        assert!(matches!(hex_decode("zz"), Err(EncodingError::Hex(_))));
+    }
+}
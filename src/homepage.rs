@@ -1,9 +1,32 @@
 use crate::Request;
+use serde::{Deserialize, Serialize};
 
-/// Add a new icon and/or widget to the Kinode homepage. Note that the process calling this
-/// function must have the `homepage:homepage:sys` messaging [`crate::Capability`].
+/// IPC body format for requests sent to `homepage:homepage:sys`.
 ///
-/// This should be called upon process startup to ensure that the process is added to the homepage.
+/// Homepage entries are keyed by the source process of the request, so each
+/// process may have at most one entry (one icon and/or one widget) at a time.
+/// `homepage:homepage:sys` does not respond to these requests.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum HomepageRequest {
+    /// Add (or overwrite) the homepage entry of the source process.
+    Add(HomepageEntry),
+    /// Remove the homepage entry of the source process.
+    Remove,
+    /// Remove the homepage entry of another process, given as a process ID string.
+    /// Requires root capabilities for `homepage:homepage:sys`.
+    RemoveOther(String),
+    /// Set the stylesheet served at `/kinode.css`, given as a CSS string.
+    /// Requires root capabilities for `homepage:homepage:sys`.
+    SetStylesheet(String),
+    /// Set the widget of the homepage entry of the source process, given as HTML,
+    /// keeping its label, icon and path. Ignored if the process has no entry.
+    SetWidget(String),
+    /// Remove the widget of the homepage entry of the source process, keeping
+    /// its label, icon and path.
+    RemoveWidget,
+}
+
+/// An entry on the Kinode homepage, contained in [`HomepageRequest::Add`].
 ///
 /// An icon must be a base64 encoded SVG.
 ///
@@ -12,19 +35,34 @@ use crate::Request;
 /// will be `my:process:pkg/mypath`.
 ///
 /// A widget should be HTML: it will be displayed in an iframe.
-pub fn add_to_homepage(label: &str, icon: Option<&str>, path: Option<&str>, widget: Option<&str>) {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HomepageEntry {
+    pub label: String,
+    pub icon: Option<String>,
+    pub path: Option<String>,
+    pub widget: Option<String>,
+}
+
+impl From<HomepageRequest> for Vec<u8> {
+    fn from(request: HomepageRequest) -> Self {
+        serde_json::to_vec(&request).unwrap()
+    }
+}
+
+/// Add a new icon and/or widget to the Kinode homepage. Note that the process calling this
+/// function must have the `homepage:homepage:sys` messaging [`crate::Capability`].
+///
+/// This should be called upon process startup to ensure that the process is added to the homepage.
+///
+/// See [`HomepageEntry`] for the format of each field.
+pub fn add_entry(label: &str, icon: Option<&str>, path: Option<&str>, widget: Option<&str>) {
     Request::to(("our", "homepage", "homepage", "sys"))
-        .body(
-            serde_json::json!({
-                "Add": {
-                    "label": label,
-                    "icon": icon,
-                    "path": path,
-                    "widget": widget
-                }
-            })
-            .to_string(),
-        )
+        .body(HomepageRequest::Add(HomepageEntry {
+            label: label.to_string(),
+            icon: icon.map(|icon| icon.to_string()),
+            path: path.map(|path| path.to_string()),
+            widget: widget.map(|widget| widget.to_string()),
+        }))
         .send()
         .unwrap();
 }
@@ -33,9 +71,45 @@ pub fn add_to_homepage(label: &str, icon: Option<&str>, path: Option<&str>, widg
 /// must have the `homepage:homepage:sys` messaging [`crate::Capability`].
 ///
 /// This usually isn't necessary as processes are not persisted on homepage between boots.
+pub fn remove_entry() {
+    Request::to(("our", "homepage", "homepage", "sys"))
+        .body(HomepageRequest::Remove)
+        .send()
+        .unwrap();
+}
+
+/// Add a new icon and/or widget to the Kinode homepage. Equivalent to [`add_entry()`].
+pub fn add_to_homepage(label: &str, icon: Option<&str>, path: Option<&str>, widget: Option<&str>) {
+    add_entry(label, icon, path, widget);
+}
+
+/// Remove the caller process from the Kinode homepage. Equivalent to [`remove_entry()`].
 pub fn remove_from_homepage() {
+    remove_entry();
+}
+
+/// Replace the widget shown on the Kinode homepage for the caller process, keeping the
+/// label, icon and path of its entry, which must have been added with [`add_entry()`].
+/// Note that the process calling this function must have the `homepage:homepage:sys`
+/// messaging [`crate::Capability`].
+///
+/// Call this whenever the widget HTML should change, e.g. upon a timer firing, to display
+/// live data.
+pub fn update_widget(widget: &str) {
+    Request::to(("our", "homepage", "homepage", "sys"))
+        .body(HomepageRequest::SetWidget(widget.to_string()))
+        .send()
+        .unwrap();
+}
+
+/// Remove the widget from the caller process's homepage entry, keeping its label, icon
+/// and path. Note that the process calling this function must have the
+/// `homepage:homepage:sys` messaging [`crate::Capability`].
+///
+/// To remove the entry entirely, use [`remove_entry()`].
+pub fn remove_widget() {
     Request::to(("our", "homepage", "homepage", "sys"))
-        .body("\"Remove\"")
+        .body(HomepageRequest::RemoveWidget)
         .send()
         .unwrap();
 }
@@ -0,0 +1,181 @@
+use crate::http::server::{HttpResponse, HttpResponseWithBody, IncomingHttpRequest};
+use crate::http::StatusCode;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//
+// API keys for external services, sent as bearer tokens or in an X-API-Key header
+//
+
+/// Header in which clients may send an API key, instead of `Authorization: Bearer`.
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Length in bytes of the random part of API keys.
+const SECRET_LEN: usize = 32;
+/// Length in bytes of API key IDs.
+const ID_LEN: usize = 8;
+
+/// An API key, as kept by [`ApiKeys`]. The key itself is only known to whoever
+/// it was given to: only its SHA-256 hash is kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    /// A name to tell keys apart, e.g. the service the key was issued to.
+    pub name: String,
+    /// Seconds since the UNIX epoch.
+    pub created_at: u64,
+    /// Seconds since the UNIX epoch, updated when the key is rotated.
+    pub rotated_at: Option<u64>,
+    hash: String,
+}
+
+/// The API keys managed by a process, to authenticate requests from external
+/// services on paths bound without the node login cookie.
+///
+/// Keys have the form `{id}.{secret}`, so that a key is found by its ID and
+/// checked against its hash. They are sent in an `Authorization: Bearer`
+/// header or an [`API_KEY_HEADER`] header.
+///
+/// [`ApiKeys`] is serializable, so that a process can persist its keys, e.g.
+/// with [`crate::set_state()`]. To have requests without a valid key answered
+/// `401 Unauthorized` automatically, give the keys to
+/// [`crate::http::server::HttpServer::set_api_keys()`] and bind paths with
+/// [`crate::http::server::HttpBindingConfig::require_api_key()`].
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::http::api_keys::ApiKeys;
+///
+/// let mut keys = ApiKeys::new();
+/// let (id, key) = keys.generate("ci");
+/// assert_eq!(keys.verify(&key).unwrap().name, "ci");
+/// let rotated = keys.rotate(&id).unwrap();
+/// assert!(keys.verify(&key).is_none());
+/// assert!(keys.verify(&rotated).is_some());
+/// keys.revoke(&id);
+/// assert!(keys.verify(&rotated).is_none());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeys {
+    keys: HashMap<String, ApiKey>,
+}
+
+impl ApiKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate a new key named `name`. Returns its ID and the key, which is
+    /// not kept and must be given to the client now.
+    pub fn generate(&mut self, name: &str) -> (String, String) {
+        let id = hex::encode(random_bytes(ID_LEN));
+        let key = new_key(&id);
+        self.keys.insert(
+            id.clone(),
+            ApiKey {
+                id: id.clone(),
+                name: name.to_string(),
+                created_at: now_secs(),
+                rotated_at: None,
+                hash: hash(&key),
+            },
+        );
+        (id, key)
+    }
+
+    /// Replace the key with ID `id` by a new one, keeping its ID and name.
+    /// The old key stops working immediately. Returns the new key, or `None`
+    /// if there is no key with this ID.
+    pub fn rotate(&mut self, id: &str) -> Option<String> {
+        let api_key = self.keys.get_mut(id)?;
+        let key = new_key(id);
+        api_key.hash = hash(&key);
+        api_key.rotated_at = Some(now_secs());
+        Some(key)
+    }
+
+    /// Revoke the key with ID `id`. Returns whether there was such a key.
+    pub fn revoke(&mut self, id: &str) -> bool {
+        self.keys.remove(id).is_some()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ApiKey> {
+        self.keys.get(id)
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = &ApiKey> {
+        self.keys.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The key that `key` is, if it is valid.
+    pub fn verify(&self, key: &str) -> Option<&ApiKey> {
+        let (id, _) = key.split_once('.')?;
+        let api_key = self.keys.get(id)?;
+        constant_time_eq(api_key.hash.as_bytes(), hash(key).as_bytes()).then_some(api_key)
+    }
+
+    /// The key a request was made with, if it sent a valid one in an
+    /// `Authorization: Bearer` header or an [`API_KEY_HEADER`] header.
+    pub fn authenticate(&self, request: &IncomingHttpRequest) -> Option<&ApiKey> {
+        let headers = request.headers();
+        let bearer = headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                let (scheme, token) = value.trim().split_once(' ')?;
+                scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+            });
+        let header = headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim);
+        bearer
+            .and_then(|key| self.verify(key))
+            .or_else(|| header.and_then(|key| self.verify(key)))
+    }
+
+    /// As [`ApiKeys::authenticate()`], with the `401 Unauthorized` response to
+    /// send if the request has no valid key.
+    pub fn check(&self, request: &IncomingHttpRequest) -> Result<&ApiKey, HttpResponseWithBody> {
+        self.authenticate(request).ok_or_else(|| {
+            HttpResponse::json_error(StatusCode::UNAUTHORIZED, "missing or invalid API key")
+                .header("WWW-Authenticate", "Bearer")
+        })
+    }
+}
+
+fn new_key(id: &str) -> String {
+    format!("{}.{}", id, hex::encode(random_bytes(SECRET_LEN)))
+}
+
+fn hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -1,9 +1,13 @@
+use super::multipart::MultipartBuilder;
 pub use super::server::{HttpResponse, WsMessageType};
-use crate::{get_blob, LazyLoadBlob as KiBlob, Message, Request as KiRequest};
+use crate::vfs::{metadata, parse_response, vfs_request, VfsAction, VfsError, VfsResponse};
+use crate::{get_blob, LazyLoadBlob as KiBlob, Message, Request as KiRequest, SendError};
 use http::Method;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
 
 /// [`crate::Request`] type sent to the `http-client:distro:sys` service in order to open a
@@ -44,11 +48,98 @@ pub struct OutgoingHttpRequest {
     /// must parse to [`url::Url`]
     pub url: String,
     pub headers: HashMap<String, String>,
+    /// proxy to send the request through, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<HttpProxy>,
+    /// if set, the response body is sent in chunks rather than in the response blob
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunked: Option<ChunkedBody>,
+}
+
+/// How http-client delivers the body of a response to an [`OutgoingHttpRequest`]
+/// in chunks: the response itself has no blob, and is followed by a
+/// [`HttpClientRequest::DownloadChunk`] for each chunk of at most `chunk_size`
+/// bytes, then a [`HttpClientRequest::DownloadComplete`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkedBody {
+    /// chosen by the process, to tell the chunks of concurrent downloads apart
+    pub download_id: u64,
+    pub chunk_size: u64,
+}
+
+/// Proxy that http-client sends an [`OutgoingHttpRequest`] through.
+///
+/// Set it per request with [`OutgoingRequestBuilder::proxy()`], or for every
+/// request of the process with [`set_default_proxy()`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpProxy {
+    /// must parse to [`url::Url`] with scheme `http`, `https`, `socks5` or `socks5h`
+    pub url: String,
+    pub auth: Option<ProxyAuth>,
+}
+
+/// Credentials for an [`HttpProxy`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl HttpProxy {
+    pub fn new<T>(url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            auth: None,
+        }
+    }
+
+    /// Authenticate to the proxy with a username and password.
+    pub fn basic_auth<T, U>(mut self, username: T, password: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.auth = Some(ProxyAuth {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Check that the proxy URL parses and has a supported scheme.
+    pub fn validate(&self) -> std::result::Result<(), HttpClientError> {
+        match url::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") => Ok(()),
+            _ => Err(HttpClientError::BadUrl {
+                url: self.url.clone(),
+            }),
+        }
+    }
+}
+
+thread_local! {
+    static DEFAULT_PROXY: RefCell<Option<HttpProxy>> = const { RefCell::new(None) };
+}
+
+/// Set the proxy used by every HTTP request this process sends with http-client,
+/// unless overridden with [`OutgoingRequestBuilder::proxy()`]. `None` sends
+/// requests directly, which is the default.
+pub fn set_default_proxy(proxy: Option<HttpProxy>) {
+    DEFAULT_PROXY.with(|default| *default.borrow_mut() = proxy);
+}
+
+/// The proxy set with [`set_default_proxy()`], if any.
+pub fn default_proxy() -> Option<HttpProxy> {
+    DEFAULT_PROXY.with(|default| default.borrow().clone())
 }
 
 /// [`crate::Request`] that comes from an open WebSocket client connection in the
 /// `http-client:distro:sys` service. Be prepared to receive these after
-/// using a [`HttpClientAction::WebSocketOpen`] to open a connection.
+/// using a [`HttpClientAction::WebSocketOpen`] to open a connection, or after
+/// sending an [`OutgoingHttpRequest`] with a [`ChunkedBody`].
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum HttpClientRequest {
     WebSocketPush {
@@ -58,6 +149,18 @@ pub enum HttpClientRequest {
     WebSocketClose {
         channel_id: u32,
     },
+    /// Chunk number `index`, counting from 0, of a response body, in the blob.
+    /// `total` is the number of chunks, if the length of the body is known.
+    DownloadChunk {
+        download_id: u64,
+        index: u64,
+        total: Option<u64>,
+    },
+    /// All `chunks` chunks of a response body have been sent.
+    DownloadComplete {
+        download_id: u64,
+        chunks: u64,
+    },
 }
 
 /// [`crate::Response`] type received from the `http-client:distro:sys` service after
@@ -95,10 +198,38 @@ pub enum HttpClientError {
     WsPushBadText,
     #[error("failed to close connection {channel_id} because it was not open")]
     WsCloseFailed { channel_id: u32 },
+
+    // Typed response errors
+    /// Not actually issued by `http-client:distro:sys`, just this library
+    #[error("request failed with status {status}: {body}")]
+    BadStatus { status: u16, body: String },
+    /// Not actually issued by `http-client:distro:sys`, just this library
+    #[error("expected a JSON response, got content type {content_type}: {body}")]
+    NotJson { content_type: String, body: String },
+    /// Not actually issued by `http-client:distro:sys`, just this library
+    #[error("failed to deserialize JSON response: {error}: {body}")]
+    BadJson { error: String, body: String },
+    /// Not actually issued by `http-client:distro:sys`, just this library
+    #[error("response context missing or invalid: {0}")]
+    BadContext(String),
+}
+
+/// How many bytes of a response body are kept in [`HttpClientError::BadStatus`],
+/// [`HttpClientError::NotJson`] and [`HttpClientError::BadJson`].
+const BODY_SNIPPET_LEN: usize = 256;
+
+fn body_snippet(body: &[u8]) -> String {
+    let snippet = String::from_utf8_lossy(&body[..body.len().min(BODY_SNIPPET_LEN)]);
+    if body.len() > BODY_SNIPPET_LEN {
+        format!("{}...", snippet)
+    } else {
+        snippet.into_owned()
+    }
 }
 
 /// Fire off an HTTP request. If a timeout is given, the response will
 /// come in the main event loop, otherwise none will be given.
+/// The request goes through the proxy set with [`set_default_proxy()`], if any.
 ///
 /// Note that the response type is [`type@HttpClientResponse`], which, if
 /// it originated from this request, will be of the variant [`type@HttpClientResponse::Http`].
@@ -117,6 +248,8 @@ pub fn send_request(
                 version: None,
                 url: url.to_string(),
                 headers: headers.unwrap_or_default(),
+                proxy: default_proxy(),
+                chunked: None,
             }))
             .unwrap(),
         )
@@ -131,6 +264,8 @@ pub fn send_request(
 /// Make an HTTP request using http-client and await its response.
 ///
 /// Returns HTTP response from the `http` crate if successful, with the body type as bytes.
+///
+/// Prefer building the request with [`request()`], which is easier to read and extend.
 pub fn send_request_await_response(
     method: Method,
     url: url::Url,
@@ -138,45 +273,501 @@ pub fn send_request_await_response(
     timeout: u64,
     body: Vec<u8>,
 ) -> std::result::Result<http::Response<Vec<u8>>, HttpClientError> {
-    let res = KiRequest::to(("our", "http-client", "distro", "sys"))
-        .body(
-            serde_json::to_vec(&HttpClientAction::Http(OutgoingHttpRequest {
-                method: method.to_string(),
-                version: None,
-                url: url.to_string(),
-                headers: headers.unwrap_or_default(),
-            }))
-            .map_err(|_| HttpClientError::MalformedRequest)?,
-        )
-        .blob_bytes(body)
-        .send_and_await_response(timeout)
-        .unwrap();
-    let Ok(Message::Response { body, .. }) = res else {
-        return Err(HttpClientError::ExecuteRequestFailed(
-            "http-client timed out".to_string(),
-        ));
-    };
-    let resp = match serde_json::from_slice::<
-        std::result::Result<HttpClientResponse, HttpClientError>,
-    >(&body)
+    request(method, url)
+        .headers(headers.unwrap_or_default())
+        .timeout(timeout)
+        .body(body)
+        .send()
+}
+
+/// Make an HTTP request using http-client, await its response, and deserialize
+/// its JSON body: see [`OutgoingRequestBuilder::send_json()`].
+pub fn send_request_await_json<T>(
+    method: Method,
+    url: url::Url,
+    headers: Option<HashMap<String, String>>,
+    timeout: u64,
+    body: Vec<u8>,
+) -> std::result::Result<T, HttpClientError>
+where
+    T: DeserializeOwned,
+{
+    request(method, url)
+        .headers(headers.unwrap_or_default())
+        .timeout(timeout)
+        .body(body)
+        .send_json()
+}
+
+/// Start building an HTTP request to `url`, to send with http-client.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::http::{client, Method};
+///
+/// let response = client::request(Method::POST, "https://example.com/api/items")
+///     .bearer_auth("my-token")
+///     .json(&serde_json::json!({ "name": "milk" }))
+///     .timeout(30)
+///     .send()?;
+/// assert!(response.status().is_success());
+/// # Ok::<(), client::HttpClientError>(())
+/// ```
+pub fn request<T>(method: Method, url: T) -> OutgoingRequestBuilder
+where
+    T: AsRef<str>,
+{
+    OutgoingRequestBuilder {
+        method,
+        url: url.as_ref().to_string(),
+        query: vec![],
+        headers: HashMap::new(),
+        body: vec![],
+        timeout: DEFAULT_TIMEOUT,
+        retry: None,
+        max_redirects: None,
+        proxy: default_proxy(),
+        chunked: None,
+        error: None,
+    }
+}
+
+/// Timeout in seconds of requests built with [`request()`], unless set otherwise.
+const DEFAULT_TIMEOUT: u64 = 30;
+
+/// Builder for an HTTP request sent with http-client: see [`request()`].
+#[derive(Clone, Debug)]
+pub struct OutgoingRequestBuilder {
+    method: Method,
+    url: String,
+    query: Vec<(String, String)>,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+    timeout: u64,
+    retry: Option<RetryPolicy>,
+    max_redirects: Option<u32>,
+    proxy: Option<HttpProxy>,
+    chunked: Option<ChunkedBody>,
+    /// The first error met while building, returned by [`OutgoingRequestBuilder::send()`].
+    error: Option<HttpClientError>,
+}
+
+impl OutgoingRequestBuilder {
+    /// Set a header, replacing any previous value.
+    pub fn header<T, U>(mut self, key: T, value: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
     {
-        Ok(Ok(HttpClientResponse::Http(resp))) => resp,
-        Ok(Ok(HttpClientResponse::WebSocketAck)) => {
-            return Err(HttpClientError::ExecuteRequestFailed(
-                "http-client gave unexpected response".to_string(),
-            ))
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set several headers, replacing any previous values.
+    pub fn headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Set the `Authorization` header to a bearer token.
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("Authorization", format!("Bearer {}", token))
+    }
+
+    /// Append a query parameter to the URL.
+    pub fn query<T, U>(mut self, key: T, value: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.query.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set the request body to the given bytes.
+    pub fn body<T>(mut self, body: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        self.body = body.into();
+        self
+    }
+
+    /// Set the request body to the given value serialized to JSON, and set
+    /// the `Content-Type` header accordingly.
+    pub fn json<T>(mut self, body: &T) -> Self
+    where
+        T: Serialize,
+    {
+        match serde_json::to_vec(body) {
+            Ok(body) => self.header("Content-Type", "application/json").body(body),
+            Err(e) => {
+                self.error
+                    .get_or_insert(HttpClientError::BuildRequestFailed(e.to_string()));
+                self
+            }
         }
-        Ok(Err(e)) => return Err(e),
-        Err(e) => {
-            return Err(HttpClientError::ExecuteRequestFailed(format!(
-                "http-client gave invalid response: {e:?}"
-            )))
+    }
+
+    /// Set the request body to the given multipart/form-data body, and set
+    /// the `Content-Type` header accordingly.
+    pub fn multipart(self, form: MultipartBuilder) -> Self {
+        let (content_type, body) = form.build();
+        self.header("Content-Type", content_type).body(body)
+    }
+
+    /// Set how long to wait for the response, in seconds. Defaults to 30.
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry the request according to `policy` when it fails with a transient error.
+    /// The timeout applies to each attempt.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Follow `3xx` redirects given with a `Location` header, up to `max_redirects`
+    /// hops. Following more fails with [`HttpClientError::ExecuteRequestFailed`].
+    ///
+    /// `301`, `302` and `303` redirects are followed with a bodiless `GET`, as
+    /// browsers do, and `307` and `308` with the original method and body.
+    /// The `Authorization` and `Cookie` headers are dropped when redirected to
+    /// another origin. The URL finally requested is given by the [`FinalUrl`]
+    /// extension of the response.
+    pub fn follow_redirects(mut self, max_redirects: u32) -> Self {
+        self.max_redirects = Some(max_redirects);
+        self
+    }
+
+    /// Send the request through `proxy`, or directly if `None`, instead of through
+    /// the proxy set with [`set_default_proxy()`].
+    pub fn proxy(mut self, proxy: Option<HttpProxy>) -> Self {
+        if let Some(Err(e)) = proxy.as_ref().map(HttpProxy::validate) {
+            self.error.get_or_insert(e);
         }
-    };
+        self.proxy = proxy;
+        self
+    }
+
+    /// Send the request and await its response, retrying it if a
+    /// [`RetryPolicy`] is set. Once retries are exhausted, the last response
+    /// or error is returned.
+    ///
+    /// Returns HTTP response from the `http` crate if successful, with the body type as bytes.
+    /// Its [`FinalUrl`] extension holds the URL requested, after any redirects.
+    pub fn send(self) -> std::result::Result<http::Response<Vec<u8>>, HttpClientError> {
+        let (response, url) = self.execute()?;
+        let body = get_blob().unwrap_or_default().bytes;
+        Ok(into_http_response(response, body, Some(url)))
+    }
+
+    /// Send the request and await its response, but have http-client deliver the
+    /// body of the response in chunks of at most `chunk_size` bytes, each in a
+    /// request arriving in the message loop: pass them to
+    /// [`ChunkedDownload::handle()`]. This keeps memory use bounded for large
+    /// downloads, and allows reporting progress.
+    ///
+    /// The request is sent once: the chunks of a failed attempt or of a redirect
+    /// would be mixed into the download, so this fails with
+    /// [`HttpClientError::BuildRequestFailed`] if [`OutgoingRequestBuilder::retry()`]
+    /// or [`OutgoingRequestBuilder::follow_redirects()`] was set.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{await_message, http::{client::{self, DownloadEvent}, Method}};
+    ///
+    /// let mut download = client::request(Method::GET, "https://example.com/video.mp4")
+    ///     .send_chunked(1024 * 1024)?;
+    /// while !download.is_complete() {
+    ///     let Ok(message) = await_message() else { continue };
+    ///     match download.handle(&message) {
+    ///         Some(DownloadEvent::Chunk { index, total, bytes }) => {
+    ///             println!("chunk {} of {:?}: {} bytes", index + 1, total, bytes.len());
+    ///         }
+    ///         Some(DownloadEvent::Complete { chunks }) => println!("done in {} chunks", chunks),
+    ///         None => {}
+    ///     }
+    /// }
+    /// # Ok::<(), client::HttpClientError>(())
+    /// ```
+    pub fn send_chunked(
+        mut self,
+        chunk_size: u64,
+    ) -> std::result::Result<ChunkedDownload, HttpClientError> {
+        if chunk_size == 0 {
+            return Err(HttpClientError::BuildRequestFailed(
+                "chunk size must be positive".to_string(),
+            ));
+        }
+        if self.retry.is_some() || self.max_redirects.is_some() {
+            return Err(HttpClientError::BuildRequestFailed(
+                "retries and redirects are not supported for chunked downloads".to_string(),
+            ));
+        }
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let download_id = rand::random();
+        self.chunked = Some(ChunkedBody {
+            download_id,
+            chunk_size,
+        });
+        let url = self.full_url()?;
+        let response = self.execute_once(&url)?;
+        Ok(ChunkedDownload {
+            download_id,
+            response: into_http_response(response, vec![], Some(url)).map(|_| ()),
+            chunks_received: 0,
+            bytes_received: 0,
+            total: None,
+            complete: false,
+        })
+    }
+
+    /// Send the request without waiting for its response, which arrives in the
+    /// message loop carrying `context`: match it back with [`parse_async_response()`].
+    /// If the request times out, a [`crate::SendError`] carrying `context` arrives
+    /// instead: see [`parse_async_timeout()`].
+    ///
+    /// Retries and redirects are not applied to requests sent this way.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{await_message, http::{client, Method}};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct FetchPrice {
+    ///     symbol: String,
+    /// }
+    ///
+    /// client::request(Method::GET, "https://example.com/price/ETH")
+    ///     .send_with_context(&FetchPrice { symbol: "ETH".to_string() })?;
+    /// loop {
+    ///     match await_message() {
+    ///         Ok(message) if message.is_process("http-client:distro:sys") => {
+    ///             let (fetch, response) = client::parse_async_response::<FetchPrice>(&message)?;
+    ///             println!("{}: {:?}", fetch.symbol, response.body());
+    ///         }
+    ///         Err(error) => {
+    ///             if let Some(fetch) = client::parse_async_timeout::<FetchPrice>(&error) {
+    ///                 println!("{}: timed out", fetch.symbol);
+    ///             }
+    ///         }
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok::<(), client::HttpClientError>(())
+    /// ```
+    pub fn send_with_context<C>(mut self, context: &C) -> std::result::Result<(), HttpClientError>
+    where
+        C: Serialize,
+    {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let context = serde_json::to_vec(context)
+            .map_err(|e| HttpClientError::BuildRequestFailed(e.to_string()))?;
+        let url = self.full_url()?;
+        KiRequest::to(("our", "http-client", "distro", "sys"))
+            .body(self.action(&url)?)
+            .blob_bytes(self.body)
+            .expects_response(self.timeout)
+            .context(context)
+            .send()
+            .unwrap();
+        Ok(())
+    }
+
+    /// Send the request, as [`OutgoingRequestBuilder::send()`] does, and deserialize
+    /// the JSON body of the response.
+    ///
+    /// Fails with [`HttpClientError::BadStatus`] if the status is not `2xx`, with
+    /// [`HttpClientError::NotJson`] if the response has a `Content-Type` other than
+    /// JSON, and with [`HttpClientError::BadJson`] if the body does not deserialize.
+    /// These errors carry the start of the body, e.g. an error message from the API.
+    pub fn send_json<T>(self) -> std::result::Result<T, HttpClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.send()?;
+        let body = response.body();
+        if !response.status().is_success() {
+            return Err(HttpClientError::BadStatus {
+                status: response.status().as_u16(),
+                body: body_snippet(body),
+            });
+        }
+        if let Some(content_type) = response.headers().get(http::header::CONTENT_TYPE) {
+            let content_type = content_type.to_str().unwrap_or_default();
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            if !mime.eq_ignore_ascii_case("application/json") && !mime.ends_with("+json") {
+                return Err(HttpClientError::NotJson {
+                    content_type: content_type.to_string(),
+                    body: body_snippet(body),
+                });
+            }
+        }
+        serde_json::from_slice(body).map_err(|e| HttpClientError::BadJson {
+            error: e.to_string(),
+            body: body_snippet(body),
+        })
+    }
+
+    /// Send the request, with retries and redirects, without loading the body of
+    /// the response into the process: it is left in the blob of the last message.
+    /// Returns the response and the URL finally requested.
+    fn execute(mut self) -> std::result::Result<(HttpResponse, url::Url), HttpClientError> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let mut url = self.full_url()?;
+        let mut redirects = 0;
+        loop {
+            let response = self.execute_with_retries(&url)?;
+            let Some(max_redirects) = self.max_redirects else {
+                return Ok((response, url));
+            };
+            let location = response
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("location"))
+                .map(|(_, value)| value.trim());
+            let Some(location) = location.filter(|_| (300..400).contains(&response.status)) else {
+                return Ok((response, url));
+            };
+            if redirects >= max_redirects {
+                return Err(HttpClientError::ExecuteRequestFailed(format!(
+                    "more than {} redirects",
+                    max_redirects
+                )));
+            }
+            let Ok(next) = url.join(location) else {
+                return Err(HttpClientError::BadUrl {
+                    url: location.to_string(),
+                });
+            };
+            if matches!(response.status, 301..=303) && self.method != Method::HEAD {
+                self.method = Method::GET;
+                self.body.clear();
+                self.headers
+                    .retain(|key, _| !key.eq_ignore_ascii_case("content-type"));
+            }
+            if next.origin() != url.origin() {
+                self.headers.retain(|key, _| {
+                    !key.eq_ignore_ascii_case("authorization")
+                        && !key.eq_ignore_ascii_case("cookie")
+                });
+            }
+            url = next;
+            redirects += 1;
+        }
+    }
+
+    fn execute_with_retries(
+        &self,
+        url: &url::Url,
+    ) -> std::result::Result<HttpResponse, HttpClientError> {
+        let Some(policy) = self.retry.clone() else {
+            return self.execute_once(url);
+        };
+        let mut attempt = 1;
+        loop {
+            let result = self.execute_once(url);
+            if attempt >= policy.max_attempts {
+                return result;
+            }
+            let delay = match &result {
+                Ok(response) if policy.retry_on.contains(&response.status) => {
+                    let retry_after = response
+                        .headers
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case("retry-after"))
+                        .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    policy
+                        .backoff_delay(attempt)
+                        .max(retry_after.unwrap_or_default())
+                        .min(policy.max_backoff)
+                }
+                Err(HttpClientError::ExecuteRequestFailed(_)) => policy.backoff_delay(attempt),
+                _ => return result,
+            };
+            crate::timer::set_and_await_timer(delay.as_millis() as u64).ok();
+            attempt += 1;
+        }
+    }
+
+    fn execute_once(&self, url: &url::Url) -> std::result::Result<HttpResponse, HttpClientError> {
+        let res = KiRequest::to(("our", "http-client", "distro", "sys"))
+            .body(self.action(url)?)
+            .blob_bytes(self.body.clone())
+            .send_and_await_response(self.timeout)
+            .unwrap();
+        let Ok(Message::Response { body, .. }) = res else {
+            return Err(HttpClientError::ExecuteRequestFailed(
+                "http-client timed out".to_string(),
+            ));
+        };
+        parse_http_response(&body)
+    }
+
+    /// The URL to request, with the query parameters added.
+    fn full_url(&self) -> std::result::Result<url::Url, HttpClientError> {
+        let Ok(mut url) = url::Url::parse(&self.url) else {
+            return Err(HttpClientError::BadUrl {
+                url: self.url.clone(),
+            });
+        };
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        Ok(url)
+    }
+
+    /// The body of the request to http-client for this request to `url`.
+    fn action(&self, url: &url::Url) -> std::result::Result<Vec<u8>, HttpClientError> {
+        serde_json::to_vec(&HttpClientAction::Http(OutgoingHttpRequest {
+            method: self.method.to_string(),
+            version: None,
+            url: url.to_string(),
+            headers: self.headers.clone(),
+            proxy: self.proxy.clone(),
+            chunked: self.chunked,
+        }))
+        .map_err(|_| HttpClientError::MalformedRequest)
+    }
+}
+
+/// Parse the body of a response from http-client to an HTTP request.
+fn parse_http_response(body: &[u8]) -> std::result::Result<HttpResponse, HttpClientError> {
+    match serde_json::from_slice::<std::result::Result<HttpClientResponse, HttpClientError>>(body) {
+        Ok(Ok(HttpClientResponse::Http(resp))) => Ok(resp),
+        Ok(Ok(HttpClientResponse::WebSocketAck)) => Err(HttpClientError::ExecuteRequestFailed(
+            "http-client gave unexpected response".to_string(),
+        )),
+        Ok(Err(e)) => Err(e),
+        Err(e) => Err(HttpClientError::ExecuteRequestFailed(format!(
+            "http-client gave invalid response: {e:?}"
+        ))),
+    }
+}
+
+fn into_http_response(
+    response: HttpResponse,
+    body: Vec<u8>,
+    url: Option<url::Url>,
+) -> http::Response<Vec<u8>> {
     let mut http_response = http::Response::builder()
-        .status(http::StatusCode::from_u16(resp.status).unwrap_or_default());
+        .status(http::StatusCode::from_u16(response.status).unwrap_or_default());
+    if let Some(url) = url {
+        http_response = http_response.extension(FinalUrl(url));
+    }
     let headers = http_response.headers_mut().unwrap();
-    for (key, value) in &resp.headers {
+    for (key, value) in &response.headers {
         let Ok(key) = http::header::HeaderName::from_str(key) else {
             continue;
         };
@@ -185,9 +776,378 @@ pub fn send_request_await_response(
         };
         headers.insert(key, value);
     }
-    Ok(http_response
-        .body(get_blob().unwrap_or_default().bytes)
-        .unwrap())
+    http_response.body(body).unwrap()
+}
+
+/// Parse a response to a request sent with [`OutgoingRequestBuilder::send_with_context()`],
+/// returning the context it was sent with and the HTTP response.
+///
+/// Fails with [`HttpClientError::BadContext`] if the message is not a response
+/// from http-client with a context of type `C`.
+pub fn parse_async_response<C>(
+    message: &Message,
+) -> std::result::Result<(C, http::Response<Vec<u8>>), HttpClientError>
+where
+    C: DeserializeOwned,
+{
+    if message.is_request() || !message.is_process("http-client:distro:sys") {
+        return Err(HttpClientError::BadContext(
+            "not a response from http-client".to_string(),
+        ));
+    }
+    let context = serde_json::from_slice::<C>(message.context().unwrap_or_default())
+        .map_err(|e| HttpClientError::BadContext(e.to_string()))?;
+    let response = parse_http_response(message.body())?;
+    let body = message.blob().unwrap_or_default().bytes;
+    Ok((context, into_http_response(response, body, None)))
+}
+
+/// The context of a request sent with [`OutgoingRequestBuilder::send_with_context()`]
+/// that timed out, if `error` is for such a request with a context of type `C`.
+pub fn parse_async_timeout<C>(error: &SendError) -> Option<C>
+where
+    C: DeserializeOwned,
+{
+    if error.target().process.to_string() != "http-client:distro:sys" {
+        return None;
+    }
+    serde_json::from_slice(error.context()?).ok()
+}
+
+/// A download started with [`OutgoingRequestBuilder::send_chunked()`], whose
+/// body arrives in chunks.
+#[derive(Debug)]
+pub struct ChunkedDownload {
+    download_id: u64,
+    response: http::Response<()>,
+    chunks_received: u64,
+    bytes_received: u64,
+    total: Option<u64>,
+    complete: bool,
+}
+
+/// A chunk of a [`ChunkedDownload`], or its end.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DownloadEvent {
+    Chunk {
+        index: u64,
+        total: Option<u64>,
+        bytes: Vec<u8>,
+    },
+    Complete {
+        chunks: u64,
+    },
+}
+
+impl ChunkedDownload {
+    pub fn download_id(&self) -> u64 {
+        self.download_id
+    }
+
+    /// The status and headers of the response. Its [`FinalUrl`] extension holds
+    /// the URL requested, after any redirects.
+    pub fn response(&self) -> &http::Response<()> {
+        &self.response
+    }
+
+    pub fn chunks_received(&self) -> u64 {
+        self.chunks_received
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// The number of chunks in the body, if its length is known.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Handle a message from the message loop, returning the chunk it carries if
+    /// it is a chunk of this download. Other messages are ignored, returning `None`.
+    ///
+    /// Must be called before receiving another message, as the chunk is in the blob.
+    pub fn handle(&mut self, message: &Message) -> Option<DownloadEvent> {
+        if !message.is_request()
+            || !message.is_local()
+            || !message.is_process("http-client:distro:sys")
+        {
+            return None;
+        }
+        match serde_json::from_slice(message.body()).ok()? {
+            HttpClientRequest::DownloadChunk {
+                download_id,
+                index,
+                total,
+            } if download_id == self.download_id => {
+                let bytes = message.blob().unwrap_or_default().bytes;
+                self.chunks_received += 1;
+                self.bytes_received += bytes.len() as u64;
+                self.total = total;
+                Some(DownloadEvent::Chunk {
+                    index,
+                    total,
+                    bytes,
+                })
+            }
+            HttpClientRequest::DownloadComplete {
+                download_id,
+                chunks,
+            } if download_id == self.download_id => {
+                self.complete = true;
+                Some(DownloadEvent::Complete { chunks })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The URL finally requested by [`OutgoingRequestBuilder::send()`], after any
+/// redirects, found in the [`http::Extensions`] of the response.
+///
+/// ```no_run
+/// use kinode_process_lib::http::{client::{self, FinalUrl}, Method};
+///
+/// let response = client::request(Method::GET, "https://example.com/latest")
+///     .follow_redirects(5)
+///     .send()?;
+/// let FinalUrl(url) = response.extensions().get::<FinalUrl>().unwrap();
+/// # Ok::<(), client::HttpClientError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinalUrl(pub url::Url);
+
+/// Options for [`download_to_file()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DownloadOptions {
+    pub headers: HashMap<String, String>,
+    /// How long to wait for the remote server, in seconds.
+    pub timeout: u64,
+    pub retry: Option<RetryPolicy>,
+    /// The most redirects followed, as by [`OutgoingRequestBuilder::follow_redirects()`].
+    pub max_redirects: Option<u32>,
+    /// How long to wait for the VFS to write the file and report on it, in seconds.
+    pub vfs_timeout: u64,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions {
+            headers: HashMap::new(),
+            timeout: 300,
+            retry: None,
+            max_redirects: Some(10),
+            vfs_timeout: 60,
+        }
+    }
+}
+
+impl DownloadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn header<T, U>(mut self, key: T, value: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+    /// Set the most redirects followed, or `None` not to follow redirects. Defaults to 10.
+    pub fn max_redirects(mut self, max_redirects: Option<u32>) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+    pub fn vfs_timeout(mut self, vfs_timeout: u64) -> Self {
+        self.vfs_timeout = vfs_timeout;
+        self
+    }
+}
+
+/// A file written by [`download_to_file()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Download {
+    /// The length of the file in bytes.
+    pub len: u64,
+    /// The hash of the file, as computed by the VFS.
+    pub hash: [u8; 32],
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("http-client error: {0}")]
+    Http(#[from] HttpClientError),
+    #[error("remote server responded with status {0}")]
+    Status(u16),
+    #[error("vfs error: {0}")]
+    Vfs(#[from] VfsError),
+}
+
+/// Fetch `url` with a `GET` request and write the response body to the file at
+/// `vfs_path`, replacing any existing file. Returns the length and hash of the file.
+///
+/// The body is passed from http-client to the VFS by inheriting the blob of the
+/// response, so it is never copied into the memory of the process. Responses
+/// without a `2xx` status are not written.
+///
+/// The process must have write capability for the drive of `vfs_path`.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::http::client::{download_to_file, DownloadOptions};
+///
+/// let download = download_to_file(
+///     "https://example.com/model.bin",
+///     "/my-app:template.os/models/model.bin",
+///     DownloadOptions::new().timeout(600),
+/// )?;
+/// println!("downloaded {} bytes", download.len);
+/// # Ok::<(), kinode_process_lib::http::client::DownloadError>(())
+/// ```
+pub fn download_to_file(
+    url: &str,
+    vfs_path: &str,
+    options: DownloadOptions,
+) -> std::result::Result<Download, DownloadError> {
+    let mut builder = request(Method::GET, url)
+        .headers(options.headers)
+        .timeout(options.timeout);
+    if let Some(policy) = options.retry {
+        builder = builder.retry(policy);
+    }
+    if let Some(max_redirects) = options.max_redirects {
+        builder = builder.follow_redirects(max_redirects);
+    }
+    let (response, _) = builder.execute()?;
+    if !(200..300).contains(&response.status) {
+        return Err(DownloadError::Status(response.status));
+    }
+    let message = vfs_request(vfs_path, VfsAction::Write)
+        .inherit(true)
+        .send_and_await_response(options.vfs_timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+    match parse_response(message.body())? {
+        VfsResponse::Ok => {}
+        VfsResponse::Err(e) => return Err(e.into()),
+        _ => return Err(unexpected_response(vfs_path).into()),
+    }
+    let len = metadata(vfs_path, Some(options.vfs_timeout))?.len;
+    let message = vfs_request(vfs_path, VfsAction::Hash)
+        .send_and_await_response(options.vfs_timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+    match parse_response(message.body())? {
+        VfsResponse::Hash(hash) => Ok(Download { len, hash }),
+        VfsResponse::Err(e) => Err(e.into()),
+        _ => Err(unexpected_response(vfs_path).into()),
+    }
+}
+
+fn unexpected_response(path: &str) -> VfsError {
+    VfsError::ParseError {
+        error: "unexpected response".to_string(),
+        path: path.to_string(),
+    }
+}
+
+/// When and how often to retry an HTTP request sent with
+/// [`OutgoingRequestBuilder::send()`]: see [`OutgoingRequestBuilder::retry()`].
+///
+/// Requests are retried when http-client fails to execute them, e.g. on a
+/// timeout or connection error, and when the response status is in `retry_on`.
+/// The delay before each retry grows exponentially from `initial_backoff` up to
+/// `max_backoff`, and honors a `Retry-After` header given in seconds.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::http::{client::{self, RetryPolicy}, Method};
+/// use std::time::Duration;
+///
+/// let response = client::request(Method::GET, "https://example.com/api")
+///     .retry(RetryPolicy::new(5).backoff(Duration::from_secs(1), Duration::from_secs(20)))
+///     .send();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The most times the request is sent, including the first.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// The factor by which the delay grows after each retry.
+    pub multiplier: f64,
+    /// Whether each delay is randomized between half and all of its value,
+    /// so that many clients do not retry in lockstep.
+    pub jitter: bool,
+    /// Response statuses on which the request is retried.
+    pub retry_on: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, with delays from 500ms doubling up to 30s, with jitter,
+    /// retrying on 429, 502, 503 and 504.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+            retry_on: vec![429, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The default policy, with the given maximum number of attempts.
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+    pub fn backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    pub fn retry_on(mut self, retry_on: Vec<u16>) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// The delay before the retry following the given attempt, counting from 1.
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let delay = Duration::from_secs_f64(secs.min(self.max_backoff.as_secs_f64()).max(0.0));
+        if self.jitter {
+            delay.mul_f64(rand::Rng::gen_range(&mut rand::thread_rng(), 0.5..=1.0))
+        } else {
+            delay
+        }
+    }
 }
 
 pub fn open_ws_connection(
@@ -251,3 +1211,221 @@ pub fn close_ws_connection(channel_id: u32) -> std::result::Result<(), HttpClien
         _ => Err(HttpClientError::WsCloseFailed { channel_id }),
     }
 }
+
+/// A response stored by a [`ResponseCache`], with the validators to revalidate it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Storage for a [`ResponseCache`], keyed by method and URL. Implemented for
+/// [`crate::kv::Kv`] and [`VfsCacheStore`].
+pub trait CacheStore {
+    /// The response stored under `key`, if any. Failures to read count as misses.
+    fn load(&self, key: &str) -> Option<CachedResponse>;
+    /// Store `response` under `key`. Failures to write are ignored.
+    fn store(&self, key: &str, response: &CachedResponse);
+}
+
+impl CacheStore for crate::kv::Kv<String, CachedResponse> {
+    fn load(&self, key: &str) -> Option<CachedResponse> {
+        self.get(&key.to_string()).ok()
+    }
+
+    fn store(&self, key: &str, response: &CachedResponse) {
+        let _ = self.set(&key.to_string(), response, None);
+    }
+}
+
+/// A [`CacheStore`] keeping each response in a file in a VFS directory,
+/// named by the hash of its key.
+#[derive(Clone, Debug)]
+pub struct VfsCacheStore {
+    /// The directory, which must exist, e.g. `/my-app:template.os/http-cache`.
+    pub dir: String,
+    pub timeout: u64,
+}
+
+impl VfsCacheStore {
+    pub fn new<T>(dir: T, timeout: u64) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            dir: dir.into(),
+            timeout,
+        }
+    }
+
+    fn file(&self, key: &str) -> crate::vfs::File {
+        use sha2::{Digest, Sha256};
+        let name = hex::encode(Sha256::digest(key.as_bytes()));
+        crate::vfs::File::new(
+            format!("{}/{}", self.dir.trim_end_matches('/'), name),
+            self.timeout,
+        )
+    }
+}
+
+impl CacheStore for VfsCacheStore {
+    fn load(&self, key: &str) -> Option<CachedResponse> {
+        serde_json::from_slice(&self.file(key).read().ok()?).ok()
+    }
+
+    fn store(&self, key: &str, response: &CachedResponse) {
+        if let Ok(bytes) = serde_json::to_vec(response) {
+            let _ = self.file(key).write(&bytes);
+        }
+    }
+}
+
+/// Marks a response returned by [`ResponseCache::send()`] from the cache, after
+/// the server answered `304 Not Modified`. Found in the [`http::Extensions`]
+/// of the response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FromCache;
+
+/// An opt-in cache of `GET` responses, revalidated with the server on every
+/// request using conditional requests: a response with an `ETag` or
+/// `Last-Modified` header is stored, and later requests for the same URL send
+/// `If-None-Match` and `If-Modified-Since`. When the server answers
+/// `304 Not Modified`, the stored response is returned instead of downloading
+/// it again.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{our, kv, http::{client::{self, CachedResponse, ResponseCache}, Method}};
+///
+/// let store = kv::open::<String, CachedResponse>(our().package_id(), "http-cache", None)?;
+/// let cache = ResponseCache::new(store);
+/// let response = cache.send(client::request(Method::GET, "https://example.com/blocks/latest"))?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResponseCache<S> {
+    store: S,
+}
+
+impl<S> ResponseCache<S>
+where
+    S: CacheStore,
+{
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Send `request`, as [`OutgoingRequestBuilder::send()`] does, revalidating
+    /// any stored response. Requests with a method other than `GET` are sent
+    /// without using the cache.
+    pub fn send(
+        &self,
+        mut request: OutgoingRequestBuilder,
+    ) -> std::result::Result<http::Response<Vec<u8>>, HttpClientError> {
+        if request.method != Method::GET {
+            return request.send();
+        }
+        let key = format!("{} {}", request.method, request.full_url()?);
+        let cached = self.store.load(&key);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header("If-Modified-Since", last_modified);
+            }
+        }
+        let (response, url) = request.execute()?;
+        if response.status == 304 {
+            if let Some(cached) = cached {
+                let mut response = HttpResponse::new(cached.status);
+                response.headers = cached.headers;
+                let mut response = into_http_response(response, cached.body, Some(url));
+                response.extensions_mut().insert(FromCache);
+                return Ok(response);
+            }
+        }
+        let body = get_blob().unwrap_or_default().bytes;
+        if response.status == 200 {
+            let header = |name: &str| {
+                response
+                    .headers
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.clone())
+            };
+            let etag = header("etag");
+            let last_modified = header("last-modified");
+            let no_store = header("cache-control")
+                .is_some_and(|value| value.to_ascii_lowercase().contains("no-store"));
+            if (etag.is_some() || last_modified.is_some()) && !no_store {
+                self.store.store(
+                    &key,
+                    &CachedResponse {
+                        status: response.status,
+                        headers: response.headers.clone(),
+                        body: body.clone(),
+                        etag,
+                        last_modified,
+                    },
+                );
+            }
+        }
+        Ok(into_http_response(response, body, Some(url)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        let policy = RetryPolicy::new(5)
+            .backoff(Duration::from_secs(1), Duration::from_secs(5))
+            .jitter(false);
+        let delays: Vec<u64> = (1..=4)
+            .map(|attempt| policy.backoff_delay(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 5]);
+        let delay = policy.jitter(true).backoff_delay(2);
+        assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_body_snippet() {
+        assert_eq!(body_snippet(b"short"), "short");
+        let snippet = body_snippet(&[b'a'; BODY_SNIPPET_LEN + 1]);
+        assert_eq!(snippet.len(), BODY_SNIPPET_LEN + 3);
+        assert!(snippet.ends_with("a..."));
+    }
+
+    #[test]
+    fn test_proxy() {
+        assert!(HttpProxy::new("socks5://127.0.0.1:1080").validate().is_ok());
+        assert!(HttpProxy::new("ftp://127.0.0.1").validate().is_err());
+        assert!(HttpProxy::new("not a url").validate().is_err());
+
+        let mut request = OutgoingHttpRequest {
+            method: "GET".to_string(),
+            version: None,
+            url: "https://example.com".to_string(),
+            headers: HashMap::new(),
+            proxy: None,
+            chunked: None,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(!json.contains("proxy"));
+        request.proxy = Some(HttpProxy::new("http://proxy:8080").basic_auth("user", "pass"));
+        let parsed: OutgoingHttpRequest =
+            serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+        assert_eq!(parsed.proxy, request.proxy);
+    }
+}
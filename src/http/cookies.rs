@@ -0,0 +1,287 @@
+use crate::http::server::{HttpResponse, HttpResponseWithBody, IncomingHttpRequest};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+//
+// Parsing of the Cookie header and building of Set-Cookie headers
+//
+
+/// Parse the value of a `Cookie` header, e.g. `session=abc; theme=dark`, into a map
+/// from cookie names to values. Values wrapped in double quotes are unquoted.
+/// If a name appears more than once, the first value is kept.
+pub fn parse_cookies(header: &str) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+    for pair in header.split(';') {
+        let Some((name, value)) = pair.split_once('=') else {
+            continue;
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        cookies
+            .entry(name.to_string())
+            .or_insert_with(|| value.to_string());
+    }
+    cookies
+}
+
+impl IncomingHttpRequest {
+    /// The cookies sent with this request, parsed from its `Cookie` header(s).
+    pub fn cookies(&self) -> HashMap<String, String> {
+        let mut cookies = HashMap::new();
+        for header in self.headers().get_all(http::header::COOKIE) {
+            let Ok(header) = header.to_str() else {
+                continue;
+            };
+            for (name, value) in parse_cookies(header) {
+                cookies.entry(name).or_insert(value);
+            }
+        }
+        cookies
+    }
+
+    /// The value of a single cookie sent with this request.
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.cookies().remove(name)
+    }
+}
+
+/// The `SameSite` attribute of a cookie.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Requires the cookie to also be `Secure`.
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Error of [`CookieBuilder::new()`], for a cookie that can't be written to a
+/// `Set-Cookie` header as given.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum CookieError {
+    #[error("invalid cookie name {0:?}: must be a non-empty HTTP token")]
+    InvalidName(String),
+    #[error("invalid value for cookie {0}: must not contain whitespace, control characters, '\"', ',', ';' or '\\'")]
+    InvalidValue(String),
+}
+
+/// Builder for the value of a `Set-Cookie` header.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::http::cookies::{CookieBuilder, SameSite};
+/// use std::time::Duration;
+///
+/// let cookie = CookieBuilder::new("session", "abc")?
+///     .path("/")
+///     .max_age(Duration::from_secs(3600))
+///     .same_site(SameSite::Strict)
+///     .http_only(true);
+/// assert_eq!(
+///     cookie.to_string(),
+///     "session=abc; Max-Age=3600; Path=/; SameSite=Strict; HttpOnly"
+/// );
+/// # Ok::<(), kinode_process_lib::http::cookies::CookieError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CookieBuilder {
+    name: String,
+    value: String,
+    max_age: Option<Duration>,
+    path: Option<String>,
+    domain: Option<String>,
+    same_site: Option<SameSite>,
+    secure: bool,
+    http_only: bool,
+}
+
+impl CookieBuilder {
+    /// A cookie named `name`, which must be an HTTP token, holding `value`, which
+    /// must only contain the characters allowed unquoted in a cookie value:
+    /// printable ASCII other than whitespace, `"`, `,`, `;` and `\`. Encode other
+    /// values, e.g. as base64url or hex, first.
+    pub fn new<T, U>(name: T, value: U) -> Result<Self, CookieError>
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        let name = name.into();
+        let value = value.into();
+        if name.is_empty() || !name.bytes().all(is_token_byte) {
+            return Err(CookieError::InvalidName(name));
+        }
+        if !value.bytes().all(is_cookie_octet) {
+            return Err(CookieError::InvalidValue(name));
+        }
+        Ok(Self {
+            name,
+            value,
+            max_age: None,
+            path: None,
+            domain: None,
+            same_site: None,
+            secure: false,
+            http_only: false,
+        })
+    }
+
+    /// A cookie that removes the cookie `name` from the client, by expiring it immediately.
+    /// The path and domain must match those the cookie was set with.
+    pub fn removal<T>(name: T) -> Result<Self, CookieError>
+    where
+        T: Into<String>,
+    {
+        Ok(Self::new(name, "")?.max_age(Duration::ZERO))
+    }
+
+    /// Set how long the client keeps the cookie. Without this, it is a session cookie.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn path<T>(mut self, path: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn domain<T>(mut self, domain: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Set whether the cookie is only sent over HTTPS.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Set whether the cookie is hidden from JavaScript.
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+}
+
+/// Whether `byte` may appear in an HTTP token (RFC 7230), e.g. a cookie name.
+fn is_token_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+/// Whether `byte` may appear in an unquoted cookie value (RFC 6265).
+fn is_cookie_octet(byte: u8) -> bool {
+    matches!(byte, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e)
+}
+
+impl fmt::Display for CookieBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        Ok(())
+    }
+}
+
+impl HttpResponse {
+    /// Set a cookie on the client with a `Set-Cookie` header.
+    ///
+    /// Response headers are a map, so a response can set only one cookie:
+    /// setting another replaces the first.
+    pub fn set_cookie(self, cookie: &CookieBuilder) -> Self {
+        self.header("Set-Cookie", cookie.to_string())
+    }
+}
+
+impl HttpResponseWithBody {
+    /// Set a cookie on the client with a `Set-Cookie` header,
+    /// as [`HttpResponse::set_cookie()`].
+    pub fn set_cookie(self, cookie: &CookieBuilder) -> Self {
+        self.header("Set-Cookie", cookie.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cookies() {
+        let cookies = parse_cookies("session=abc; theme=\"dark\"; flag; session=def;=x");
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies["session"], "abc");
+        assert_eq!(cookies["theme"], "dark");
+    }
+
+    #[test]
+    fn test_removal() {
+        let cookie = CookieBuilder::removal("session")
+            .unwrap()
+            .path("/")
+            .secure(true);
+        assert_eq!(cookie.to_string(), "session=; Max-Age=0; Path=/; Secure");
+    }
+
+    #[test]
+    fn test_new_validates() {
+        assert!(CookieBuilder::new("session", "a.b-c_d%3D").is_ok());
+        assert_eq!(
+            CookieBuilder::new("", "abc"),
+            Err(CookieError::InvalidName("".to_string()))
+        );
+        assert_eq!(
+            CookieBuilder::new("a;b", "abc"),
+            Err(CookieError::InvalidName("a;b".to_string()))
+        );
+        for value in ["a;b", "a,b", "a b", "a\nb", "\"abc\""] {
+            assert_eq!(
+                CookieBuilder::new("session", value),
+                Err(CookieError::InvalidValue("session".to_string()))
+            );
+        }
+    }
+}
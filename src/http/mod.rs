@@ -1,3 +1,9 @@
+pub mod api_keys;
 pub mod client;
+pub mod cookies;
+pub mod multipart;
+pub mod query;
 pub mod server;
+pub mod sessions;
 pub use http::{HeaderMap, HeaderName, HeaderValue, Method, Response, StatusCode};
+pub use query::{decode_query, encode_query, from_query, to_query, url_with_query};
@@ -0,0 +1,460 @@
+use crate::http::server::IncomingHttpRequest;
+use crate::vfs::{create_file, open_file, File, VfsError};
+use crate::LazyLoadBlob;
+use rand::Rng;
+use std::collections::HashMap;
+use thiserror::Error;
+
+//
+// Parsing of multipart/form-data request bodies
+//
+
+/// Size of the chunks in which [`Part::write_to_vfs()`] writes a part to a file.
+const VFS_CHUNK_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum MultipartError {
+    #[error("expected Content-Type multipart/form-data with a boundary, got {0:?}")]
+    MissingBoundary(Option<String>),
+    #[error("malformed multipart body: {0}")]
+    Malformed(&'static str),
+    #[error("vfs error: {0}")]
+    Vfs(#[from] VfsError),
+}
+
+/// Get the boundary from a `Content-Type` header value such as
+/// `multipart/form-data; boundary=----abc`.
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    if !params
+        .next()?
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+    params.find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("boundary") {
+            return None;
+        }
+        let value = value.trim().trim_matches('"');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// One part of a multipart body, borrowing its data from the body.
+#[derive(Clone, Debug)]
+pub struct Part<'a> {
+    /// The headers of the part, with lowercased names.
+    pub headers: HashMap<String, String>,
+    /// The form field name, from the `Content-Disposition` header.
+    pub name: Option<String>,
+    /// The name of the uploaded file, if this part is a file.
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// The data of the part as a string, if it is valid UTF-8.
+    pub fn text(&self) -> Option<&'a str> {
+        std::str::from_utf8(self.data).ok()
+    }
+
+    /// Write the data of the part to a new file in the VFS at `path`,
+    /// in chunks, so that large uploads are not sent to the VFS in one message.
+    ///
+    /// The process must have write capability for the drive of `path`.
+    pub fn write_to_vfs(&self, path: &str, timeout: Option<u64>) -> Result<File, VfsError> {
+        let mut file = create_file(path, timeout)?;
+        for chunk in self.data.chunks(VFS_CHUNK_SIZE) {
+            file.append(chunk)?;
+        }
+        Ok(file)
+    }
+}
+
+/// An iterator over the parts of a multipart body.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{get_blob, http::{multipart::Multipart, server::IncomingHttpRequest}};
+///
+/// fn handle_upload(request: &IncomingHttpRequest) -> anyhow::Result<()> {
+///     let blob = get_blob().unwrap_or_default();
+///     for part in Multipart::from_request(request, &blob)? {
+///         let part = part?;
+///         if let Some(filename) = &part.filename {
+///             part.write_to_vfs(&format!("/my-app:template.os/uploads/{}", filename), None)?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Multipart<'a> {
+    body: &'a [u8],
+    /// `--` followed by the boundary.
+    delimiter: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+impl<'a> Multipart<'a> {
+    /// Parse `body` as a multipart body with the given boundary.
+    pub fn new(body: &'a [u8], boundary: &str) -> Self {
+        let delimiter = format!("--{}", boundary).into_bytes();
+        // skip the preamble, up to and including the first delimiter
+        let (position, done) = match find(body, &delimiter, 0) {
+            Some(index) => (index + delimiter.len(), false),
+            None => (body.len(), true),
+        };
+        Self {
+            body,
+            delimiter,
+            position,
+            done,
+        }
+    }
+
+    /// Parse the body of an HTTP request, held in `blob`, using the boundary
+    /// given in its `Content-Type` header.
+    pub fn from_request(
+        request: &IncomingHttpRequest,
+        blob: &'a LazyLoadBlob,
+    ) -> Result<Self, MultipartError> {
+        let content_type = request
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let Some(boundary) = content_type.as_deref().and_then(boundary) else {
+            return Err(MultipartError::MissingBoundary(content_type));
+        };
+        Ok(Self::new(&blob.bytes, &boundary))
+    }
+
+    fn next_part(&mut self) -> Result<Option<Part<'a>>, MultipartError> {
+        let rest = &self.body[self.position..];
+        if rest.starts_with(b"--") {
+            return Ok(None);
+        }
+        // lines end with CRLF, as required by RFC 7578
+        if !rest.starts_with(b"\r\n") {
+            return Err(MultipartError::Malformed("expected CRLF after boundary"));
+        }
+        let headers_start = self.position + 2;
+        // a part without headers has the blank line right after the boundary
+        let (headers_end, data_start) = if rest[2..].starts_with(b"\r\n") {
+            (headers_start, headers_start + 2)
+        } else {
+            let Some(headers_end) = find(self.body, b"\r\n\r\n", headers_start) else {
+                return Err(MultipartError::Malformed("unterminated part headers"));
+            };
+            (headers_end, headers_end + 4)
+        };
+        let headers = parse_headers(&self.body[headers_start..headers_end])?;
+        let mut closing = b"\r\n".to_vec();
+        closing.extend_from_slice(&self.delimiter);
+        let Some(data_end) = find(self.body, &closing, data_start) else {
+            return Err(MultipartError::Malformed("missing closing boundary"));
+        };
+        self.position = data_end + closing.len();
+
+        let disposition = headers
+            .get("content-disposition")
+            .map(|value| disposition_params(value))
+            .unwrap_or_default();
+        Ok(Some(Part {
+            name: disposition.get("name").cloned(),
+            filename: disposition.get("filename").cloned(),
+            content_type: headers.get("content-type").cloned(),
+            headers,
+            data: &self.body[data_start..data_end],
+        }))
+    }
+}
+
+impl<'a> Iterator for Multipart<'a> {
+    type Item = Result<Part<'a>, MultipartError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_part() {
+            Ok(Some(part)) => Some(Ok(part)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| index + from)
+}
+
+fn parse_headers(bytes: &[u8]) -> Result<HashMap<String, String>, MultipartError> {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return Err(MultipartError::Malformed("part headers are not UTF-8"));
+    };
+    let mut headers = HashMap::new();
+    for line in text.split("\r\n").filter(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(MultipartError::Malformed("invalid part header"));
+        };
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+    Ok(headers)
+}
+
+/// Parse the parameters of a `Content-Disposition` header, e.g.
+/// `form-data; name="file"; filename="a.txt"`.
+fn disposition_params(value: &str) -> HashMap<String, String> {
+    value
+        .split(';')
+        .skip(1)
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((
+                key.trim().to_lowercase(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect()
+}
+
+//
+// Encoding of multipart/form-data request bodies
+//
+
+/// Builder for a multipart/form-data body, e.g. to upload files to an external
+/// API with [`crate::http::client::OutgoingRequestBuilder::multipart()`].
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::http::{client, multipart::MultipartBuilder, Method};
+///
+/// let form = MultipartBuilder::new()
+///     .text("title", "holiday")
+///     .vfs_file("photo", "/my-app:template.os/photos/beach.jpg", None)?;
+/// let response = client::request(Method::POST, "https://example.com/upload")
+///     .multipart(form)
+///     .send()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultipartBuilder {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Default for MultipartBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartBuilder {
+    /// Start an empty body with a random boundary.
+    pub fn new() -> Self {
+        let boundary: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        Self::with_boundary(format!("----KinodeFormBoundary{}", boundary))
+    }
+
+    /// Start an empty body with the given boundary, which must not occur in any part.
+    pub fn with_boundary<T>(boundary: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            boundary: boundary.into(),
+            body: vec![],
+        }
+    }
+
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// The value of the `Content-Type` header for the body.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Add a text field.
+    pub fn text<T, U>(self, name: T, value: U) -> Self
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        self.part(name.as_ref(), None, None, value.as_ref().as_bytes())
+    }
+
+    /// Add a file part with the given file name, content type and data.
+    pub fn bytes<T, U>(self, name: T, filename: U, content_type: &str, data: &[u8]) -> Self
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        self.part(
+            name.as_ref(),
+            Some(filename.as_ref()),
+            Some(content_type),
+            data,
+        )
+    }
+
+    /// Add a file part holding the file at `path` in the VFS. The file name is the
+    /// last segment of `path`, and the content type is guessed from its extension.
+    ///
+    /// The process must have read capability for the drive of `path`.
+    pub fn vfs_file(self, name: &str, path: &str, timeout: Option<u64>) -> Result<Self, VfsError> {
+        let data = open_file(path, false, timeout)?.read()?;
+        let filename = path.rsplit('/').next().unwrap_or(path);
+        let content_type = mime_guess::from_path(filename).first_or_octet_stream();
+        Ok(self.bytes(name, filename, content_type.essence_str(), &data))
+    }
+
+    /// Finish the body, returning it and the value of its `Content-Type` header.
+    pub fn build(mut self) -> (String, Vec<u8>) {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        (self.content_type(), self.body)
+    }
+
+    fn part(
+        mut self,
+        name: &str,
+        filename: Option<&str>,
+        content_type: Option<&str>,
+        data: &[u8],
+    ) -> Self {
+        let mut headers = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            self.boundary,
+            escape_quoted(name)
+        );
+        if let Some(filename) = filename {
+            headers.push_str(&format!("; filename=\"{}\"", escape_quoted(filename)));
+        }
+        headers.push_str("\r\n");
+        if let Some(content_type) = content_type {
+            headers.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        headers.push_str("\r\n");
+        self.body.extend_from_slice(headers.as_bytes());
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+}
+
+/// Escape a `Content-Disposition` parameter value as browsers do, so that it
+/// cannot end the quoted string or the header.
+fn escape_quoted(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            boundary("multipart/form-data; boundary=\"xyz\""),
+            Some("xyz".to_string())
+        );
+        assert_eq!(boundary("application/json"), None);
+
+        let body = b"preamble\r\n--xyz\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            hello\r\n--xyz\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\r\n\
+            line 1\r\nline 2\r\n--xyz--\r\n";
+        let parts = Multipart::new(body, "xyz")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name.as_deref(), Some("title"));
+        assert_eq!(parts[0].text(), Some("hello"));
+        assert!(!parts[0].is_file());
+        assert_eq!(parts[1].filename.as_deref(), Some("a.txt"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("text/plain"));
+        assert_eq!(parts[1].data, b"line 1\r\nline 2");
+    }
+
+    #[test]
+    fn test_parse_without_headers() {
+        let body = b"--xyz\r\n\r\nhello\r\n--xyz\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            \r\n--xyz--\r\n";
+        let parts = Multipart::new(body, "xyz")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].headers.is_empty());
+        assert_eq!(parts[0].name, None);
+        assert_eq!(parts[0].text(), Some("hello"));
+        assert_eq!(parts[1].name.as_deref(), Some("title"));
+        assert_eq!(parts[1].text(), Some(""));
+    }
+
+    #[test]
+    fn test_parse_lf_line_endings() {
+        let body = b"--xyz\n\
+            Content-Disposition: form-data; name=\"title\"\n\n\
+            hello\n--xyz--\n";
+        let mut parts = Multipart::new(body, "xyz");
+        assert!(matches!(
+            parts.next(),
+            Some(Err(MultipartError::Malformed(
+                "expected CRLF after boundary"
+            )))
+        ));
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn test_build() {
+        let (content_type, body) = MultipartBuilder::with_boundary("xyz")
+            .text("title", "hello")
+            .bytes("file", "a\".txt", "text/plain", b"line 1\r\nline 2")
+            .build();
+        assert_eq!(content_type, "multipart/form-data; boundary=xyz");
+        assert_eq!(boundary(&content_type).as_deref(), Some("xyz"));
+        let parts = Multipart::new(&body, "xyz")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name.as_deref(), Some("title"));
+        assert_eq!(parts[0].text(), Some("hello"));
+        assert_eq!(parts[1].filename.as_deref(), Some("a%22.txt"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("text/plain"));
+        assert_eq!(parts[1].data, b"line 1\r\nline 2");
+    }
+}
@@ -0,0 +1,91 @@
+use url::form_urlencoded;
+
+//
+// Building URLs and encoding query strings
+//
+
+/// Parse `base` as a URL and append the given query parameters to it, percent-encoded.
+/// Any query already in `base` is kept.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::http::url_with_query;
+///
+/// let url = url_with_query("https://example.com/search?page=2", &[("q", "café & crème")]).unwrap();
+/// assert_eq!(url.as_str(), "https://example.com/search?page=2&q=caf%C3%A9+%26+cr%C3%A8me");
+/// ```
+pub fn url_with_query<K, V>(base: &str, params: &[(K, V)]) -> Result<url::Url, url::ParseError>
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut url = url::Url::parse(base)?;
+    if !params.is_empty() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+    Ok(url)
+}
+
+/// Encode the given parameters as an `application/x-www-form-urlencoded` query
+/// string, without a leading `?`.
+pub fn encode_query<K, V>(params: &[(K, V)]) -> String
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish()
+}
+
+/// Decode a query string, with or without a leading `?`, into its parameters,
+/// in order. Parameters that appear more than once are all kept.
+pub fn decode_query(query: &str) -> Vec<(String, String)> {
+    form_urlencoded::parse(query.strip_prefix('?').unwrap_or(query).as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// Encode a struct or map as a query string, as [`encode_query()`].
+pub fn to_query<T>(value: &T) -> Result<String, serde_urlencoded::ser::Error>
+where
+    T: serde::Serialize,
+{
+    serde_urlencoded::to_string(value)
+}
+
+/// Decode a query string, with or without a leading `?`, into a struct or map.
+pub fn from_query<T>(query: &str) -> Result<T, serde_urlencoded::de::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_urlencoded::from_str(query.strip_prefix('?').unwrap_or(query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_query() {
+        let query = encode_query(&[("q", "a b/ü"), ("tag", "x"), ("tag", "y")]);
+        assert_eq!(query, "q=a+b%2F%C3%BC&tag=x&tag=y");
+        assert_eq!(
+            decode_query(&format!("?{}", query)),
+            vec![
+                ("q".to_string(), "a b/ü".to_string()),
+                ("tag".to_string(), "x".to_string()),
+                ("tag".to_string(), "y".to_string()),
+            ]
+        );
+
+        let map: BTreeMap<String, String> = from_query("?a=1&b=%26").unwrap();
+        assert_eq!(map["b"], "&");
+        assert_eq!(to_query(&map).unwrap(), "a=1&b=%26");
+
+        assert!(url_with_query("not a url", &[("q", "x")]).is_err());
+        let url = url_with_query::<&str, &str>("https://example.com/a", &[]).unwrap();
+        assert_eq!(url.as_str(), "https://example.com/a");
+    }
+}
@@ -1,12 +1,19 @@
-use crate::vfs::{FileType, VfsAction, VfsRequest, VfsResponse};
+use crate::http::api_keys::ApiKeys;
+use crate::lru::LruCache;
+use crate::rate_limit::{Decision, RateLimit, RateLimiter};
+use crate::timer::set_timer;
+use crate::vfs::{temp_file_from_blob, FileType, TempFile, VfsAction, VfsRequest, VfsResponse};
 use crate::{
-    get_blob, last_blob, LazyLoadBlob as KiBlob, Message, Request as KiRequest,
-    Response as KiResponse,
+    await_message, get_blob, last_blob, Address, LazyLoadBlob as KiBlob, Message,
+    Request as KiRequest, Response as KiResponse, SendError,
 };
 pub use http::StatusCode;
 use http::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Instant;
 use thiserror::Error;
 
 /// [`crate::Request`] received from the `http-server:distro:sys` service as a
@@ -31,6 +38,15 @@ pub enum HttpServerRequest {
     /// Receiving will indicate that the client closed the socket. Can be sent to close
     /// from the server-side, as [`type@HttpServerAction::WebSocketClose`].
     WebSocketClose(u32),
+    /// Processes will receive this kind of request when a client connects to a path
+    /// bound with [`HttpServerAction::SseBind`]. Events are sent to the client with
+    /// [`HttpServerAction::SsePush`] and this channel ID.
+    SseOpen {
+        path: String,
+        channel_id: u32,
+    },
+    /// Receiving will indicate that the client disconnected from the event stream.
+    SseClose(u32),
 }
 
 impl HttpServerRequest {
@@ -50,6 +66,23 @@ impl HttpServerRequest {
     }
 }
 
+/// Prefix of the name of the login cookie set by `http-server:distro:sys`,
+/// which is followed by the name of the node.
+pub const AUTH_COOKIE_PREFIX: &str = "kinode-auth_";
+
+/// The claims of the JWT in the login cookie set by `http-server:distro:sys`
+/// when a user logs in to the node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwtClaims {
+    /// The name of the node logged in to.
+    pub username: String,
+    /// For a login to the secure subdomain of a package, that subdomain.
+    #[serde(default)]
+    pub subdomain: Option<String>,
+    /// Expiry time, in seconds since the UNIX epoch.
+    pub expiration: u64,
+}
+
 /// An HTTP request routed to a process as a result of a binding.
 ///
 /// BODY is stored in the lazy_load_blob, as bytes.
@@ -67,6 +100,9 @@ pub struct IncomingHttpRequest {
     headers: HashMap<String, String>,
     url_params: HashMap<String, String>,
     query_params: HashMap<String, String>,
+    /// The VFS path the body was written to: see [`IncomingHttpRequest::spilled_body()`].
+    #[serde(skip)]
+    spilled_body: Option<String>,
 }
 
 impl IncomingHttpRequest {
@@ -97,6 +133,13 @@ impl IncomingHttpRequest {
         }
     }
 
+    /// The VFS path of the temporary file holding the body of this request, if it
+    /// was over the limit of a path bound with [`HttpBindingConfig::spill_to_vfs()`].
+    /// The body is then not in the blob. The file is removed once the handler returns.
+    pub fn spilled_body(&self) -> Option<&str> {
+        self.spilled_body.as_deref()
+    }
+
     pub fn path(&self) -> Result<String, url::ParseError> {
         let url = url::Url::parse(&self.url)?;
         // skip the first path segment, which is the process ID.
@@ -129,6 +172,260 @@ impl IncomingHttpRequest {
     pub fn query_params(&self) -> &HashMap<String, String> {
         &self.query_params
     }
+
+    /// The claims of the node login cookie sent with this request, if it has an
+    /// unexpired one for our node, e.g. to tell which node identity is making a
+    /// request on an authenticated path.
+    ///
+    /// The JWT signature is not checked here: processes do not hold the node's
+    /// signing secret. `http-server:distro:sys` checks it before forwarding requests
+    /// on authenticated paths, so these claims can only be trusted on those paths.
+    pub fn auth_claims(&self) -> Option<JwtClaims> {
+        let node = crate::our().node;
+        let cookie_name = format!("{}{}", AUTH_COOKIE_PREFIX, node);
+        // the cookie of a secure subdomain login is named for the subdomain too
+        let subdomain_prefix = format!("{}@", cookie_name);
+        let cookies = self.cookies();
+        let jwt = cookies.get(&cookie_name).or_else(|| {
+            cookies
+                .iter()
+                .find(|(name, _)| name.starts_with(&subdomain_prefix))
+                .map(|(_, value)| value)
+        })?;
+        let mut parts = jwt.split('.');
+        let (_header, payload, _signature) = (parts.next()?, parts.next()?, parts.next()?);
+        let payload = crate::encoding::base64url_decode(payload).ok()?;
+        let claims = serde_json::from_slice::<JwtClaims>(&payload).ok()?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        (claims.username == node && claims.expiration > now).then_some(claims)
+    }
+
+    /// Whether the `If-None-Match` header of this request matches `etag`, i.e.
+    /// whether the client already has the current version of the resource and
+    /// can be answered with [`HttpResponse::not_modified()`].
+    pub fn etag_matches(&self, etag: &str) -> bool {
+        let etag = etag.trim_start_matches("W/");
+        self.headers()
+            .get_all(http::header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|candidate| candidate.trim())
+            .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+    }
+
+    /// Whether the `Accept` header of this request accepts the given MIME type,
+    /// e.g. `text/html`, either by name or through a wildcard such as `text/*`.
+    /// A request without an `Accept` header accepts any type.
+    pub fn accepts(&self, mime: &str) -> bool {
+        self.accept_quality(mime) > 0.0
+    }
+
+    /// Whether the `Accept` header of this request accepts `application/json`.
+    pub fn accepts_json(&self) -> bool {
+        self.accepts("application/json")
+    }
+
+    /// Choose the type to respond with from the MIME types a handler can produce,
+    /// according to the `Accept` header of this request, e.g. to serve JSON to API
+    /// clients and HTML to browsers from the same binding. Ties are broken by the
+    /// order of `offered`. Returns `None` if no offered type is acceptable.
+    pub fn preferred_type<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        let mut best: Option<(&'a str, f32)> = None;
+        for mime in offered {
+            let quality = self.accept_quality(mime);
+            if quality > 0.0 && best.map_or(true, |(_, best)| quality > best) {
+                best = Some((mime, quality));
+            }
+        }
+        best.map(|(mime, _)| mime)
+    }
+
+    /// The quality given to a MIME type by the most specific matching media range
+    /// in the `Accept` header of this request: 1 if there is no such header, and
+    /// 0 if no range matches.
+    fn accept_quality(&self, mime: &str) -> f32 {
+        let headers = self.headers();
+        let mut ranges = headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .peekable();
+        if ranges.peek().is_none() {
+            return 1.0;
+        }
+        let (kind, subtype) = mime.split_once('/').unwrap_or((mime, ""));
+        let mut best: Option<(u8, f32)> = None;
+        for range in ranges {
+            let mut params = range.split(';');
+            let media_range = params.next().unwrap_or_default().trim();
+            let (range_kind, range_subtype) =
+                media_range.split_once('/').unwrap_or((media_range, ""));
+            let specificity = if range_kind == "*" && range_subtype == "*" {
+                0
+            } else if range_kind.eq_ignore_ascii_case(kind) && range_subtype == "*" {
+                1
+            } else if range_kind.eq_ignore_ascii_case(kind)
+                && range_subtype.eq_ignore_ascii_case(subtype)
+            {
+                2
+            } else {
+                continue;
+            };
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if best.map_or(true, |(best, _)| specificity > best) {
+                best = Some((specificity, quality));
+            }
+        }
+        best.map_or(0.0, |(_, quality)| quality)
+    }
+
+    /// Whether the `Accept-Encoding` header of this request accepts the given
+    /// content coding, e.g. `gzip` or `br`, either by name or through `*`.
+    /// A coding given with `q=0` is refused.
+    pub fn accepts_encoding(&self, encoding: &str) -> bool {
+        let mut wildcard = None;
+        for coding in self
+            .headers()
+            .get_all(http::header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+        {
+            let mut params = coding.split(';');
+            let name = params.next().unwrap_or_default().trim();
+            let refused = params.any(|param| {
+                param
+                    .trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    == Some(0.0)
+            });
+            if name.eq_ignore_ascii_case(encoding) {
+                return !refused;
+            }
+            if name == "*" {
+                wildcard = Some(!refused);
+            }
+        }
+        wildcard.unwrap_or(false)
+    }
+
+    /// Check the `Content-Type` of this request, if it has one, against `accept`,
+    /// which is given the MIME type without parameters.
+    fn check_content_type<F>(&self, accept: F) -> Result<(), BodyError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        if let Some(content_type) = self.headers().get(http::header::CONTENT_TYPE) {
+            let content_type = content_type.to_str().unwrap_or_default();
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            if !accept(mime) {
+                return Err(BodyError::UnsupportedContentType(content_type.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserialize the JSON body of this request, which is held in the blob of the
+    /// message it arrived in. Must be called while handling that message.
+    ///
+    /// Requests with a `Content-Type` other than `application/json` (or a `+json`
+    /// type) are rejected; requests without one are accepted.
+    /// The error can be returned to the client with [`BodyError::to_response()`].
+    pub fn json_body<T>(&self) -> Result<T, BodyError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.check_content_type(|mime| mime == "application/json" || mime.ends_with("+json"))?;
+        let Some(blob) = get_blob().filter(|blob| !blob.bytes.is_empty()) else {
+            return Err(BodyError::NoBody);
+        };
+        serde_json::from_slice(&blob.bytes).map_err(|e| BodyError::Deserialize(e.to_string()))
+    }
+
+    fn form_bytes(&self) -> Result<Vec<u8>, BodyError> {
+        self.check_content_type(|mime| mime == "application/x-www-form-urlencoded")?;
+        let Some(blob) = get_blob() else {
+            return Err(BodyError::NoBody);
+        };
+        Ok(blob.bytes)
+    }
+
+    /// Parse the `application/x-www-form-urlencoded` body of this request, e.g. an
+    /// HTML form post, into a map from field names to values. If a field appears
+    /// more than once, the last value is kept. Must be called while handling the
+    /// message the request arrived in.
+    ///
+    /// Requests with a different `Content-Type` are rejected; requests without one
+    /// are accepted. The error can be returned to the client with [`BodyError::to_response()`].
+    pub fn form_body(&self) -> Result<HashMap<String, String>, BodyError> {
+        Ok(url::form_urlencoded::parse(&self.form_bytes()?)
+            .into_owned()
+            .collect())
+    }
+
+    /// Deserialize the `application/x-www-form-urlencoded` body of this request
+    /// into `T`, as [`IncomingHttpRequest::form_body()`].
+    pub fn form_body_as<T>(&self) -> Result<T, BodyError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        serde_urlencoded::from_bytes(&self.form_bytes()?)
+            .map_err(|e| BodyError::Deserialize(e.to_string()))
+    }
+
+    /// Parse the URL parameter `name`, e.g. `id` for a request to a path bound
+    /// as `/items/:id`, or `rest` for a path bound as `/files/*rest`, into any
+    /// type that implements [`std::str::FromStr`].
+    pub fn url_param<T>(&self, name: &str) -> Result<T, UrlParamError>
+    where
+        T: std::str::FromStr,
+    {
+        let Some(value) = self.url_params.get(name) else {
+            return Err(UrlParamError::Missing(name.to_string()));
+        };
+        value.parse().map_err(|_| UrlParamError::Invalid {
+            name: name.to_string(),
+            value: value.clone(),
+        })
+    }
+}
+
+/// Error returned by [`IncomingHttpRequest::json_body()`] and
+/// [`IncomingHttpRequest::form_body()`].
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum BodyError {
+    #[error("request has no body")]
+    NoBody,
+    #[error("unsupported Content-Type {0}")]
+    UnsupportedContentType(String),
+    #[error("failed to deserialize request body: {0}")]
+    Deserialize(String),
+}
+
+impl BodyError {
+    /// A `400 Bad Request` response describing this error, with a JSON body
+    /// of the form `{"error": "..."}`.
+    pub fn to_response(&self) -> (HttpResponse, Option<KiBlob>) {
+        HttpResponse::json_error(StatusCode::BAD_REQUEST, &self.to_string()).into_parts()
+    }
+}
+
+/// Error returned by [`IncomingHttpRequest::url_param()`].
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum UrlParamError {
+    #[error("missing URL parameter {0}")]
+    Missing(String),
+    #[error("invalid URL parameter {name}: {value}")]
+    Invalid { name: String, value: String },
 }
 
 /// The possible message types for [`HttpServerRequest::WebSocketPush`].
@@ -222,12 +519,26 @@ pub enum HttpServerAction {
     },
     /// Sending will close a socket the process controls.
     WebSocketClose(u32),
+    /// Bind a path to receive incoming Server-Sent Events connections: GET requests
+    /// to it are answered with a `text/event-stream` response that is kept open.
+    SseBind {
+        path: String,
+        authenticated: bool,
+        local_only: bool,
+    },
+    /// When sent, expects a [`crate::LazyLoadBlob`] containing the bytes to write to
+    /// the event stream, i.e. one or more complete SSE frames.
+    SsePush { channel_id: u32 },
+    /// Sending will end an event stream the process controls.
+    SseClose(u32),
 }
 
 /// HTTP Response type that can be shared over Wasm boundary to apps.
 /// Respond to [`IncomingHttpRequest`] with this type.
 ///
-/// BODY is stored in the [`crate::LazyLoadBlob`] as bytes
+/// BODY is stored in the [`crate::LazyLoadBlob`] as bytes. It can be attached to
+/// the response with [`HttpResponse::body_bytes()`] and friends, which give an
+/// [`HttpResponseWithBody`].
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HttpResponse {
     pub status: u16,
@@ -245,6 +556,90 @@ impl HttpResponse {
         }
     }
 
+    /// A `304 Not Modified` response for a resource with the given ETag.
+    pub fn not_modified(etag: &str) -> Self {
+        Self::new(StatusCode::NOT_MODIFIED).header("ETag", etag)
+    }
+
+    /// A `302 Found` response, redirecting the client to `location`.
+    pub fn redirect(location: &str) -> Self {
+        Self::new(StatusCode::FOUND).header("Location", location)
+    }
+
+    /// A `301 Moved Permanently` response, redirecting the client to `location`.
+    /// Browsers cache permanent redirects, so prefer [`HttpResponse::redirect()`]
+    /// unless the move really is permanent.
+    pub fn permanent_redirect(location: &str) -> Self {
+        Self::new(StatusCode::MOVED_PERMANENTLY).header("Location", location)
+    }
+
+    /// An empty `204 No Content` response.
+    pub fn no_content() -> Self {
+        Self::new(StatusCode::NO_CONTENT)
+    }
+
+    /// An empty `404 Not Found` response.
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND)
+    }
+
+    /// A response with the given status and a JSON body of the form
+    /// `{"error": "..."}` holding `message`.
+    pub fn json_error<T>(status: T, message: &str) -> HttpResponseWithBody
+    where
+        T: Into<u16>,
+    {
+        Self::new(status).body_json(&serde_json::json!({ "error": message }))
+    }
+
+    /// Attach the given bytes as the body of the response.
+    pub fn body_bytes<T>(self, bytes: T) -> HttpResponseWithBody
+    where
+        T: Into<Vec<u8>>,
+    {
+        HttpResponseWithBody {
+            response: self,
+            body: Ok(KiBlob::new(None::<String>, bytes)),
+        }
+    }
+
+    /// Attach `data` serialized to JSON as the body of the response, and set the
+    /// `Content-Type` to `application/json`. If serialization fails, the error
+    /// is returned by [`HttpResponseWithBody::send()`].
+    pub fn body_json<T>(self, data: &T) -> HttpResponseWithBody
+    where
+        T: Serialize,
+    {
+        HttpResponseWithBody {
+            response: self.header("Content-Type", "application/json"),
+            body: serde_json::to_vec(data)
+                .map(|bytes| KiBlob::new(Some("application/json"), bytes))
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Attach an HTML document as the body of the response, and set the
+    /// `Content-Type` to `text/html; charset=utf-8`.
+    pub fn body_html(self, html: &str) -> HttpResponseWithBody {
+        HttpResponseWithBody {
+            response: self.header("Content-Type", "text/html; charset=utf-8"),
+            body: Ok(KiBlob::new(Some("text/html"), html)),
+        }
+    }
+
+    /// The form returned by HTTP handlers given to [`HttpServer::handle_request()`]
+    /// and [`HttpServer::serve()`], without a body.
+    pub fn into_parts(self) -> (HttpResponse, Option<KiBlob>) {
+        (self, None)
+    }
+
+    /// Send this response, without a body, to the incoming HTTP request
+    /// ([`HttpServerRequest::Http`]) currently being handled.
+    pub fn send(self) -> anyhow::Result<()> {
+        KiResponse::new().body(serde_json::to_vec(&self)?).send()?;
+        Ok(())
+    }
+
     pub fn set_status(mut self, status: u16) -> Self {
         self.status = status;
         self
@@ -265,6 +660,59 @@ impl HttpResponse {
     }
 }
 
+/// An [`HttpResponse`] with a body, made with [`HttpResponse::body_bytes()`]
+/// and friends.
+#[derive(Clone, Debug)]
+pub struct HttpResponseWithBody {
+    pub response: HttpResponse,
+    /// The body, or the error serializing it.
+    body: Result<KiBlob, String>,
+}
+
+impl HttpResponseWithBody {
+    pub fn set_status(mut self, status: u16) -> Self {
+        self.response.status = status;
+        self
+    }
+
+    pub fn header<T, U>(mut self, key: T, value: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.response = self.response.header(key, value);
+        self
+    }
+
+    pub fn set_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.response.headers = headers;
+        self
+    }
+
+    /// Split the response into the form returned by HTTP handlers given to
+    /// [`HttpServer::handle_request()`] and [`HttpServer::serve()`].
+    /// A body that failed to serialize is replaced by an empty `500` response.
+    pub fn into_parts(self) -> (HttpResponse, Option<KiBlob>) {
+        match self.body {
+            Ok(blob) => (self.response, Some(blob)),
+            Err(_) => (HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR), None),
+        }
+    }
+
+    /// Send this response, with its body, to the incoming HTTP request
+    /// ([`HttpServerRequest::Http`]) currently being handled.
+    pub fn send(self) -> anyhow::Result<()> {
+        let blob = self
+            .body
+            .map_err(|e| anyhow::anyhow!("http-server: failed to serialize body: {}", e))?;
+        KiResponse::new()
+            .body(serde_json::to_vec(&self.response)?)
+            .blob(blob)
+            .send()?;
+        Ok(())
+    }
+}
+
 /// Part of the [`crate::Response`] type issued by http-server
 #[derive(Clone, Debug, Error, Serialize, Deserialize)]
 pub enum HttpServerError {
@@ -286,6 +734,300 @@ pub enum HttpServerError {
     UnexpectedResponse,
 }
 
+/// Metrics of the HTTP traffic handled by an [`HttpServer`]: see
+/// [`HttpServer::enable_metrics()`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpMetrics {
+    /// Requests handled, per bound path.
+    pub requests: HashMap<String, u64>,
+    /// Responses sent, per status code.
+    pub statuses: HashMap<u16, u64>,
+    /// Time taken to respond to requests, per bound path.
+    pub latency: HashMap<String, LatencyStats>,
+    /// WebSocket channels currently open.
+    pub ws_channels: usize,
+}
+
+/// Time taken to respond to the requests on a path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub total_secs: f64,
+    pub max_secs: f64,
+}
+
+impl LatencyStats {
+    /// The mean time taken to respond, in seconds.
+    pub fn mean_secs(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_secs / self.count as f64
+        }
+    }
+}
+
+impl HttpMetrics {
+    /// Render the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let registry = crate::metrics::Registry::new();
+        let requests = registry.counter("http_requests_total", "HTTP requests handled.");
+        for (path, count) in &self.requests {
+            requests.inc_by(&[("path", path)], *count as f64);
+        }
+        let responses = registry.counter("http_responses_total", "HTTP responses sent.");
+        for (status, count) in &self.statuses {
+            responses.inc_by(&[("status", &status.to_string())], *count as f64);
+        }
+        let total = registry.counter(
+            "http_request_duration_seconds_total",
+            "Total time taken to respond to HTTP requests.",
+        );
+        let max = registry.gauge(
+            "http_request_duration_seconds_max",
+            "Longest time taken to respond to an HTTP request.",
+        );
+        for (path, latency) in &self.latency {
+            total.inc_by(&[("path", path)], latency.total_secs);
+            max.set(&[("path", path)], latency.max_secs);
+        }
+        registry
+            .gauge("ws_channels_open", "WebSocket channels currently open.")
+            .set(&[], self.ws_channels as f64);
+        registry.render()
+    }
+}
+
+/// Structured health of a process, served as JSON by a path bound with
+/// [`HttpServer::bind_healthcheck()`].
+///
+/// A process is live if it is running and able to respond at all, and ready if it
+/// is able to do useful work, e.g. once it has finished initializing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HealthStatus {
+    pub live: bool,
+    pub ready: bool,
+    /// Seconds since the healthcheck was bound, if not set by the process.
+    pub uptime_secs: Option<u64>,
+    /// The most recent error the process encountered, if any.
+    pub last_error: Option<String>,
+    /// Lengths of named internal queues, e.g. pending requests.
+    pub queue_depths: HashMap<String, u64>,
+}
+
+impl Default for HealthStatus {
+    fn default() -> Self {
+        Self {
+            live: true,
+            ready: true,
+            uptime_secs: None,
+            last_error: None,
+            queue_depths: HashMap::new(),
+        }
+    }
+}
+
+impl HealthStatus {
+    /// Create a new HealthStatus that is live and ready.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn live(mut self, live: bool) -> Self {
+        self.live = live;
+        self
+    }
+
+    pub fn ready(mut self, ready: bool) -> Self {
+        self.ready = ready;
+        self
+    }
+
+    pub fn uptime_secs(mut self, uptime_secs: u64) -> Self {
+        self.uptime_secs = Some(uptime_secs);
+        self
+    }
+
+    pub fn last_error<T>(mut self, last_error: Option<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        self.last_error = last_error.map(|e| e.into());
+        self
+    }
+
+    pub fn queue_depth<T>(mut self, queue: T, depth: u64) -> Self
+    where
+        T: Into<String>,
+    {
+        self.queue_depths.insert(queue.into(), depth);
+        self
+    }
+}
+
+/// An event passed to the handler given to [`HttpServer::serve()`].
+#[derive(Debug)]
+pub enum ServerEvent {
+    /// An HTTP request to a bound path. The handler's return value is sent as the response.
+    Http(IncomingHttpRequest),
+    /// A WebSocket message from a client, with the message bytes.
+    WsPush {
+        channel_id: u32,
+        message_type: WsMessageType,
+        blob: KiBlob,
+    },
+    /// A client opened a WebSocket on a bound path. The channel has already
+    /// been added to the server's channels.
+    WsOpen { path: String, channel_id: u32 },
+    /// A client closed a WebSocket. The channel has already been removed from
+    /// the server's channels.
+    WsClose(u32),
+    /// A client connected to a path bound with [`HttpServer::bind_sse_path()`].
+    /// The channel has already been added to the server's channels.
+    SseOpen { path: String, channel: SseChannel },
+    /// A client disconnected from an event stream. The channel has already been
+    /// removed from the server's channels.
+    SseClose(u32),
+    /// A message that is not from `http-server:distro:sys` on our node.
+    Message(Message),
+    /// An error from a request this process sent.
+    SendError(SendError),
+}
+
+/// How often a comment is sent to every open Server-Sent Events channel to keep
+/// idle connections from being closed by proxies and clients.
+pub const SSE_KEEPALIVE_INTERVAL_MS: u64 = 15_000;
+
+/// Timer context used to schedule SSE keep-alives.
+const SSE_KEEPALIVE_CONTEXT: &[u8] = b"http-server:sse-keepalive";
+
+const SSE_KEEPALIVE_FRAME: &str = ": keep-alive\n\n";
+
+/// A Server-Sent Event, formatted as an `event:`/`id:`/`retry:`/`data:` frame.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::http::server::SseEvent;
+///
+/// let event = SseEvent::new("line 1\nline 2").event("update").id("7");
+/// assert_eq!(event.to_string(), "event: update\nid: 7\ndata: line 1\ndata: line 2\n\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SseEvent {
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<u64>,
+    data: String,
+}
+
+impl SseEvent {
+    /// Create an event carrying `data`, which may span multiple lines.
+    pub fn new<T>(data: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            event: None,
+            id: None,
+            retry: None,
+            data: data.into(),
+        }
+    }
+
+    /// Create an event carrying `data` serialized to JSON.
+    pub fn json<T>(data: &T) -> serde_json::Result<Self>
+    where
+        T: Serialize,
+    {
+        Ok(Self::new(serde_json::to_string(data)?))
+    }
+
+    /// Set the event type, dispatched to `addEventListener(event, ...)` listeners
+    /// in the browser rather than to `onmessage`.
+    pub fn event<T>(mut self, event: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set the event ID, which the browser sends back as `Last-Event-ID` on reconnect.
+    pub fn id<T>(mut self, id: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set how long, in milliseconds, the browser waits before reconnecting.
+    pub fn retry(mut self, retry: u64) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl std::fmt::Display for SseEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", event)?;
+        }
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", id)?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry)?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {}", line.strip_suffix('\r').unwrap_or(line))?;
+        }
+        writeln!(f)
+    }
+}
+
+/// A handle to a client connected to a path bound with [`HttpServer::bind_sse_path()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SseChannel {
+    pub channel_id: u32,
+}
+
+impl SseChannel {
+    /// Send an event to the client.
+    pub fn send(&self, event: &SseEvent) {
+        send_sse_push(self.channel_id, event.to_string().into_bytes());
+    }
+
+    /// End the event stream.
+    pub fn close(&self) {
+        KiRequest::to(("our", "http-server", "distro", "sys"))
+            .body(serde_json::to_vec(&HttpServerAction::SseClose(self.channel_id)).unwrap())
+            .send()
+            .unwrap();
+    }
+}
+
+fn send_sse_push(channel_id: u32, bytes: Vec<u8>) {
+    KiRequest::to(("our", "http-server", "distro", "sys"))
+        .body(serde_json::to_vec(&HttpServerAction::SsePush { channel_id }).unwrap())
+        .blob(KiBlob::new(Some("text/event-stream"), bytes))
+        .send()
+        .unwrap();
+}
+
+/// A handler for requests to an HTTP path that the [`HttpServer`] answers itself,
+/// rather than passing them to the handler given to [`HttpServer::handle_request()`].
+#[derive(Clone)]
+struct InternalHttpHandler(Rc<InternalHttpHandlerFn>);
+
+type InternalHttpHandlerFn = dyn Fn(&IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>);
+
+impl std::fmt::Debug for InternalHttpHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InternalHttpHandler")
+    }
+}
+
 /// Whether the [`HttpServerAction::WebSocketPush`] is [`crate::Request`] or [`crate::Response`].
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum MessageType {
@@ -300,6 +1042,35 @@ pub struct HttpServer {
     ws_paths: HashMap<String, WsBindingConfig>,
     /// A mapping of WebSocket paths to the channels that are open on them.
     ws_channels: HashMap<String, HashSet<u32>>,
+    /// A mapping of WebSocket rooms to the channels that have joined them.
+    ws_rooms: HashMap<String, HashSet<u32>>,
+    /// Data attached to WebSocket channels by the process.
+    ws_channel_data: HashMap<u32, serde_json::Value>,
+    sse_paths: HashMap<String, HttpBindingConfig>,
+    /// A mapping of Server-Sent Events paths to the channels that are open on them.
+    sse_channels: HashMap<String, HashSet<u32>>,
+    /// Whether the timer driving SSE keep-alive comments has been set.
+    sse_keepalive_started: bool,
+    /// ETags of the static content bound to HTTP paths.
+    etags: HashMap<String, String>,
+    /// Rate limiters of HTTP paths bound with [`HttpBindingConfig::rate_limit()`].
+    rate_limiters: HashMap<String, RateLimiter<String>>,
+    /// Metrics of HTTP traffic, if enabled with [`HttpServer::enable_metrics()`].
+    metrics: Option<Rc<RefCell<HttpMetrics>>>,
+    /// Paths unbound by dropping a [`BoundPath`], to forget on the next call
+    /// that handles a request or binds a path.
+    dropped_paths: Rc<RefCell<Vec<(BoundPathKind, String)>>>,
+    /// The page sent when an HTTP handler fails without a body,
+    /// set with [`HttpServer::set_error_page()`].
+    error_page: Option<ErrorPage>,
+    /// Files served with [`HttpServer::serve_file_lazy()`] that were read recently,
+    /// keyed by their VFS path. Holds nothing unless enabled with
+    /// [`HttpServer::set_lazy_file_cache()`].
+    lazy_file_cache: Rc<RefCell<LruCache<String, KiBlob>>>,
+    /// Keys accepted on paths bound with [`HttpBindingConfig::require_api_key()`].
+    api_keys: ApiKeys,
+    /// HTTP paths answered by the server itself, e.g. a healthcheck.
+    internal_http_handlers: HashMap<String, InternalHttpHandler>,
     /// The timeout given for `http-server:distro:sys` to respond to a configuration request.
     pub timeout: u64,
 }
@@ -328,6 +1099,12 @@ pub struct HttpBindingConfig {
     local_only: bool,
     secure_subdomain: bool,
     static_content: Option<KiBlob>,
+    etag: bool,
+    ranges: bool,
+    rate_limit: Option<(RateLimit, RateLimitBy)>,
+    max_body_size: Option<u64>,
+    spill_to_vfs: bool,
+    require_api_key: bool,
 }
 
 impl HttpBindingConfig {
@@ -340,6 +1117,12 @@ impl HttpBindingConfig {
             local_only: false,
             secure_subdomain: false,
             static_content: None,
+            etag: false,
+            ranges: false,
+            rate_limit: None,
+            max_body_size: None,
+            spill_to_vfs: false,
+            require_api_key: false,
         }
     }
 
@@ -355,6 +1138,12 @@ impl HttpBindingConfig {
             local_only,
             secure_subdomain,
             static_content,
+            etag: false,
+            ranges: false,
+            rate_limit: None,
+            max_body_size: None,
+            spill_to_vfs: false,
+            require_api_key: false,
         }
     }
 
@@ -388,20 +1177,332 @@ impl HttpBindingConfig {
         self.static_content = static_content;
         self
     }
-}
 
-/// Configuration for a WebSocket binding.
-///
-/// `authenticated` is set to true by default and means that the WebSocket server will
-/// require a valid login cookie to access this path.
-///
-/// `extension` is set to false by default and means that the WebSocket will
-/// not use the WebSocket extension protocol to connect with a runtime extension.
-#[derive(Clone, Copy, Debug)]
-pub struct WsBindingConfig {
-    authenticated: bool,
-    secure_subdomain: bool,
-    extension: bool,
+    /// Set whether static content is served by this process, with an `ETag` header,
+    /// rather than cached by the HTTP server. Clients that send the ETag back in an
+    /// `If-None-Match` header are answered `304 Not Modified` without the content,
+    /// so browsers need not re-download unchanged assets.
+    ///
+    /// Requests are answered by [`HttpServer::handle_request()`] or [`HttpServer::serve()`]
+    /// without calling the given handler. Has no effect without static content.
+    pub fn etag(mut self, etag: bool) -> Self {
+        self.etag = etag;
+        self
+    }
+
+    /// Set whether static content is served by this process, honoring `Range`
+    /// headers, rather than cached by the HTTP server. A request for a single
+    /// byte range is answered `206 Partial Content` with that part of the content,
+    /// as browsers require to seek in audio and video.
+    ///
+    /// Requests are answered by [`HttpServer::handle_request()`] or [`HttpServer::serve()`]
+    /// without calling the given handler. Has no effect without static content.
+    pub fn ranges(mut self, ranges: bool) -> Self {
+        self.ranges = ranges;
+        self
+    }
+
+    /// Limit the rate of requests to this path, with a token bucket per client,
+    /// as identified by `by`. Requests over the limit are answered with
+    /// `429 Too Many Requests` by [`HttpServer::handle_request()`] or
+    /// [`HttpServer::serve()`], without calling the given handler.
+    ///
+    /// Requests over the limit are never deferred: `max_defer` is ignored.
+    /// Has no effect on static content cached by the HTTP server.
+    pub fn rate_limit(mut self, limit: RateLimit, by: RateLimitBy) -> Self {
+        self.rate_limit = Some((limit, by));
+        self
+    }
+
+    /// Limit the size of request bodies on this path, in bytes. Larger requests are
+    /// answered with `413 Payload Too Large` by [`HttpServer::handle_request()`]
+    /// or [`HttpServer::serve()`], without calling the given handler.
+    ///
+    /// Requests are checked by their `Content-Length` header, so that the process
+    /// doesn't load oversized bodies with [`get_blob()`]. Requests without a valid
+    /// one, e.g. those with a chunked body, are checked by loading the body. Either way,
+    /// http-server:distro:sys has already received the whole body before the
+    /// request reaches the process, so this limit doesn't bound its memory use.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Set whether requests over [`HttpBindingConfig::max_body_size()`] are handled
+    /// with their body written to a temporary file in the `tmp` drive, instead of
+    /// being answered with `413 Payload Too Large`. The body is passed to the VFS
+    /// without being loaded into the process, and the handler finds the file with
+    /// [`IncomingHttpRequest::spilled_body()`]. The file is removed once the handler
+    /// returns: a handler that responds later must move or copy it first.
+    ///
+    /// Has no effect without a `max_body_size`.
+    pub fn spill_to_vfs(mut self, spill_to_vfs: bool) -> Self {
+        self.spill_to_vfs = spill_to_vfs;
+        self
+    }
+
+    /// Set whether requests to this path must carry one of the API keys given to
+    /// [`HttpServer::set_api_keys()`]. Requests without a valid key are answered
+    /// with `401 Unauthorized` by [`HttpServer::handle_request()`] or
+    /// [`HttpServer::serve()`], without calling the given handler.
+    ///
+    /// Typically used on paths that are not `authenticated`, to expose an API to
+    /// external services that do not have the node login cookie.
+    /// Has no effect on static content cached by the HTTP server.
+    pub fn require_api_key(mut self, require_api_key: bool) -> Self {
+        self.require_api_key = require_api_key;
+        self
+    }
+}
+
+/// How the clients of a rate-limited HTTP path are told apart:
+/// see [`HttpBindingConfig::rate_limit()`].
+///
+/// Requests that can't be told apart, because their source address is missing
+/// or unparsable, are not rate limited, rather than sharing one limit through
+/// which any client could block all the others.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RateLimitBy {
+    /// By the IP address the request came from.
+    SourceIp,
+    /// By the value of the cookie with the given name, e.g. a session cookie.
+    /// Requests without the cookie are limited by their IP address.
+    Cookie(String),
+}
+
+impl RateLimitBy {
+    /// The client a request is limited as, or `None` if it can't be told apart.
+    fn key(&self, request: &IncomingHttpRequest) -> Option<String> {
+        let source_ip = || {
+            request
+                .source_socket_addr()
+                .ok()
+                .map(|addr| format!("ip:{}", addr.ip()))
+        };
+        match self {
+            RateLimitBy::SourceIp => source_ip(),
+            RateLimitBy::Cookie(name) => request
+                .cookie(name)
+                .map(|value| format!("cookie:{}", value))
+                .or_else(source_ip),
+        }
+    }
+}
+
+/// Options for [`HttpServer::serve_ui_with()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UiOptions {
+    spa_fallback: bool,
+    lazy: bool,
+    precompressed: bool,
+}
+
+impl UiOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether GET requests for paths under each root that match no file are
+    /// answered with the top-level `index.html`, for apps with client-side routing,
+    /// so that reloading the page on a client-side route does not 404.
+    ///
+    /// Paths whose last segment has a file extension, e.g. a missing `app.js`, are
+    /// still answered `404 Not Found`. Requests are answered by
+    /// [`HttpServer::handle_request()`] or [`HttpServer::serve()`] without calling
+    /// the given handler.
+    pub fn spa_fallback(mut self, spa_fallback: bool) -> Self {
+        self.spa_fallback = spa_fallback;
+        self
+    }
+
+    /// Set whether files are read from the VFS on each request, with
+    /// [`HttpServer::serve_file_lazy_raw_path()`], rather than cached in
+    /// http-server at bind time, so that a large UI is not held in memory twice.
+    ///
+    /// Precompressed siblings are then served only at their own paths, not
+    /// according to the `Accept-Encoding` of each request.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Set whether files with precompressed `.br` or `.gz` siblings are served
+    /// with [`HttpServer::serve_precompressed_file_raw_path()`], according to the
+    /// `Accept-Encoding` of each request. Off by default.
+    ///
+    /// Such files are then answered by the process, so requests must be passed
+    /// to [`HttpServer::handle_request()`] or [`HttpServer::serve()`]. Otherwise,
+    /// they are cached and served by http-server, and their siblings only at
+    /// their own paths. Has no effect if `lazy` is set.
+    pub fn precompressed(mut self, precompressed: bool) -> Self {
+        self.precompressed = precompressed;
+        self
+    }
+}
+
+/// A custom body for error responses, sent instead of the runtime's generic
+/// error: see [`HttpServer::bind_not_found_page()`] and [`HttpServer::set_error_page()`].
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::http::server::{ErrorPage, HttpBindingConfig, HttpServer};
+///
+/// let mut server = HttpServer::new(5);
+/// server
+///     .bind_not_found_page(
+///         "/app",
+///         HttpBindingConfig::default(),
+///         ErrorPage::html("<h1>Nothing here</h1>"),
+///     )
+///     .unwrap();
+/// server.set_error_page(ErrorPage::html("<h1>Something went wrong</h1>"));
+/// ```
+#[derive(Clone)]
+pub struct ErrorPage(ErrorPageBody);
+
+#[derive(Clone)]
+enum ErrorPageBody {
+    Static { content_type: String, body: KiBlob },
+    Handler(Rc<ErrorPageHandlerFn>),
+}
+
+type ErrorPageHandlerFn =
+    dyn Fn(&IncomingHttpRequest, StatusCode) -> (HttpResponse, Option<KiBlob>);
+
+impl ErrorPage {
+    /// A fixed body, sent with the given `Content-Type`.
+    pub fn new<T, U>(content_type: T, body: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<Vec<u8>>,
+    {
+        let content_type = content_type.into();
+        ErrorPage(ErrorPageBody::Static {
+            body: KiBlob::new(Some(content_type.clone()), body),
+            content_type,
+        })
+    }
+
+    /// A fixed HTML body.
+    pub fn html<T>(html: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new("text/html", html.into())
+    }
+
+    /// A response built by `handler` from the request and the error status,
+    /// e.g. to render the requested path into the page.
+    pub fn handler<F>(handler: F) -> Self
+    where
+        F: Fn(&IncomingHttpRequest, StatusCode) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        ErrorPage(ErrorPageBody::Handler(Rc::new(handler)))
+    }
+
+    fn response(
+        &self,
+        request: &IncomingHttpRequest,
+        status: StatusCode,
+    ) -> (HttpResponse, Option<KiBlob>) {
+        match &self.0 {
+            ErrorPageBody::Static { content_type, body } => (
+                HttpResponse::new(status).header("Content-Type", content_type),
+                Some(body.clone()),
+            ),
+            ErrorPageBody::Handler(handler) => handler(request, status),
+        }
+    }
+
+    /// Replace a handler's response with this page if it is a
+    /// `500 Internal Server Error` without a body.
+    fn fill(
+        &self,
+        request: &IncomingHttpRequest,
+        response: HttpResponse,
+        blob: Option<KiBlob>,
+    ) -> (HttpResponse, Option<KiBlob>) {
+        if response.status == StatusCode::INTERNAL_SERVER_ERROR && blob.is_none() {
+            self.response(request, StatusCode::INTERNAL_SERVER_ERROR)
+        } else {
+            (response, blob)
+        }
+    }
+}
+
+impl std::fmt::Debug for ErrorPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            ErrorPageBody::Static { content_type, .. } => {
+                write!(f, "ErrorPage::Static({})", content_type)
+            }
+            ErrorPageBody::Handler(_) => write!(f, "ErrorPage::Handler"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BoundPathKind {
+    Http,
+    Ws,
+}
+
+/// A path bound with [`HttpServer::bind_scoped()`] or [`HttpServer::bind_ws_scoped()`],
+/// which is unbound when this guard is dropped.
+///
+/// The unbind request is sent without waiting for a response, and the
+/// [`HttpServer`] forgets the path the next time it handles a request or binds
+/// a path.
+#[must_use = "the path is unbound as soon as this guard is dropped"]
+#[derive(Debug)]
+pub struct BoundPath {
+    kind: BoundPathKind,
+    /// `None` once leaked.
+    path: Option<String>,
+    dropped_paths: Rc<RefCell<Vec<(BoundPathKind, String)>>>,
+}
+
+impl BoundPath {
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or_default()
+    }
+
+    /// Keep the path bound after this guard is gone, returning the path.
+    /// It can still be unbound with [`HttpServer::unbind_http_path()`] or
+    /// [`HttpServer::unbind_ws_path()`].
+    pub fn leak(mut self) -> String {
+        self.path.take().unwrap_or_default()
+    }
+}
+
+impl Drop for BoundPath {
+    fn drop(&mut self) {
+        let Some(path) = self.path.take() else {
+            return;
+        };
+        let action = match self.kind {
+            BoundPathKind::Http => HttpServerAction::Unbind { path: path.clone() },
+            BoundPathKind::Ws => HttpServerAction::WebSocketUnbind { path: path.clone() },
+        };
+        let _ = KiRequest::to(("our", "http-server", "distro", "sys"))
+            .body(serde_json::to_vec(&action).unwrap())
+            .send();
+        self.dropped_paths.borrow_mut().push((self.kind, path));
+    }
+}
+
+/// Configuration for a WebSocket binding.
+///
+/// `authenticated` is set to true by default and means that the WebSocket server will
+/// require a valid login cookie to access this path.
+///
+/// `extension` is set to false by default and means that the WebSocket will
+/// not use the WebSocket extension protocol to connect with a runtime extension.
+#[derive(Clone, Copy, Debug)]
+pub struct WsBindingConfig {
+    authenticated: bool,
+    secure_subdomain: bool,
+    extension: bool,
 }
 
 impl WsBindingConfig {
@@ -451,11 +1552,29 @@ impl HttpServer {
             http_paths: HashMap::new(),
             ws_paths: HashMap::new(),
             ws_channels: HashMap::new(),
+            ws_rooms: HashMap::new(),
+            ws_channel_data: HashMap::new(),
+            sse_paths: HashMap::new(),
+            sse_channels: HashMap::new(),
+            sse_keepalive_started: false,
+            etags: HashMap::new(),
+            rate_limiters: HashMap::new(),
+            metrics: None,
+            dropped_paths: Rc::new(RefCell::new(Vec::new())),
+            error_page: None,
+            lazy_file_cache: Rc::new(RefCell::new(LruCache::new(0))),
+            api_keys: ApiKeys::new(),
+            internal_http_handlers: HashMap::new(),
             timeout,
         }
     }
 
     /// Register a new path with the HTTP server configured using [`HttpBindingConfig`].
+    ///
+    /// The path may contain parameters, such as `:id` in `/api/items/:id`, and end
+    /// in a wildcard, such as `*rest` in `/files/*rest`, which matches the rest of
+    /// the path: see [`PathPattern`]. Handlers read their values with
+    /// [`IncomingHttpRequest::url_param()`].
     pub fn bind_http_path<T>(
         &mut self,
         path: T,
@@ -464,8 +1583,14 @@ impl HttpServer {
     where
         T: Into<String>,
     {
+        self.forget_dropped_paths();
         let path: String = path.into();
-        let cache = config.static_content.is_some();
+        if config.spill_to_vfs && config.max_body_size.is_some() {
+            crate::vfs::temp_drive(Some(self.timeout))
+                .map_err(|_| HttpServerError::UnexpectedResponse)?;
+        }
+        let serve_by_process = (config.etag || config.ranges) && config.static_content.is_some();
+        let cache = config.static_content.is_some() && !serve_by_process;
         let req = KiRequest::to(("our", "http-server", "distro", "sys")).body(
             serde_json::to_vec(&if config.secure_subdomain {
                 HttpServerAction::SecureBind {
@@ -483,10 +1608,10 @@ impl HttpServer {
             .unwrap(),
         );
         let res = match config.static_content.clone() {
-            Some(static_content) => req
+            Some(static_content) if cache => req
                 .blob(static_content)
                 .send_and_await_response(self.timeout),
-            None => req.send_and_await_response(self.timeout),
+            _ => req.send_and_await_response(self.timeout),
         };
         let Ok(Message::Response { body, .. }) = res.unwrap() else {
             return Err(HttpServerError::Timeout);
@@ -495,11 +1620,38 @@ impl HttpServer {
             return Err(HttpServerError::UnexpectedResponse);
         };
         if resp.is_ok() {
+            self.internal_http_handlers.remove(&path);
+            self.etags.remove(&path);
+            self.rate_limiters.remove(&path);
+            if let Some((limit, _)) = config.rate_limit {
+                self.rate_limiters.insert(
+                    path.clone(),
+                    RateLimiter::new(limit.max_defer(std::time::Duration::ZERO)),
+                );
+            }
+            if let Some(static_content) = config.static_content.clone() {
+                let etag = compute_etag(&static_content.bytes);
+                self.etags.insert(path.clone(), etag);
+                if serve_by_process {
+                    let (etag, ranges) = (config.etag, config.ranges);
+                    self.internal_http_handlers.insert(
+                        path.clone(),
+                        InternalHttpHandler(Rc::new(move |request| {
+                            static_response(request, &static_content, etag, ranges)
+                        })),
+                    );
+                }
+            }
             self.http_paths.insert(path, config);
         }
         resp
     }
 
+    /// The ETag of the static content bound to a path, computed when it was bound.
+    pub fn etag(&self, path: &str) -> Option<&str> {
+        self.etags.get(path).map(|etag| etag.as_str())
+    }
+
     /// Register a new path with the HTTP server configured using [`WsBindingConfig`].
     pub fn bind_ws_path<T>(
         &mut self,
@@ -509,6 +1661,7 @@ impl HttpServer {
     where
         T: Into<String>,
     {
+        self.forget_dropped_paths();
         let path: String = path.into();
         let res = KiRequest::to(("our", "http-server", "distro", "sys"))
             .body(if config.secure_subdomain {
@@ -575,6 +1728,8 @@ impl HttpServer {
             return Err(HttpServerError::UnexpectedResponse);
         };
         if resp.is_ok() {
+            self.internal_http_handlers.remove(&path);
+            self.etags.insert(path.clone(), compute_etag(&content));
             self.http_paths.insert(
                 path,
                 HttpBindingConfig {
@@ -585,6 +1740,12 @@ impl HttpServer {
                         mime: content_type,
                         bytes: content,
                     }),
+                    etag: false,
+                    ranges: false,
+                    rate_limit: None,
+                    max_body_size: None,
+                    spill_to_vfs: false,
+                    require_api_key: false,
                 },
             );
         }
@@ -627,6 +1788,12 @@ impl HttpServer {
                     local_only: false,
                     secure_subdomain: true,
                     static_content: None,
+                    etag: false,
+                    ranges: false,
+                    rate_limit: None,
+                    max_body_size: None,
+                    spill_to_vfs: false,
+                    require_api_key: false,
                 },
             );
         }
@@ -673,7 +1840,8 @@ impl HttpServer {
         resp
     }
 
-    /// Modify a previously-bound HTTP path.
+    /// Modify a previously-bound HTTP path, replacing its whole configuration
+    /// as [`HttpServer::bind_http_path()`] would.
     pub fn modify_http_path<T>(
         &mut self,
         path: &str,
@@ -682,35 +1850,10 @@ impl HttpServer {
     where
         T: Into<String>,
     {
-        let entry = self
-            .http_paths
-            .get_mut(path)
-            .ok_or(HttpServerError::MalformedRequest)?;
-        let res = KiRequest::to(("our", "http-server", "distro", "sys"))
-            .body(
-                serde_json::to_vec(&HttpServerAction::Bind {
-                    path: path.to_string(),
-                    authenticated: config.authenticated,
-                    local_only: config.local_only,
-                    cache: true,
-                })
-                .unwrap(),
-            )
-            .send_and_await_response(self.timeout)
-            .unwrap();
-        let Ok(Message::Response { body, .. }) = res else {
-            return Err(HttpServerError::Timeout);
-        };
-        let Ok(resp) = serde_json::from_slice::<Result<(), HttpServerError>>(&body) else {
-            return Err(HttpServerError::UnexpectedResponse);
-        };
-        if resp.is_ok() {
-            entry.authenticated = config.authenticated;
-            entry.local_only = config.local_only;
-            entry.secure_subdomain = config.secure_subdomain;
-            entry.static_content = config.static_content;
+        if !self.http_paths.contains_key(path) {
+            return Err(HttpServerError::MalformedRequest);
         }
-        resp
+        self.bind_http_path(path, config)
     }
 
     /// Modify a previously-bound WS path
@@ -754,7 +1897,7 @@ impl HttpServer {
         resp
     }
 
-    /// Unbind a previously-bound HTTP path.
+    /// Unbind a previously-bound HTTP or Server-Sent Events path.
     pub fn unbind_http_path<T>(&mut self, path: T) -> Result<(), HttpServerError>
     where
         T: Into<String>,
@@ -771,11 +1914,85 @@ impl HttpServer {
             return Err(HttpServerError::UnexpectedResponse);
         };
         if resp.is_ok() {
-            self.http_paths.remove(&path);
+            self.forget_http_path(&path);
         }
         resp
     }
 
+    /// Forget the state kept for an HTTP or Server-Sent Events path once it is unbound.
+    fn forget_http_path(&mut self, path: &str) {
+        self.http_paths.remove(path);
+        self.sse_paths.remove(path);
+        self.sse_channels.remove(path);
+        self.internal_http_handlers.remove(path);
+        self.etags.remove(path);
+        self.rate_limiters.remove(path);
+    }
+
+    /// Forget the state kept for paths unbound by dropping a [`BoundPath`].
+    fn forget_dropped_paths(&mut self) {
+        let dropped = std::mem::take(&mut *self.dropped_paths.borrow_mut());
+        for (kind, path) in dropped {
+            match kind {
+                BoundPathKind::Http => self.forget_http_path(&path),
+                BoundPathKind::Ws => {
+                    self.ws_paths.remove(&path);
+                }
+            }
+        }
+    }
+
+    /// Register a new HTTP path, as [`HttpServer::bind_http_path()`] does, for as long
+    /// as the returned guard lives: dropping it unbinds the path. Suited to temporary
+    /// endpoints, e.g. a one-shot OAuth callback or an ephemeral file share.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::http::server::{HttpBindingConfig, HttpServer};
+    ///
+    /// let mut server = HttpServer::new(5);
+    /// let callback = server
+    ///     .bind_scoped("/oauth/callback", HttpBindingConfig::default())
+    ///     .unwrap();
+    /// // ...handle the callback request, then:
+    /// drop(callback);
+    /// ```
+    pub fn bind_scoped<T>(
+        &mut self,
+        path: T,
+        config: HttpBindingConfig,
+    ) -> Result<BoundPath, HttpServerError>
+    where
+        T: Into<String>,
+    {
+        let path: String = path.into();
+        self.bind_http_path(path.clone(), config)?;
+        Ok(self.bound_path_guard(BoundPathKind::Http, path))
+    }
+
+    /// Register a new WebSocket path, as [`HttpServer::bind_ws_path()`] does, for as
+    /// long as the returned guard lives: dropping it unbinds the path.
+    pub fn bind_ws_scoped<T>(
+        &mut self,
+        path: T,
+        config: WsBindingConfig,
+    ) -> Result<BoundPath, HttpServerError>
+    where
+        T: Into<String>,
+    {
+        let path: String = path.into();
+        self.bind_ws_path(path.clone(), config)?;
+        Ok(self.bound_path_guard(BoundPathKind::Ws, path))
+    }
+
+    fn bound_path_guard(&self, kind: BoundPathKind, path: String) -> BoundPath {
+        BoundPath {
+            kind,
+            path: Some(path),
+            dropped_paths: self.dropped_paths.clone(),
+        }
+    }
+
     /// Unbind a previously-bound WebSocket path.
     pub fn unbind_ws_path<T>(&mut self, path: T) -> Result<(), HttpServerError>
     where
@@ -853,26 +2070,147 @@ impl HttpServer {
         paths: Vec<&str>,
         config: HttpBindingConfig,
     ) -> Result<(), HttpServerError> {
-        let _res = KiRequest::to(("our", "vfs", "distro", "sys"))
-            .body(
-                serde_json::to_vec(&VfsRequest {
-                    path: file_path.to_string(),
-                    action: VfsAction::Read,
-                })
-                .map_err(|_| HttpServerError::MalformedRequest)?,
-            )
-            .send_and_await_response(self.timeout)
-            .unwrap();
+        let blob = self.read_vfs_file(file_path, file_path)?;
 
-        let Some(mut blob) = get_blob() else {
-            return Err(HttpServerError::NoBlob);
-        };
+        for path in paths {
+            self.bind_http_path(path, config.clone().static_content(Some(blob.clone())))?;
+        }
 
-        let content_type = get_mime_type(&file_path);
-        blob.mime = Some(content_type);
+        Ok(())
+    }
+
+    /// Read a file from the VFS into a blob, with the MIME type of `mime_from`.
+    fn read_vfs_file(&self, file_path: &str, mime_from: &str) -> Result<KiBlob, HttpServerError> {
+        read_vfs_file(file_path, mime_from, self.timeout)
+    }
 
+    /// Serve a file from within the `pkg` folder of this package's drive, as
+    /// [`HttpServer::serve_file()`] does, but read it from the VFS on each request
+    /// rather than caching it in http-server at bind time.
+    pub fn serve_file_lazy(
+        &mut self,
+        file_path: &str,
+        paths: Vec<&str>,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        let file_path = format!(
+            "/{}/pkg/{}",
+            crate::our().package_id(),
+            file_path.trim_start_matches('/')
+        );
+        self.serve_file_lazy_raw_path(&file_path, paths, config)
+    }
+
+    /// Serve a file from the given absolute directory, reading it from the VFS on
+    /// each request rather than caching it in http-server at bind time, so that
+    /// binding many or large files does not hold them all in memory.
+    ///
+    /// Requests are answered by this process, in [`HttpServer::handle_request()`]
+    /// or [`HttpServer::serve()`]: `GET` and `HEAD` with the file, or
+    /// `404 Not Found` if it cannot be read, and other methods with
+    /// `405 Method Not Allowed`. Recently read files are kept in memory if
+    /// enabled with [`HttpServer::set_lazy_file_cache()`]. The config
+    /// `static_content` field will be ignored, while [`HttpBindingConfig::etag()`]
+    /// and [`HttpBindingConfig::ranges()`] are honored.
+    pub fn serve_file_lazy_raw_path(
+        &mut self,
+        file_path: &str,
+        paths: Vec<&str>,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        let (etag, ranges) = (config.etag, config.ranges);
         for path in paths {
-            self.bind_http_path(path, config.clone().static_content(Some(blob.clone())))?;
+            let file_path = file_path.to_string();
+            let cache = self.lazy_file_cache.clone();
+            let timeout = self.timeout;
+            self.bind_internal_http_path(
+                path,
+                config.clone().static_content(None),
+                move |request| {
+                    if !matches!(
+                        request.method(),
+                        Ok(http::Method::GET) | Ok(http::Method::HEAD)
+                    ) {
+                        return (
+                            HttpResponse::new(StatusCode::METHOD_NOT_ALLOWED)
+                                .header("Allow", "GET, HEAD"),
+                            None,
+                        );
+                    }
+                    let cached = cache.borrow_mut().get(&file_path).cloned();
+                    let content = match cached {
+                        Some(content) => content,
+                        None => match read_vfs_file(&file_path, &file_path, timeout) {
+                            Ok(content) => {
+                                cache
+                                    .borrow_mut()
+                                    .insert(file_path.clone(), content.clone());
+                                content
+                            }
+                            Err(_) => return (HttpResponse::not_found(), None),
+                        },
+                    };
+                    static_response(request, &content, etag, ranges)
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Keep up to `capacity` files served with [`HttpServer::serve_file_lazy()`]
+    /// in memory, evicting the least recently requested. Zero, the default,
+    /// reads every request from the VFS. Cached files are not refreshed if they
+    /// change in the VFS, except by evicting them, e.g. by setting the capacity
+    /// to zero and back.
+    pub fn set_lazy_file_cache(&mut self, capacity: usize) {
+        self.lazy_file_cache.borrow_mut().set_capacity(capacity);
+    }
+
+    /// Serve a file from the given absolute directory, along with precompressed
+    /// versions of it, e.g. `app.js.gz` and `app.js.br` for `app.js`.
+    ///
+    /// Requests are answered by this process, in [`HttpServer::handle_request()`]
+    /// or [`HttpServer::serve()`], with a version the client accepts according
+    /// to its `Accept-Encoding` header, preferring Brotli to gzip and falling back
+    /// to the uncompressed file. If the config sets
+    /// [`HttpBindingConfig::etag()`], each version is served with its own ETag,
+    /// and if it sets [`HttpBindingConfig::ranges()`], ranges are of the version served.
+    pub fn serve_precompressed_file_raw_path(
+        &mut self,
+        file_path: &str,
+        encodings: &[ContentEncoding],
+        paths: Vec<&str>,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        let identity = self.read_vfs_file(file_path, file_path)?;
+        let mut variants = vec![];
+        for encoding in encodings {
+            let variant_path = format!("{}.{}", file_path, encoding.extension());
+            variants.push((*encoding, self.read_vfs_file(&variant_path, file_path)?));
+        }
+        variants.sort_by_key(|(encoding, _)| *encoding);
+        let variants = Rc::new(variants);
+        let (etag, ranges) = (config.etag, config.ranges);
+
+        for path in paths {
+            let identity = identity.clone();
+            let variants = variants.clone();
+            self.bind_internal_http_path(
+                path,
+                config.clone().static_content(None),
+                move |request| {
+                    let (encoding, content) = variants
+                        .iter()
+                        .find(|(encoding, _)| request.accepts_encoding(encoding.as_str()))
+                        .map(|(encoding, blob)| (Some(*encoding), blob.clone()))
+                        .unwrap_or((None, identity.clone()));
+                    let (mut response, blob) = static_response(request, &content, etag, ranges);
+                    if let (Some(encoding), Some(_)) = (encoding, &blob) {
+                        response = response.header("Content-Encoding", encoding.as_str());
+                    }
+                    (response.header("Vary", "Accept-Encoding"), blob)
+                },
+            )?;
         }
 
         Ok(())
@@ -883,6 +2221,10 @@ impl HttpServer {
     ///
     /// The directory is relative to the `pkg` folder within this package's drive.
     ///
+    /// To serve files with precompressed siblings, e.g. `app.js` next to `app.js.br`
+    /// or `app.js.gz`, according to the `Accept-Encoding` of each request, use
+    /// [`HttpServer::serve_ui_with()`] and [`UiOptions::precompressed()`].
+    ///
     /// The config `static_content` field will be ignored in favor of the files' contents.
     /// An error will be returned if the file does not exist.
     pub fn serve_ui(
@@ -890,6 +2232,18 @@ impl HttpServer {
         directory: &str,
         roots: Vec<&str>,
         config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        self.serve_ui_with(directory, roots, config, UiOptions::default())
+    }
+
+    /// Serve static files from a given directory, as [`HttpServer::serve_ui()`]
+    /// does, with the given options.
+    pub fn serve_ui_with(
+        &mut self,
+        directory: &str,
+        roots: Vec<&str>,
+        config: HttpBindingConfig,
+        options: UiOptions,
     ) -> Result<(), HttpServerError> {
         let our = crate::our();
         let initial_path = format!("{}/pkg/{}", our.package_id(), directory);
@@ -920,27 +2274,45 @@ impl HttpServer {
                 return Err(HttpServerError::UnexpectedResponse);
             };
 
-            for entry in directory_info {
+            let files: HashSet<&str> = directory_info
+                .iter()
+                .filter(|entry| entry.file_type == FileType::File)
+                .map(|entry| entry.path.as_str())
+                .collect();
+
+            for entry in &directory_info {
                 match entry.file_type {
                     FileType::Directory => {
                         // push the directory onto the queue
-                        queue.push_back(entry.path);
+                        queue.push_back(entry.path.clone());
                     }
                     FileType::File => {
                         // if it's a file, serve it statically at its path
                         // if it's `index.html`, serve additionally as the root
+                        let relative_path = entry.path.replace(&initial_path, "");
+                        let mut paths = vec![relative_path.as_str()];
                         if entry.path.ends_with("index.html") {
-                            for root in &roots {
-                                self.serve_file_raw_path(
-                                    &entry.path,
-                                    vec![root, &entry.path.replace(&initial_path, "")],
-                                    config.clone(),
-                                )?;
-                            }
+                            paths.extend(roots.iter().copied());
+                        }
+                        // if it has precompressed siblings, serve those too
+                        let encodings: Vec<ContentEncoding> =
+                            [ContentEncoding::Br, ContentEncoding::Gzip]
+                                .into_iter()
+                                .filter(|encoding| {
+                                    files.contains(
+                                        format!("{}.{}", entry.path, encoding.extension()).as_str(),
+                                    )
+                                })
+                                .collect();
+                        if options.lazy {
+                            self.serve_file_lazy_raw_path(&entry.path, paths, config.clone())?;
+                        } else if encodings.is_empty() || !options.precompressed {
+                            self.serve_file_raw_path(&entry.path, paths, config.clone())?;
                         } else {
-                            self.serve_file_raw_path(
+                            self.serve_precompressed_file_raw_path(
                                 &entry.path,
-                                vec![&entry.path.replace(&initial_path, "")],
+                                &encodings,
+                                paths,
                                 config.clone(),
                             )?;
                         }
@@ -952,22 +2324,349 @@ impl HttpServer {
             }
         }
 
+        if options.spa_fallback {
+            let index_path = format!("{}/index.html", initial_path);
+            let index = self.read_vfs_file(&index_path, &index_path)?;
+            for root in &roots {
+                let fallback_path = format!("{}/*path", root.trim_end_matches('/'));
+                let index = index.clone();
+                self.bind_internal_http_path(
+                    &fallback_path,
+                    config.clone().static_content(None),
+                    move |request| spa_fallback_response(request, &index),
+                )?;
+            }
+        }
+
         Ok(())
     }
 
-    /// Handle a WebSocket open event from the HTTP server.
-    pub fn handle_websocket_open(&mut self, path: &str, channel_id: u32) {
-        self.ws_channels
-            .entry(path.to_string())
-            .or_insert(HashSet::new())
-            .insert(channel_id);
+    /// Bind a path that the server answers itself in [`HttpServer::handle_request()`],
+    /// without passing requests to it on to the given HTTP handler.
+    fn bind_internal_http_path<F>(
+        &mut self,
+        path: &str,
+        config: HttpBindingConfig,
+        handler: F,
+    ) -> Result<(), HttpServerError>
+    where
+        F: Fn(&IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        self.bind_http_path(path, config)?;
+        self.internal_http_handlers
+            .insert(path.to_string(), InternalHttpHandler(Rc::new(handler)));
+        Ok(())
+    }
+
+    /// Start collecting metrics of the HTTP traffic handled by
+    /// [`HttpServer::handle_request()`] or [`HttpServer::serve()`]: requests and
+    /// response latency per bound path, responses per status code, and open
+    /// WebSocket channels. Read them with [`HttpServer::metrics()`].
+    ///
+    /// Requests that the handler given to [`HttpServer::serve()`] responds to itself,
+    /// by returning `None`, are not counted.
+    pub fn enable_metrics(&mut self) {
+        if self.metrics.is_none() {
+            self.metrics = Some(Rc::new(RefCell::new(HttpMetrics::default())));
+            self.record_ws_channels();
+        }
+    }
+
+    /// A snapshot of the metrics collected since [`HttpServer::enable_metrics()`],
+    /// or `None` if metrics are not enabled.
+    pub fn metrics(&self) -> Option<HttpMetrics> {
+        self.metrics
+            .as_ref()
+            .map(|metrics| metrics.borrow().clone())
+    }
+
+    /// Serve the metrics of this server at the given path, in the Prometheus text
+    /// format, enabling them if needed. Requests to the path are answered by
+    /// [`HttpServer::handle_request()`] or [`HttpServer::serve()`].
+    ///
+    /// Scrapers usually cannot log in, so consider an unauthenticated,
+    /// local-only binding, e.g. `HttpBindingConfig::new(false, true, false, None)`.
+    pub fn bind_metrics(
+        &mut self,
+        path: &str,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        self.enable_metrics();
+        let metrics = self.metrics.clone().unwrap();
+        self.bind_internal_http_path(path, config.static_content(None), move |_request| {
+            (
+                HttpResponse::new(StatusCode::OK)
+                    .header("Content-Type", "text/plain; version=0.0.4"),
+                Some(KiBlob::new(
+                    Some("text/plain"),
+                    metrics.borrow().render().into_bytes(),
+                )),
+            )
+        })
+    }
+
+    /// Answer requests for paths under `prefix` that match no other bound path
+    /// with `page`, as `404 Not Found`, rather than with the runtime's generic error.
+    ///
+    /// Binds `{prefix}/*path`, so it cannot be combined with
+    /// [`UiOptions::spa_fallback()`] on the same root. Requests are answered by
+    /// [`HttpServer::handle_request()`] or [`HttpServer::serve()`] without calling
+    /// the given handler.
+    pub fn bind_not_found_page(
+        &mut self,
+        prefix: &str,
+        config: HttpBindingConfig,
+        page: ErrorPage,
+    ) -> Result<(), HttpServerError> {
+        let path = format!("{}/*path", prefix.trim_end_matches('/'));
+        self.bind_internal_http_path(&path, config.static_content(None), move |request| {
+            page.response(request, StatusCode::NOT_FOUND)
+        })
+    }
+
+    /// Answer with `page`, as `500 Internal Server Error`, when the HTTP handler
+    /// given to [`HttpServer::handle_request()`] or [`HttpServer::serve()`] returns
+    /// a `500 Internal Server Error` without a body.
+    ///
+    /// Panics are not caught: processes built for `wasm32-wasip1` abort on panic,
+    /// so a handler should return its errors as responses instead.
+    pub fn set_error_page(&mut self, page: ErrorPage) {
+        self.error_page = Some(page);
+    }
+
+    /// The bound path of a request, to label its metrics with, if metrics are enabled.
+    fn metrics_path(&self, request: &IncomingHttpRequest) -> Option<String> {
+        self.metrics.as_ref()?;
+        Some(
+            request
+                .bound_path(Some(&crate::our().process.to_string()))
+                .to_string(),
+        )
+    }
+
+    fn record_response(&self, path: Option<String>, status: u16, started: Instant) {
+        let (Some(metrics), Some(path)) = (&self.metrics, path) else {
+            return;
+        };
+        let mut metrics = metrics.borrow_mut();
+        *metrics.requests.entry(path.clone()).or_default() += 1;
+        *metrics.statuses.entry(status).or_default() += 1;
+        let secs = started.elapsed().as_secs_f64();
+        let latency = metrics.latency.entry(path).or_default();
+        latency.count += 1;
+        latency.total_secs += secs;
+        latency.max_secs = latency.max_secs.max(secs);
+    }
+
+    fn record_ws_channels(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.borrow_mut().ws_channels = self.ws_channels.values().map(HashSet::len).sum();
+        }
+    }
+
+    /// Serve a healthcheck at the given path: each request to it is answered
+    /// with the [`HealthStatus`] returned by `status`, as JSON, by
+    /// [`HttpServer::handle_request()`].
+    ///
+    /// Responds `200 OK` if the process is live and ready, and
+    /// `503 Service Unavailable` otherwise. If `status` does not set
+    /// `uptime_secs`, it is filled in with the time since this call.
+    ///
+    /// The path is bound unauthenticated but local-only, so it can be polled by
+    /// monitoring on the node without a login cookie.
+    pub fn bind_healthcheck<F>(&mut self, path: &str, status: F) -> Result<(), HttpServerError>
+    where
+        F: Fn() -> HealthStatus + 'static,
+    {
+        let started = Instant::now();
+        self.bind_internal_http_path(
+            path,
+            HttpBindingConfig::new(false, true, false, None),
+            move |_request| {
+                let mut health = status();
+                if health.uptime_secs.is_none() {
+                    health.uptime_secs = Some(started.elapsed().as_secs());
+                }
+                let status = if health.live && health.ready {
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                };
+                (
+                    HttpResponse::new(status).header("Content-Type", "application/json"),
+                    Some(KiBlob::new(
+                        Some("application/json"),
+                        serde_json::to_vec(&health).unwrap(),
+                    )),
+                )
+            },
+        )
+    }
+
+    /// Serve a directory of static files embedded into the process at compile time,
+    /// binding each file at its path within the directory. `index.html` files are
+    /// additionally bound at the given roots, as in [`HttpServer::serve_ui()`].
+    ///
+    /// Use the [`crate::serve_embedded_ui!`] macro rather than calling this directly.
+    ///
+    /// The config `static_content` field will be ignored in favor of the files' contents.
+    pub fn serve_embedded_ui(
+        &mut self,
+        directory: &include_dir::Dir<'static>,
+        roots: Vec<&str>,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(directory);
+
+        while let Some(directory) = queue.pop_front() {
+            queue.extend(directory.dirs());
+            for file in directory.files() {
+                let path = format!("/{}", file.path().to_string_lossy());
+                let blob = KiBlob::new(Some(get_mime_type(&path)), file.contents());
+                let config = config.clone().static_content(Some(blob));
+                if path.ends_with("index.html") {
+                    for root in &roots {
+                        self.bind_http_path(*root, config.clone())?;
+                    }
+                }
+                self.bind_http_path(path, config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Register a new path with the HTTP server to serve Server-Sent Events from,
+    /// configured using [`HttpBindingConfig`]. `secure_subdomain` and `static_content`
+    /// are ignored.
+    ///
+    /// Clients that connect are tracked as channels on the path, to which events
+    /// can be pushed with [`SseChannel::send()`] or [`HttpServer::sse_push_all_channels()`].
+    /// The library keeps idle connections open by sending a comment to every channel
+    /// every [`SSE_KEEPALIVE_INTERVAL_MS`], driven by a timer that is handled by
+    /// [`HttpServer::serve()`], or by [`HttpServer::handle_sse_keepalive()`] in a
+    /// hand-written message loop.
+    ///
+    /// Requires a runtime whose `http-server:distro:sys` supports [`HttpServerAction::SseBind`].
+    pub fn bind_sse_path<T>(
+        &mut self,
+        path: T,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError>
+    where
+        T: Into<String>,
+    {
+        let path: String = path.into();
+        let res = KiRequest::to(("our", "http-server", "distro", "sys"))
+            .body(
+                serde_json::to_vec(&HttpServerAction::SseBind {
+                    path: path.clone(),
+                    authenticated: config.authenticated,
+                    local_only: config.local_only,
+                })
+                .unwrap(),
+            )
+            .send_and_await_response(self.timeout);
+        let Ok(Message::Response { body, .. }) = res.unwrap() else {
+            return Err(HttpServerError::Timeout);
+        };
+        let Ok(resp) = serde_json::from_slice::<Result<(), HttpServerError>>(&body) else {
+            return Err(HttpServerError::UnexpectedResponse);
+        };
+        if resp.is_ok() {
+            self.sse_paths.insert(path, config);
+            if !self.sse_keepalive_started {
+                self.sse_keepalive_started = true;
+                set_timer(
+                    SSE_KEEPALIVE_INTERVAL_MS,
+                    Some(SSE_KEEPALIVE_CONTEXT.to_vec()),
+                );
+            }
+        }
+        resp
+    }
+
+    /// Handle a Server-Sent Events open event from the HTTP server.
+    pub fn handle_sse_open(&mut self, path: &str, channel_id: u32) {
+        self.sse_channels
+            .entry(path.to_string())
+            .or_default()
+            .insert(channel_id);
+    }
+
+    /// Handle a Server-Sent Events close event from the HTTP server.
+    pub fn handle_sse_close(&mut self, channel_id: u32) {
+        self.sse_channels.iter_mut().for_each(|(_, channels)| {
+            channels.remove(&channel_id);
+        });
+    }
+
+    /// The open Server-Sent Events channels on a path.
+    pub fn sse_channels(&self, path: &str) -> Vec<SseChannel> {
+        self.sse_channels
+            .get(path)
+            .map(|channels| {
+                channels
+                    .iter()
+                    .map(|channel_id| SseChannel {
+                        channel_id: *channel_id,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Push an event to all Server-Sent Events channels on a given path.
+    pub fn sse_push_all_channels(&self, path: &str, event: &SseEvent) {
+        for channel in self.sse_channels(path) {
+            channel.send(event);
+        }
+    }
+
+    /// If `message` is the timer response driving SSE keep-alives, send a comment
+    /// to every open Server-Sent Events channel, set the next timer, and return `true`.
+    /// Otherwise, return `false` and leave the message to the caller.
+    ///
+    /// Only needed in message loops that do not use [`HttpServer::serve()`].
+    pub fn handle_sse_keepalive(&mut self, message: &Message) -> bool {
+        if message.is_request()
+            || !message.is_process("timer:distro:sys")
+            || message.context() != Some(SSE_KEEPALIVE_CONTEXT)
+        {
+            return false;
+        }
+        for channel_id in self.sse_channels.values().flatten() {
+            send_sse_push(*channel_id, SSE_KEEPALIVE_FRAME.as_bytes().to_vec());
+        }
+        set_timer(
+            SSE_KEEPALIVE_INTERVAL_MS,
+            Some(SSE_KEEPALIVE_CONTEXT.to_vec()),
+        );
+        true
+    }
+
+    /// Handle a WebSocket open event from the HTTP server.
+    pub fn handle_websocket_open(&mut self, path: &str, channel_id: u32) {
+        self.ws_channels
+            .entry(path.to_string())
+            .or_insert(HashSet::new())
+            .insert(channel_id);
+        self.record_ws_channels();
     }
 
     /// Handle a WebSocket close event from the HTTP server.
+    /// The channel also leaves any rooms it has joined, and its data is removed.
     pub fn handle_websocket_close(&mut self, channel_id: u32) {
         self.ws_channels.iter_mut().for_each(|(_, channels)| {
             channels.remove(&channel_id);
         });
+        self.ws_channel_data.remove(&channel_id);
+        self.record_ws_channels();
+        self.ws_rooms.retain(|_, channels| {
+            channels.remove(&channel_id);
+            !channels.is_empty()
+        });
     }
 
     pub fn parse_request(&self, body: &[u8]) -> Result<HttpServerRequest, HttpServerError> {
@@ -983,15 +2682,29 @@ impl HttpServer {
         mut http_handler: impl FnMut(IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>),
         mut ws_handler: impl FnMut(u32, WsMessageType, KiBlob),
     ) {
+        self.forget_dropped_paths();
         match server_request {
-            HttpServerRequest::Http(http_request) => {
-                let (response, blob) = http_handler(http_request);
-                let response = KiResponse::new().body(serde_json::to_vec(&response).unwrap());
-                if let Some(blob) = blob {
-                    response.blob(blob).send().unwrap();
-                } else {
-                    response.send().unwrap();
-                }
+            HttpServerRequest::Http(mut http_request) => {
+                let started = Instant::now();
+                let path = self.metrics_path(&http_request);
+                let (response, blob) = match self.check_limits(&mut http_request) {
+                    Err(limited) => limited,
+                    // the spilled body, if any, is removed once handled
+                    Ok(_spilled) => match (
+                        self.internal_http_handler(&http_request),
+                        self.error_page.clone(),
+                    ) {
+                        (Some(handler), _) => (handler.0)(&http_request),
+                        (None, None) => http_handler(http_request),
+                        (None, Some(page)) => {
+                            let request = http_request.clone();
+                            let (response, blob) = http_handler(http_request);
+                            page.fill(&request, response, blob)
+                        }
+                    },
+                };
+                self.record_response(path, response.status, started);
+                respond(response, blob);
             }
             HttpServerRequest::WebSocketPush {
                 channel_id,
@@ -1003,18 +2716,368 @@ impl HttpServer {
             HttpServerRequest::WebSocketClose(channel_id) => {
                 self.handle_websocket_close(channel_id);
             }
+            HttpServerRequest::SseOpen { path, channel_id } => {
+                self.handle_sse_open(&path, channel_id);
+            }
+            HttpServerRequest::SseClose(channel_id) => {
+                self.handle_sse_close(channel_id);
+            }
+        }
+    }
+
+    fn internal_http_handler(&self, request: &IncomingHttpRequest) -> Option<InternalHttpHandler> {
+        self.internal_http_handlers
+            .get(request.bound_path(Some(&crate::our().process.to_string())))
+            .cloned()
+    }
+
+    /// If an HTTP request is over the rate limit of the path it was made on, lacks
+    /// an API key it requires, or is over its body size limit, the response to
+    /// send instead of handling it. Otherwise, the temporary file its body was
+    /// spilled to, if any, to keep until it is handled.
+    fn check_limits(
+        &mut self,
+        request: &mut IncomingHttpRequest,
+    ) -> Result<Option<TempFile>, (HttpResponse, Option<KiBlob>)> {
+        let limited = self
+            .check_rate_limit(request)
+            .map(HttpResponse::into_parts)
+            .or_else(|| self.check_api_key(request))
+            .or_else(|| self.check_body_size(request).map(HttpResponse::into_parts));
+        match limited {
+            Some(limited) => Err(limited),
+            None => self.spill_body(request).map_err(HttpResponse::into_parts),
+        }
+    }
+
+    /// If an HTTP request lacks a valid API key and the path it was made on
+    /// requires one, the `401 Unauthorized` response to send instead of handling it.
+    fn check_api_key(
+        &self,
+        request: &IncomingHttpRequest,
+    ) -> Option<(HttpResponse, Option<KiBlob>)> {
+        let path = request.bound_path(Some(&crate::our().process.to_string()));
+        if !self.http_paths.get(path)?.require_api_key {
+            return None;
+        }
+        self.api_keys
+            .check(request)
+            .err()
+            .map(HttpResponseWithBody::into_parts)
+    }
+
+    /// Set the keys accepted on paths bound with
+    /// [`HttpBindingConfig::require_api_key()`].
+    pub fn set_api_keys(&mut self, api_keys: ApiKeys) {
+        self.api_keys = api_keys;
+    }
+
+    pub fn api_keys(&self) -> &ApiKeys {
+        &self.api_keys
+    }
+
+    /// The accepted API keys, to generate, rotate or revoke keys in place.
+    pub fn api_keys_mut(&mut self) -> &mut ApiKeys {
+        &mut self.api_keys
+    }
+
+    /// If the body of an HTTP request is larger than allowed on the path it was
+    /// made on, and the path doesn't spill it to the VFS, the `413 Payload Too Large`
+    /// response to send instead of handling it.
+    fn check_body_size(&self, request: &IncomingHttpRequest) -> Option<HttpResponse> {
+        match self.body_over_limit(request)? {
+            (true, false) => Some(HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE)),
+            _ => None,
+        }
+    }
+
+    /// If the body of an HTTP request is larger than allowed on the path it was
+    /// made on, and the path spills it to the VFS, write it to a temporary file
+    /// and point the request to it, returning the file. If writing fails, the
+    /// `500 Internal Server Error` response to send instead of handling it.
+    fn spill_body(
+        &self,
+        request: &mut IncomingHttpRequest,
+    ) -> Result<Option<TempFile>, HttpResponse> {
+        if self.body_over_limit(request) != Some((true, true)) {
+            return Ok(None);
+        }
+        let file = temp_file_from_blob("", Some(self.timeout))
+            .map_err(|_| HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR))?;
+        request.spilled_body = Some(file.path.clone());
+        Ok(Some(file))
+    }
+
+    /// Whether the body of an HTTP request is larger than allowed on the path it was
+    /// made on, and whether the path spills such bodies to the VFS, or `None` if the
+    /// path has no body size limit.
+    fn body_over_limit(&self, request: &IncomingHttpRequest) -> Option<(bool, bool)> {
+        let path = request.bound_path(Some(&crate::our().process.to_string()));
+        let config = self.http_paths.get(path)?;
+        let max_body_size = config.max_body_size?;
+        let content_length = request
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok()?.trim().parse::<u64>().ok());
+        let body_size = match content_length {
+            Some(length) => length,
+            None => get_blob().map_or(0, |blob| blob.bytes.len() as u64),
+        };
+        Some((body_size > max_body_size, config.spill_to_vfs))
+    }
+
+    /// If an HTTP request is over the rate limit of the path it was made on,
+    /// the `429 Too Many Requests` response to send instead of handling it.
+    fn check_rate_limit(&mut self, request: &IncomingHttpRequest) -> Option<HttpResponse> {
+        let path = request.bound_path(Some(&crate::our().process.to_string()));
+        let (limit, by) = self.http_paths.get(path)?.rate_limit.clone()?;
+        let limiter = self.rate_limiters.get_mut(path)?;
+        match limiter.check(&by.key(request)?) {
+            Decision::Process => None,
+            Decision::Defer(_) | Decision::Drop => {
+                let response = HttpResponse::new(StatusCode::TOO_MANY_REQUESTS);
+                if limit.per_second > 0.0 {
+                    let retry_after = (1.0 / limit.per_second).ceil() as u64;
+                    Some(response.header("Retry-After", retry_after.to_string()))
+                } else {
+                    Some(response)
+                }
+            }
         }
     }
 
+    /// Run the message loop of a process serving HTTP and WebSockets: wait for
+    /// messages, parse requests from `http-server:distro:sys`, keep track of open
+    /// WebSocket channels, and pass each event to `handler` along with the server
+    /// and `state`. Never returns.
+    ///
+    /// For [`ServerEvent::Http`], the response returned by `handler` is sent.
+    /// If it returns `None`, no response is sent, so the handler must have responded
+    /// itself, e.g. with [`send_response()`]. For other events, the return value is ignored.
+    ///
+    /// Paths bound internally, e.g. with [`HttpServer::bind_healthcheck()`], are
+    /// answered without calling `handler`, as are requests over the rate limit or
+    /// body size limit of their path or without an API key it requires, and
+    /// Server-Sent Events keep-alives are sent.
+    /// If an error page is set with [`HttpServer::set_error_page()`], it is sent
+    /// when `handler` returns a `500 Internal Server Error` without a body.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{our, http::server::{HttpResponse, HttpServer, ServerEvent}};
+    ///
+    /// let mut server = HttpServer::new(5);
+    /// let mut hits = 0u64;
+    /// server.serve(&our(), &mut hits, |_server, hits, event| match event {
+    ///     ServerEvent::Http(_request) => {
+    ///         *hits += 1;
+    ///         Some((HttpResponse::new(200u16), None))
+    ///     }
+    ///     _ => None,
+    /// });
+    /// ```
+    pub fn serve<S, F>(&mut self, our: &Address, state: &mut S, mut handler: F) -> !
+    where
+        F: FnMut(&mut HttpServer, &mut S, ServerEvent) -> Option<(HttpResponse, Option<KiBlob>)>,
+    {
+        loop {
+            self.forget_dropped_paths();
+            let message = match await_message() {
+                Ok(message) => message,
+                Err(send_error) => {
+                    handler(self, state, ServerEvent::SendError(send_error));
+                    continue;
+                }
+            };
+            if self.handle_sse_keepalive(&message) {
+                continue;
+            }
+            if !message.is_request()
+                || message.source().node != our.node
+                || !message.is_process("http-server:distro:sys")
+            {
+                handler(self, state, ServerEvent::Message(message));
+                continue;
+            }
+            let Ok(request) = self.parse_request(message.body()) else {
+                handler(self, state, ServerEvent::Message(message));
+                continue;
+            };
+            match request {
+                HttpServerRequest::Http(mut http_request) => {
+                    let started = Instant::now();
+                    let path = self.metrics_path(&http_request);
+                    // the spilled body, if any, is removed once handled
+                    let spilled = self.check_limits(&mut http_request);
+                    let result = if let Err(limited) = spilled {
+                        Some(limited)
+                    } else if let Some(internal_handler) = self.internal_http_handler(&http_request)
+                    {
+                        Some((internal_handler.0)(&http_request))
+                    } else if let Some(page) = self.error_page.clone() {
+                        let request = http_request.clone();
+                        handler(self, state, ServerEvent::Http(http_request))
+                            .map(|(response, blob)| page.fill(&request, response, blob))
+                    } else {
+                        handler(self, state, ServerEvent::Http(http_request))
+                    };
+                    if let Some((response, blob)) = result {
+                        self.record_response(path, response.status, started);
+                        respond(response, blob);
+                    }
+                }
+                HttpServerRequest::WebSocketPush {
+                    channel_id,
+                    message_type,
+                } => {
+                    let event = ServerEvent::WsPush {
+                        channel_id,
+                        message_type,
+                        blob: last_blob().unwrap_or_default(),
+                    };
+                    handler(self, state, event);
+                }
+                HttpServerRequest::WebSocketOpen { path, channel_id } => {
+                    self.handle_websocket_open(&path, channel_id);
+                    handler(self, state, ServerEvent::WsOpen { path, channel_id });
+                }
+                HttpServerRequest::WebSocketClose(channel_id) => {
+                    self.handle_websocket_close(channel_id);
+                    handler(self, state, ServerEvent::WsClose(channel_id));
+                }
+                HttpServerRequest::SseOpen { path, channel_id } => {
+                    self.handle_sse_open(&path, channel_id);
+                    let channel = SseChannel { channel_id };
+                    handler(self, state, ServerEvent::SseOpen { path, channel });
+                }
+                HttpServerRequest::SseClose(channel_id) => {
+                    self.handle_sse_close(channel_id);
+                    handler(self, state, ServerEvent::SseClose(channel_id));
+                }
+            }
+        }
+    }
+
+    /// Bind every path registered on a [`Router`] with the same configuration.
+    /// All paths must be bound successfully, or none will be bound, as in
+    /// [`HttpServer::bind_multiple_http_paths()`].
+    pub fn bind_router<S>(
+        &mut self,
+        router: &Router<S>,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        self.bind_multiple_http_paths(router.paths(), config)
+    }
+
     /// Push a WebSocket message to all channels on a given path.
     pub fn ws_push_all_channels(&self, path: &str, message_type: WsMessageType, blob: KiBlob) {
         ws_push_all_channels(&self.ws_channels, path, message_type, blob);
     }
 
+    /// Push a WebSocket message to all channels on a given path except those in
+    /// `exclude`, e.g. to broadcast a message to everyone but its sender.
+    pub fn ws_push_all_channels_except(
+        &self,
+        path: &str,
+        exclude: &[u32],
+        message_type: WsMessageType,
+        blob: KiBlob,
+    ) {
+        self.ws_push_channels_where(path, message_type, blob, |channel_id| {
+            !exclude.contains(&channel_id)
+        });
+    }
+
+    /// Push a WebSocket message to the channels on a given path for which
+    /// `predicate` returns `true`, e.g. those whose data, from
+    /// [`HttpServer::get_channel_data()`], matches.
+    pub fn ws_push_channels_where<F>(
+        &self,
+        path: &str,
+        message_type: WsMessageType,
+        blob: KiBlob,
+        mut predicate: F,
+    ) where
+        F: FnMut(u32) -> bool,
+    {
+        if let Some(channels) = self.ws_channels.get(path) {
+            channels
+                .iter()
+                .filter(|channel_id| predicate(**channel_id))
+                .for_each(|channel_id| {
+                    send_ws_push(*channel_id, message_type, blob.clone());
+                });
+        }
+    }
+
     pub fn get_ws_channels(&self) -> HashMap<String, HashSet<u32>> {
         self.ws_channels.clone()
     }
 
+    /// Attach data to a WebSocket channel, e.g. the user or document it is for,
+    /// replacing any data attached before. The data is removed when the channel closes.
+    pub fn set_channel_data<T>(&mut self, channel_id: u32, data: T) -> Result<(), serde_json::Error>
+    where
+        T: Serialize,
+    {
+        self.ws_channel_data
+            .insert(channel_id, serde_json::to_value(data)?);
+        Ok(())
+    }
+
+    /// The data attached to a WebSocket channel, if there is any and it
+    /// deserializes to `T`.
+    pub fn get_channel_data<T>(&self, channel_id: u32) -> Option<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let data = self.ws_channel_data.get(&channel_id)?;
+        T::deserialize(data).ok()
+    }
+
+    /// Remove the data attached to a WebSocket channel.
+    pub fn remove_channel_data(&mut self, channel_id: u32) {
+        self.ws_channel_data.remove(&channel_id);
+    }
+
+    /// Add an open WebSocket channel to a room, creating the room if needed.
+    /// A channel may be in any number of rooms, and leaves them all when it closes.
+    pub fn join_room(&mut self, channel_id: u32, room: &str) {
+        self.ws_rooms
+            .entry(room.to_string())
+            .or_default()
+            .insert(channel_id);
+    }
+
+    /// Remove a WebSocket channel from a room. A room is removed when its last
+    /// channel leaves.
+    pub fn leave_room(&mut self, channel_id: u32, room: &str) {
+        if let Some(channels) = self.ws_rooms.get_mut(room) {
+            channels.remove(&channel_id);
+            if channels.is_empty() {
+                self.ws_rooms.remove(room);
+            }
+        }
+    }
+
+    /// Push a WebSocket message to all channels in a given room.
+    pub fn ws_push_room(&self, room: &str, message_type: WsMessageType, blob: KiBlob) {
+        ws_push_all_channels(&self.ws_rooms, room, message_type, blob);
+    }
+
+    /// The channels in a given room, if it has any.
+    pub fn room_channels(&self, room: &str) -> Option<&HashSet<u32>> {
+        self.ws_rooms.get(room)
+    }
+
+    /// The rooms a given WebSocket channel has joined.
+    pub fn channel_rooms(&self, channel_id: u32) -> impl Iterator<Item = &str> {
+        self.ws_rooms
+            .iter()
+            .filter(move |(_, channels)| channels.contains(&channel_id))
+            .map(|(room, _)| room.as_str())
+    }
+
     /// Register multiple paths with the HTTP server using the same configuration.
     /// The security setting is determined by the `secure_subdomain` field in `HttpBindingConfig`.
     /// All paths must be bound successfully, or none will be bound. If any path
@@ -1029,10 +3092,7 @@ impl HttpServer {
 
         for path in paths {
             let path_str = path.into();
-            let result = match config.secure_subdomain {
-                true => self.secure_bind_http_path(path_str.clone()),
-                false => self.bind_http_path(path_str.clone(), config.clone()),
-            };
+            let result = self.bind_http_path(path_str.clone(), config.clone());
 
             match result {
                 // If binding succeeds, add the path to the list of bound paths
@@ -1051,15 +3111,526 @@ impl HttpServer {
     }
 }
 
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum PathPatternError {
+    #[error("path pattern must start with '/': {0}")]
+    NoLeadingSlash(String),
+    #[error("parameter without a name in path pattern {0}")]
+    EmptyName(String),
+    #[error("wildcard must be the last segment of path pattern {0}")]
+    WildcardNotLast(String),
+    #[error("parameter {1} appears twice in path pattern {0}")]
+    DuplicateName(String, String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PatternSegment {
+    Literal(String),
+    /// `:name`, matching one segment.
+    Param(String),
+    /// `*name`, matching the rest of the path.
+    Wildcard(String),
+}
+
+/// A path to bind, with parameters such as `:id` and a trailing wildcard such as
+/// `*rest`, in the syntax `http-server:distro:sys` matches requests against.
+///
+/// Binding a `PathPattern` rather than a plain string checks its syntax up front,
+/// and [`PathPattern::matches()`] gives the parameters of a path without a request,
+/// e.g. to dispatch requests that arrive on a wildcard binding.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::http::server::PathPattern;
+///
+/// let pattern = PathPattern::new("/files/:owner/*rest").unwrap();
+/// let params = pattern.matches("/files/alice/photos/cat.png").unwrap();
+/// assert_eq!(params["owner"], "alice");
+/// assert_eq!(params["rest"], "photos/cat.png");
+/// assert!(pattern.matches("/files").is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathPattern {
+    pattern: String,
+    segments: Vec<PatternSegment>,
+}
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Result<Self, PathPatternError> {
+        let Some(rest) = pattern.strip_prefix('/') else {
+            return Err(PathPatternError::NoLeadingSlash(pattern.to_string()));
+        };
+        let mut segments = vec![];
+        let mut names = HashSet::new();
+        let parts: Vec<&str> = rest.split('/').collect();
+        for (i, part) in parts.iter().enumerate() {
+            let segment = if let Some(name) = part.strip_prefix(':') {
+                PatternSegment::Param(name.to_string())
+            } else if let Some(name) = part.strip_prefix('*') {
+                if i != parts.len() - 1 {
+                    return Err(PathPatternError::WildcardNotLast(pattern.to_string()));
+                }
+                PatternSegment::Wildcard(name.to_string())
+            } else {
+                PatternSegment::Literal(part.to_string())
+            };
+            if let PatternSegment::Param(name) | PatternSegment::Wildcard(name) = &segment {
+                if name.is_empty() {
+                    return Err(PathPatternError::EmptyName(pattern.to_string()));
+                }
+                if !names.insert(name.clone()) {
+                    return Err(PathPatternError::DuplicateName(
+                        pattern.to_string(),
+                        name.clone(),
+                    ));
+                }
+            }
+            segments.push(segment);
+        }
+        Ok(Self {
+            pattern: pattern.to_string(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The names of the parameters and wildcard of the pattern, in order.
+    pub fn param_names(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            PatternSegment::Param(name) | PatternSegment::Wildcard(name) => Some(name.as_str()),
+            PatternSegment::Literal(_) => None,
+        })
+    }
+
+    /// If `path` matches the pattern, the values of its parameters. Parameters
+    /// match one non-empty segment, and a wildcard the rest of the path, which
+    /// may be empty.
+    pub fn matches(&self, path: &str) -> Option<HashMap<String, String>> {
+        let parts: Vec<&str> = path.strip_prefix('/')?.split('/').collect();
+        let mut params = HashMap::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PatternSegment::Wildcard(name) => {
+                    let rest = parts.get(i..).map(|rest| rest.join("/"));
+                    params.insert(name.clone(), rest.unwrap_or_default());
+                    return Some(params);
+                }
+                PatternSegment::Literal(literal) => {
+                    if parts.get(i) != Some(&literal.as_str()) {
+                        return None;
+                    }
+                }
+                PatternSegment::Param(name) => {
+                    let part = parts.get(i).filter(|part| !part.is_empty())?;
+                    params.insert(name.clone(), part.to_string());
+                }
+            }
+        }
+        (parts.len() == self.segments.len()).then_some(params)
+    }
+}
+
+impl std::fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl std::str::FromStr for PathPattern {
+    type Err = PathPatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Self::new(pattern)
+    }
+}
+
+impl From<PathPattern> for String {
+    fn from(pattern: PathPattern) -> Self {
+        pattern.pattern
+    }
+}
+
+/// A handler for a route of a [`Router`].
+pub type RouteHandler<S> =
+    Box<dyn FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>)>;
+
+/// Dispatches HTTP requests to handlers registered per method and path.
+///
+/// Paths are bound on the [`HttpServer`] with [`HttpServer::bind_router()`], and may
+/// contain parameters such as `:id`, which `http-server:distro:sys` matches and
+/// handlers read with [`IncomingHttpRequest::url_param()`]. Requests to a bound path
+/// with an unregistered method are answered `405 Method Not Allowed`, and requests
+/// to unregistered paths `404 Not Found`.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{our, http::server::{HttpBindingConfig, HttpResponse, HttpServer, Router, ServerEvent}};
+/// use std::collections::HashMap;
+///
+/// let mut router = Router::<HashMap<u64, String>>::new()
+///     .get("/api/items/:id", |items, request| {
+///         let Ok(id) = request.url_param::<u64>("id") else {
+///             return (HttpResponse::new(400u16), None);
+///         };
+///         match items.get(&id) {
+///             Some(_item) => (HttpResponse::new(200u16), None),
+///             None => (HttpResponse::new(404u16), None),
+///         }
+///     });
+/// let mut server = HttpServer::new(5);
+/// server.bind_router(&router, HttpBindingConfig::default()).unwrap();
+/// server.serve(&our(), &mut HashMap::new(), |_server, items, event| match event {
+///     ServerEvent::Http(request) => Some(router.handle(items, request)),
+///     _ => None,
+/// });
+/// ```
+pub struct Router<S> {
+    routes: Vec<(String, http::Method, RouteHandler<S>)>,
+}
+
+impl<S> Default for Router<S> {
+    fn default() -> Self {
+        Self { routes: vec![] }
+    }
+}
+
+impl<S> std::fmt::Debug for Router<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.routes
+                    .iter()
+                    .map(|(path, method, _)| format!("{} {}", method, path)),
+            )
+            .finish()
+    }
+}
+
+impl<S> Router<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for requests with the given method to the given path.
+    /// A later registration for the same method and path replaces an earlier one.
+    pub fn route<T, F>(mut self, method: http::Method, path: T, handler: F) -> Self
+    where
+        T: Into<String>,
+        F: FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        let path = path.into();
+        self.routes.retain(|(p, m, _)| !(p == &path && m == method));
+        self.routes.push((path, method, Box::new(handler)));
+        self
+    }
+
+    pub fn get<T, F>(self, path: T, handler: F) -> Self
+    where
+        T: Into<String>,
+        F: FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        self.route(http::Method::GET, path, handler)
+    }
+
+    pub fn post<T, F>(self, path: T, handler: F) -> Self
+    where
+        T: Into<String>,
+        F: FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        self.route(http::Method::POST, path, handler)
+    }
+
+    pub fn put<T, F>(self, path: T, handler: F) -> Self
+    where
+        T: Into<String>,
+        F: FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        self.route(http::Method::PUT, path, handler)
+    }
+
+    pub fn patch<T, F>(self, path: T, handler: F) -> Self
+    where
+        T: Into<String>,
+        F: FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        self.route(http::Method::PATCH, path, handler)
+    }
+
+    pub fn delete<T, F>(self, path: T, handler: F) -> Self
+    where
+        T: Into<String>,
+        F: FnMut(&mut S, IncomingHttpRequest) -> (HttpResponse, Option<KiBlob>) + 'static,
+    {
+        self.route(http::Method::DELETE, path, handler)
+    }
+
+    /// The distinct paths with registered routes, in registration order.
+    pub fn paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = vec![];
+        for (path, _, _) in &self.routes {
+            if !paths.contains(&path.as_str()) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    /// Dispatch a request to the handler registered for its method and bound path,
+    /// returning the handler's response.
+    pub fn handle(
+        &mut self,
+        state: &mut S,
+        request: IncomingHttpRequest,
+    ) -> (HttpResponse, Option<KiBlob>) {
+        let path = request
+            .bound_path(Some(&crate::our().process.to_string()))
+            .to_string();
+        let method = request.method().ok();
+        let mut allowed = vec![];
+        for (route_path, route_method, handler) in self.routes.iter_mut() {
+            if route_path != &path {
+                continue;
+            }
+            if Some(&*route_method) == method.as_ref() {
+                return handler(state, request);
+            }
+            allowed.push(route_method.to_string());
+        }
+        if allowed.is_empty() {
+            (HttpResponse::new(StatusCode::NOT_FOUND), None)
+        } else {
+            (
+                HttpResponse::new(StatusCode::METHOD_NOT_ALLOWED)
+                    .header("Allow", allowed.join(", ")),
+                None,
+            )
+        }
+    }
+}
+
+/// A content coding in which static files may be stored precompressed, next to
+/// the uncompressed file, with the coding's file extension appended to its name.
+/// Ordered by preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContentEncoding {
+    /// Brotli, stored as `.br`.
+    Br,
+    /// gzip, stored as `.gz`.
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// The name of the coding in `Accept-Encoding` and `Content-Encoding` headers.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Br => "br",
+            ContentEncoding::Gzip => "gzip",
+        }
+    }
+
+    /// The file extension of files stored in this coding, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContentEncoding::Br => "br",
+            ContentEncoding::Gzip => "gz",
+        }
+    }
+}
+
+/// Compute a strong ETag for some content: a quoted, truncated SHA-256 hash.
+pub fn compute_etag(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let hash = Sha256::digest(content);
+    format!("\"{}\"", hex::encode(&hash[..16]))
+}
+
+/// Answer a GET request for `content` conditionally: with `304 Not Modified` if
+/// the client already has it, according to its ETag, and otherwise with `200 OK`,
+/// the content, its ETag and, if the blob has a MIME type, its `Content-Type`.
+pub fn conditional_response(
+    request: &IncomingHttpRequest,
+    content: KiBlob,
+) -> (HttpResponse, Option<KiBlob>) {
+    static_response(request, &content, true, false)
+}
+
+/// Answer a GET request for `content`, honoring its `Range` header: a single
+/// satisfiable byte range is answered with `206 Partial Content`, that part of
+/// the content and a `Content-Range` header, an unsatisfiable one with
+/// `416 Range Not Satisfiable`, and anything else with `200 OK` and the whole
+/// content. Requests for several ranges at once get the whole content.
+pub fn range_response(
+    request: &IncomingHttpRequest,
+    content: KiBlob,
+) -> (HttpResponse, Option<KiBlob>) {
+    static_response(request, &content, false, true)
+}
+
+/// Answer a GET request for static content, optionally with an ETag, as
+/// [`conditional_response()`] does, and optionally honoring ranges, as
+/// [`range_response()`] does. A range is ignored if the request has an
+/// `If-Range` header that does not match the ETag.
+fn static_response(
+    request: &IncomingHttpRequest,
+    content: &KiBlob,
+    etag: bool,
+    ranges: bool,
+) -> (HttpResponse, Option<KiBlob>) {
+    let etag = etag.then(|| compute_etag(&content.bytes));
+    if let Some(etag) = &etag {
+        if request.etag_matches(etag) {
+            return (HttpResponse::not_modified(etag), None);
+        }
+    }
+    let mut response = HttpResponse::new(StatusCode::OK);
+    if let Some(etag) = &etag {
+        response = response.header("ETag", etag);
+    }
+    if let Some(mime) = &content.mime {
+        response = response.header("Content-Type", mime);
+    }
+    if !ranges {
+        return (response, Some(content.clone()));
+    }
+    response = response.header("Accept-Ranges", "bytes");
+    let headers = request.headers();
+    let if_range_matches = match headers.get(http::header::IF_RANGE) {
+        Some(if_range) => etag.as_deref() == if_range.to_str().ok(),
+        None => true,
+    };
+    let range = headers
+        .get(http::header::RANGE)
+        .and_then(|range| range.to_str().ok())
+        .filter(|_| if_range_matches);
+    let length = content.bytes.len();
+    match range.map(|range| parse_byte_range(range, length)) {
+        Some(Some(Ok((start, end)))) => {
+            response.status = StatusCode::PARTIAL_CONTENT.as_u16();
+            (
+                response.header(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", start, end, length),
+                ),
+                Some(KiBlob::new(
+                    content.mime.clone(),
+                    content.bytes[start..=end].to_vec(),
+                )),
+            )
+        }
+        Some(Some(Err(()))) => (
+            HttpResponse::new(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header("Content-Range", format!("bytes */{}", length)),
+            None,
+        ),
+        _ => (response, Some(content.clone())),
+    }
+}
+
+/// Parse a `Range` header for content of the given length into the inclusive
+/// bounds of a single byte range. Returns `None` for headers that are not a
+/// single byte range, which are ignored, and `Some(Err(()))` for a range that
+/// cannot be satisfied.
+fn parse_byte_range(range: &str, length: usize) -> Option<Result<(usize, usize), ()>> {
+    let spec = range.trim().strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let bounds = if start.is_empty() {
+        // a suffix: the last `end` bytes
+        let suffix = end.parse::<usize>().ok()?;
+        if suffix == 0 || length == 0 {
+            return Some(Err(()));
+        }
+        (length.saturating_sub(suffix), length - 1)
+    } else {
+        let start = start.parse::<usize>().ok()?;
+        let end = if end.is_empty() {
+            usize::MAX
+        } else {
+            end.parse::<usize>().ok()?
+        };
+        if end < start {
+            return None;
+        }
+        if start >= length {
+            return Some(Err(()));
+        }
+        (start, end.min(length - 1))
+    };
+    Some(Ok(bounds))
+}
+
+/// Read a file from the VFS into a blob, with the MIME type of `mime_from`.
+fn read_vfs_file(
+    file_path: &str,
+    mime_from: &str,
+    timeout: u64,
+) -> Result<KiBlob, HttpServerError> {
+    let response = KiRequest::to(("our", "vfs", "distro", "sys"))
+        .body(
+            serde_json::to_vec(&VfsRequest {
+                path: file_path.to_string(),
+                action: VfsAction::Read,
+            })
+            .map_err(|_| HttpServerError::MalformedRequest)?,
+        )
+        .send_and_await_response(timeout)
+        .unwrap()
+        .map_err(|_| HttpServerError::Timeout)?;
+    let Ok(VfsResponse::Read) = serde_json::from_slice::<VfsResponse>(response.body()) else {
+        return Err(HttpServerError::UnexpectedResponse);
+    };
+
+    let Some(mut blob) = get_blob() else {
+        return Err(HttpServerError::NoBlob);
+    };
+
+    blob.mime = Some(get_mime_type(mime_from));
+    Ok(blob)
+}
+
+/// Answer a request on a path with no matching file with the `index.html` of
+/// an app with client-side routing: see [`UiOptions::spa_fallback()`].
+fn spa_fallback_response(
+    request: &IncomingHttpRequest,
+    index: &KiBlob,
+) -> (HttpResponse, Option<KiBlob>) {
+    let is_get = matches!(
+        request.method(),
+        Ok(http::Method::GET) | Ok(http::Method::HEAD)
+    );
+    let path = request.path().unwrap_or_default();
+    let last_segment = path.rsplit('/').next().unwrap_or_default();
+    if !is_get || last_segment.contains('.') {
+        return (HttpResponse::not_found(), None);
+    }
+    (
+        HttpResponse::new(StatusCode::OK).header("Content-Type", "text/html"),
+        Some(index.clone()),
+    )
+}
+
+/// Respond to an incoming HTTP request with the result of an HTTP handler.
+fn respond(response: HttpResponse, blob: Option<KiBlob>) {
+    let response = KiResponse::new().body(serde_json::to_vec(&response).unwrap());
+    if let Some(blob) = blob {
+        response.blob(blob).send().unwrap();
+    } else {
+        response.send().unwrap();
+    }
+}
+
 /// Send an HTTP response to an incoming HTTP request ([`HttpServerRequest::Http`]).
 pub fn send_response(status: StatusCode, headers: Option<HashMap<String, String>>, body: Vec<u8>) {
     KiResponse::new()
         .body(
-            serde_json::to_vec(&HttpResponse {
-                status: status.as_u16(),
-                headers: headers.unwrap_or_default(),
-            })
-            .unwrap(),
+            serde_json::to_vec(&HttpResponse::new(status).set_headers(headers.unwrap_or_default()))
+                .unwrap(),
         )
         .blob_bytes(body)
         .send()
@@ -1094,6 +3665,116 @@ pub fn ws_push_all_channels(
     }
 }
 
+#[derive(Debug, Error)]
+pub enum WsProtocolError {
+    #[error("failed to encode WebSocket message: {0}")]
+    Encode(String),
+    #[error("failed to decode WebSocket message: {0}")]
+    Decode(String),
+}
+
+/// A typed WebSocket protocol: application messages of type `T`, encoded as
+/// JSON in Text frames or as MessagePack in Binary frames.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::http::server::WsProtocol;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// enum ChatMessage {
+///     Say { text: String },
+/// }
+///
+/// let protocol = WsProtocol::<ChatMessage>::text();
+/// protocol
+///     .push_typed(1, &ChatMessage::Say { text: "hi".into() })
+///     .unwrap();
+/// ```
+pub struct WsProtocol<T> {
+    message_type: WsMessageType,
+    _message: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> WsProtocol<T>
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    /// Messages encoded as JSON, sent in Text frames.
+    pub fn text() -> Self {
+        Self {
+            message_type: WsMessageType::Text,
+            _message: std::marker::PhantomData,
+        }
+    }
+
+    /// Messages encoded as MessagePack, sent in Binary frames.
+    pub fn binary() -> Self {
+        Self {
+            message_type: WsMessageType::Binary,
+            _message: std::marker::PhantomData,
+        }
+    }
+
+    /// The type of frame messages are sent in.
+    pub fn message_type(&self) -> WsMessageType {
+        self.message_type
+    }
+
+    /// Encode a message into a blob to push.
+    pub fn encode(&self, message: &T) -> Result<KiBlob, WsProtocolError> {
+        let bytes = match self.message_type {
+            WsMessageType::Binary => rmp_serde::to_vec_named(message)
+                .map_err(|e| WsProtocolError::Encode(e.to_string()))?,
+            _ => serde_json::to_vec(message).map_err(|e| WsProtocolError::Encode(e.to_string()))?,
+        };
+        Ok(KiBlob::new(None::<String>, bytes))
+    }
+
+    /// Decode a message from the blob of a WebSocket push.
+    pub fn parse_typed(&self, blob: &KiBlob) -> Result<T, WsProtocolError> {
+        match self.message_type {
+            WsMessageType::Binary => rmp_serde::from_slice(&blob.bytes)
+                .map_err(|e| WsProtocolError::Decode(e.to_string())),
+            _ => serde_json::from_slice(&blob.bytes)
+                .map_err(|e| WsProtocolError::Decode(e.to_string())),
+        }
+    }
+
+    /// Push a message on an open WebSocket channel.
+    pub fn push_typed(&self, channel_id: u32, message: &T) -> Result<(), WsProtocolError> {
+        send_ws_push(channel_id, self.message_type, self.encode(message)?);
+        Ok(())
+    }
+
+    /// Push a message to all channels on a given path of `server`.
+    pub fn push_typed_all_channels(
+        &self,
+        server: &HttpServer,
+        path: &str,
+        message: &T,
+    ) -> Result<(), WsProtocolError> {
+        server.ws_push_all_channels(path, self.message_type, self.encode(message)?);
+        Ok(())
+    }
+}
+
+impl<T> Clone for WsProtocol<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for WsProtocol<T> {}
+
+impl<T> std::fmt::Debug for WsProtocol<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WsProtocol")
+            .field("message_type", &self.message_type)
+            .finish()
+    }
+}
+
 /// Guess the MIME type of a file from its extension.
 pub fn get_mime_type(filename: &str) -> String {
     let file_path = std::path::Path::new(filename);
@@ -0,0 +1,209 @@
+use crate::http::cookies::{CookieBuilder, SameSite};
+use crate::http::server::IncomingHttpRequest;
+use crate::kv::{self, Kv, KvError};
+use crate::PackageId;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//
+// Sessions identified by signed cookies, with their data stored in kv
+//
+
+/// Key under which the secret used to sign session cookies is stored.
+const SECRET_KEY: &[u8] = b"sessions:secret";
+/// Prefix of the keys under which session data is stored.
+const SESSION_PREFIX: &str = "sessions:session:";
+/// Length in bytes of session IDs and of the signing secret.
+const RANDOM_LEN: usize = 32;
+
+/// A session, as found by [`Sessions::get_session()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session<T> {
+    pub id: String,
+    pub data: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSession<T> {
+    data: T,
+    /// Seconds since the UNIX epoch.
+    expires_at: u64,
+}
+
+/// Sessions for an app's own login flow, beyond the node login cookie.
+///
+/// Each session has a random ID, sent to the client in a cookie along with an
+/// HMAC-SHA256 signature, so that cookies cannot be forged. Session data of
+/// type `T` is stored in a kv database, along with the signing secret, which is
+/// generated the first time the database is used.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{our, http::{server::{HttpResponse, IncomingHttpRequest}, sessions::Sessions}, LazyLoadBlob};
+///
+/// fn login(request: &IncomingHttpRequest) -> anyhow::Result<(HttpResponse, Option<LazyLoadBlob>)> {
+///     let sessions = Sessions::<String>::open(our().package_id(), "sessions", "my-app-session", None)?;
+///     if let Some(session) = sessions.get_session(request)? {
+///         let response = HttpResponse::new(200u16).body_html(&format!("hello again, {}", session.data));
+///         return Ok(response.into_parts());
+///     }
+///     let (_id, cookie) = sessions.create_session(&"alice".to_string())?;
+///     Ok(HttpResponse::new(200u16).set_cookie(&cookie).into_parts())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sessions<T> {
+    kv: Kv<Vec<u8>, Vec<u8>>,
+    secret: Vec<u8>,
+    cookie_name: String,
+    max_age: Duration,
+    _data: PhantomData<T>,
+}
+
+impl<T> Sessions<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Open or create the kv database `db` to hold sessions, which are identified
+    /// by the cookie `cookie_name`. Sessions last one day unless changed with
+    /// [`Sessions::max_age()`].
+    pub fn open(
+        package_id: PackageId,
+        db: &str,
+        cookie_name: &str,
+        timeout: Option<u64>,
+    ) -> anyhow::Result<Self> {
+        let kv = kv::open_raw(package_id, db, timeout)?;
+        let secret = match get_if_present(&kv, SECRET_KEY)? {
+            Some(secret) => secret,
+            None => {
+                let secret = random_bytes();
+                kv.set_raw(SECRET_KEY, &secret, None)?;
+                secret
+            }
+        };
+        Ok(Sessions {
+            kv,
+            secret,
+            cookie_name: cookie_name.to_string(),
+            max_age: Duration::from_secs(24 * 60 * 60),
+            _data: PhantomData,
+        })
+    }
+
+    /// Set how long new sessions last.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Create a session holding `data`. Returns its ID and the cookie to set on the
+    /// client, e.g. with [`crate::http::server::HttpResponse::set_cookie()`].
+    ///
+    /// The cookie is `HttpOnly`, `SameSite=Lax` and on path `/`. It may be changed
+    /// before it is set, e.g. to make it `Secure`, as long as its value is kept.
+    pub fn create_session(&self, data: &T) -> anyhow::Result<(String, CookieBuilder)> {
+        let id = hex::encode(random_bytes());
+        self.store(&id, data, now_secs() + self.max_age.as_secs())?;
+        let cookie = CookieBuilder::new(&self.cookie_name, format!("{}.{}", id, self.sign(&id)))?
+            .max_age(self.max_age)
+            .path("/")
+            .same_site(SameSite::Lax)
+            .http_only(true);
+        Ok((id, cookie))
+    }
+
+    /// The session of a request, if it has a validly signed session cookie for
+    /// a session that exists and has not expired.
+    pub fn get_session(&self, request: &IncomingHttpRequest) -> anyhow::Result<Option<Session<T>>> {
+        let Some(id) = self.session_id(request) else {
+            return Ok(None);
+        };
+        let Some(bytes) = get_if_present(&self.kv, session_key(&id).as_bytes())? else {
+            return Ok(None);
+        };
+        let stored = serde_json::from_slice::<StoredSession<T>>(&bytes)?;
+        if stored.expires_at <= now_secs() {
+            self.kv.delete_raw(session_key(&id).as_bytes(), None)?;
+            return Ok(None);
+        }
+        Ok(Some(Session {
+            id,
+            data: stored.data,
+        }))
+    }
+
+    /// Replace the data of an existing session, keeping its expiry.
+    pub fn update_session(&self, id: &str, data: &T) -> anyhow::Result<()> {
+        let Some(bytes) = get_if_present(&self.kv, session_key(id).as_bytes())? else {
+            return Err(anyhow::anyhow!("sessions: no session {}", id));
+        };
+        let stored = serde_json::from_slice::<StoredSession<T>>(&bytes)?;
+        self.store(id, data, stored.expires_at)
+    }
+
+    /// Destroy the session of a request, if it has one. Returns the cookie to
+    /// set on the client to remove its session cookie.
+    pub fn destroy_session(&self, request: &IncomingHttpRequest) -> anyhow::Result<CookieBuilder> {
+        if let Some(id) = self.session_id(request) {
+            self.kv.delete_raw(session_key(&id).as_bytes(), None)?;
+        }
+        Ok(CookieBuilder::removal(&self.cookie_name)?.path("/"))
+    }
+
+    fn store(&self, id: &str, data: &T, expires_at: u64) -> anyhow::Result<()> {
+        let bytes = serde_json::to_vec(&StoredSession { data, expires_at })?;
+        self.kv.set_raw(session_key(id).as_bytes(), &bytes, None)
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length")
+    }
+
+    fn sign(&self, id: &str) -> String {
+        let mut mac = self.mac();
+        mac.update(id.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// The ID in the session cookie of a request, if its signature is valid.
+    fn session_id(&self, request: &IncomingHttpRequest) -> Option<String> {
+        let cookie = request.cookie(&self.cookie_name)?;
+        let (id, signature) = cookie.split_once('.')?;
+        let signature = hex::decode(signature).ok()?;
+        let mut mac = self.mac();
+        mac.update(id.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+        Some(id.to_string())
+    }
+}
+
+fn session_key(id: &str) -> String {
+    format!("{}{}", SESSION_PREFIX, id)
+}
+
+fn random_bytes() -> Vec<u8> {
+    let mut bytes = vec![0u8; RANDOM_LEN];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Get a value from kv, or `None` if the key is not found.
+fn get_if_present(kv: &Kv<Vec<u8>, Vec<u8>>, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    match kv.get_raw(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if matches!(e.downcast_ref::<KvError>(), Some(KvError::KeyNotFound)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
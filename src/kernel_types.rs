@@ -312,6 +312,16 @@ pub struct Erc721Properties {
     pub api_includes: Option<Vec<std::path::PathBuf>>,
 }
 
+impl Erc721Metadata {
+    /// The SHA-256 hash of the current version of the package, if published.
+    pub fn current_version_hash(&self) -> Option<&str> {
+        self.properties
+            .code_hashes
+            .get(&self.properties.current_version)
+            .map(|hash| hash.as_str())
+    }
+}
+
 /// the type that gets deserialized from each entry in the array in `manifest.json`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PackageManifestEntry {
@@ -1,8 +1,20 @@
 use crate::{get_blob, Message, PackageId, Request};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{Read, Write};
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
 use thiserror::Error;
 
+pub mod cached;
+pub mod indexed;
+pub mod keyspace;
+pub mod map;
+
+pub use cached::*;
+pub use indexed::*;
+pub use keyspace::*;
+pub use map::*;
+
 /// Actions are sent to a specific key value database. `db` is the name,
 /// `package_id` is the [`PackageId`] that created the database. Capabilities
 /// are checked: you can access another process's database if it has given
@@ -46,6 +58,29 @@ pub enum KvAction {
     /// A successful set will respond with [`KvResponse::Ok`]. Any error will be
     /// contained in the [`KvResponse::Err`] variant.
     Set { key: Vec<u8>, tx_id: Option<u64> },
+    /// Sets a value for the specified key in the database, as [`KvAction::Set`],
+    /// which expires `ttl_secs` seconds later.
+    ///
+    /// Expiry is lazy: once expired, the key is treated as absent by every action,
+    /// and is removed from the database when next accessed. Setting the key again,
+    /// with or without a TTL, replaces the expiry.
+    ///
+    /// # Parameters
+    /// * `key` - The key as a byte vector
+    /// * `ttl_secs` - How long the value lives, in seconds
+    /// * `tx_id` - Optional transaction ID if this operation is part of a transaction
+    /// * blob: [`Vec<u8>`] - Byte vector to store for the key
+    ///
+    /// Using this action requires the sender to have the write capability
+    /// for the database.
+    ///
+    /// A successful set will respond with [`KvResponse::Ok`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    SetWithTtl {
+        key: Vec<u8>,
+        ttl_secs: u64,
+        tx_id: Option<u64>,
+    },
     /// Deletes a key-value pair from the database.
     ///
     /// # Parameters
@@ -83,12 +118,149 @@ pub enum KvAction {
     /// A successful commit will respond with [`KvResponse::Ok`]. Any error will be
     /// contained in the [`KvResponse::Err`] variant.
     Commit { tx_id: u64 },
+    /// Discards all operations in the specified transaction.
+    ///
+    /// # Parameters
+    /// * `tx_id` - The ID of the transaction to roll back
+    ///
+    /// A successful rollback will respond with [`KvResponse::Ok`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    Rollback { tx_id: u64 },
+    /// Performs several gets, sets and deletes in one message, in order.
+    ///
+    /// # Parameters
+    /// * `ops` - The operations to perform
+    /// * `tx_id` - Optional transaction ID if the operations are part of a transaction.
+    ///   Gets in a transaction read through it, seeing its uncommitted writes.
+    /// * blob: [`Vec<u8>`] - The value of each set in `ops`, in order, each prefixed
+    ///   with its length as a little-endian `u32`
+    ///
+    /// Using this action requires the read capability for the database if `ops`
+    /// contains a get, and the write capability if it contains a set or delete.
+    ///
+    /// A successful batch will respond with [`KvResponse::Batch`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    Batch {
+        ops: Vec<KvBatchOp>,
+        tx_id: Option<u64>,
+    },
+    /// Atomically sets or deletes a key if its current value is as expected.
+    ///
+    /// # Parameters
+    /// * `key` - The key as a byte vector
+    /// * `expected` - The value the key must have, or `None` if it must not exist
+    /// * `new` - The value to set, or `None` to delete the key
+    ///
+    /// Using this action requires the sender to have the read and write capabilities
+    /// for the database.
+    ///
+    /// A successful compare-and-swap will respond with [`KvResponse::CompareAndSwap`],
+    /// whether or not the value was as expected. Any error will be contained in the
+    /// [`KvResponse::Err`] variant.
+    CompareAndSwap {
+        key: Vec<u8>,
+        expected: Option<Vec<u8>>,
+        new: Option<Vec<u8>>,
+    },
+    /// Retrieves the key-value pairs with keys in a range, ordered by key bytes.
+    ///
+    /// # Parameters
+    /// * `start` - The lower bound of the range
+    /// * `end` - The upper bound of the range
+    /// * `limit` - Optional maximum number of pairs to return
+    /// * `reverse` - Whether to return pairs in descending order of keys, starting from `end`
+    ///
+    /// Using this action requires the sender to have the read capability
+    /// for the database.
+    ///
+    /// A successful range will respond with [`KvResponse::Range`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    Range {
+        start: Bound<Vec<u8>>,
+        end: Bound<Vec<u8>>,
+        limit: Option<u64>,
+        reverse: bool,
+    },
+    /// Subscribes the sender to changes of keys starting with `prefix`: each set
+    /// or delete of such a key, by any process, sends the sender a [`KvNotification`]
+    /// request. An empty prefix subscribes to every key.
+    ///
+    /// Using this action requires the sender to have the read capability
+    /// for the database.
+    ///
+    /// A successful subscribe will respond with [`KvResponse::Ok`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    Subscribe { prefix: Vec<u8> },
+    /// Cancels a [`KvAction::Subscribe`] with the same prefix.
+    ///
+    /// A successful unsubscribe will respond with [`KvResponse::Ok`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    Unsubscribe { prefix: Vec<u8> },
+    /// Retrieves the approximate number of keys and bytes of the database,
+    /// in total and for the keys starting with each of `prefixes`.
+    ///
+    /// Using this action requires the sender to have the read capability
+    /// for the database.
+    ///
+    /// A successful stats will respond with [`KvResponse::Stats`]. Any error will be
+    /// contained in the [`KvResponse::Err`] variant.
+    Stats { prefixes: Vec<Vec<u8>> },
+}
+
+/// [`crate::Request`] sent by `kv:distro:sys` to subscribers of a database, as
+/// set up with [`KvAction::Subscribe`], when a key changes. For a set, the blob
+/// holds the new value. No response is expected.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KvNotification {
+    pub package_id: PackageId,
+    pub db: String,
+    pub key: Vec<u8>,
+    pub deleted: bool,
+}
+
+/// A change of a key of a [`Kv`], parsed from a [`KvNotification`] with [`Kv::parse_change()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KvChange<K, V> {
+    Set(K, V),
+    Deleted(K),
+}
+
+/// An operation of a [`KvAction::Batch`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KvBatchOp {
+    Get(Vec<u8>),
+    Set {
+        key: Vec<u8>,
+        /// Sent in the blob of the [`KvAction::Batch`], not in its body,
+        /// so it is empty when deserialized.
+        #[serde(skip)]
+        value: Vec<u8>,
+    },
+    Delete(Vec<u8>),
+}
+
+/// The approximate size of a database, as returned by [`KvAction::Stats`].
+/// Counts are estimates from the storage engine and may lag recent writes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KvStats {
+    pub keys: u64,
+    /// Total size of keys and values.
+    pub bytes: u64,
+    /// The size of the keys starting with each requested prefix, in request order.
+    pub prefixes: Vec<KvPrefixStats>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KvPrefixStats {
+    pub prefix: Vec<u8>,
+    pub keys: u64,
+    pub bytes: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum KvResponse {
     /// Indicates successful completion of an operation.
-    /// Sent in response to actions Open, RemoveDb, Set, Delete, and Commit.
+    /// Sent in response to actions Open, RemoveDb, Set, Delete, Commit, and Rollback.
     Ok,
     /// Returns the transaction ID for a newly created transaction.
     ///
@@ -101,6 +273,16 @@ pub enum KvResponse {
     /// * The retrieved key as a byte vector
     /// * blob: [`Vec<u8>`] - Byte vector associated with the key
     Get(Vec<u8>),
+    /// Returns the results of a [`KvAction::Batch`], one per operation, in order:
+    /// the value for a get of a key that exists, and `None` otherwise.
+    Batch(Vec<Option<Vec<u8>>>),
+    /// Returns whether a [`KvAction::CompareAndSwap`] found the expected value,
+    /// and so set or deleted the key.
+    CompareAndSwap { swapped: bool },
+    /// Returns the key-value pairs of a [`KvAction::Range`], in order.
+    Range(Vec<(Vec<u8>, Vec<u8>)>),
+    /// Returns the statistics of a [`KvAction::Stats`].
+    Stats(KvStats),
     /// Indicates an error occurred during the operation.
     Err(KvError),
 }
@@ -263,6 +445,27 @@ where
         }
     }
 
+    /// Set a value that expires after `ttl_secs` seconds, optionally in a transaction.
+    /// Once expired, [`Kv::get()`] fails with [`KvError::KeyNotFound`], as if the
+    /// key had been deleted.
+    pub fn set_with_ttl(
+        &self,
+        key: &K,
+        value: &V,
+        ttl_secs: u64,
+        tx_id: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let action = KvAction::SetWithTtl {
+            key: serde_json::to_vec(key)?,
+            ttl_secs,
+            tx_id,
+        };
+        match self.send(action, Some(serde_json::to_vec(value)?))? {
+            KvResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
     /// Set a value as a different type T
     pub fn set_as<T>(&self, key: &K, value: &T, tx_id: Option<u64>) -> anyhow::Result<()>
     where
@@ -400,6 +603,382 @@ where
             _ => Err(anyhow::anyhow!("kv: unexpected message: {:?}", res)),
         }
     }
+
+    /// Roll back a transaction, discarding its writes.
+    pub fn rollback_tx(&self, tx_id: u64) -> anyhow::Result<()> {
+        match self.send(KvAction::Rollback { tx_id }, None)? {
+            KvResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Run `f` with the ID of a new transaction, committing it if `f` succeeds
+    /// and rolling it back if `f` fails, returning the result of `f`.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{kv, our};
+    ///
+    /// let kv = kv::open::<String, i64>(our().package_id(), "balances", None)?;
+    /// kv.transaction(|tx_id| {
+    ///     kv.set(&"alice".to_string(), &90, Some(tx_id))?;
+    ///     kv.set(&"bob".to_string(), &110, Some(tx_id))
+    /// })?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn transaction<T, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        F: FnOnce(u64) -> anyhow::Result<T>,
+    {
+        let tx_id = self.begin_tx()?;
+        match f(tx_id) {
+            Ok(result) => {
+                self.commit_tx(tx_id)?;
+                Ok(result)
+            }
+            Err(error) => match self.rollback_tx(tx_id) {
+                Ok(()) => Err(error),
+                Err(rollback_error) => Err(error.context(format!(
+                    "kv: rollback of transaction {} failed: {}",
+                    tx_id, rollback_error
+                ))),
+            },
+        }
+    }
+
+    /// Perform several gets, sets and deletes in one message, optionally in a
+    /// transaction, returning the result of each: see [`KvAction::Batch`].
+    pub fn batch(
+        &self,
+        ops: Vec<KvBatchOp>,
+        tx_id: Option<u64>,
+    ) -> anyhow::Result<Vec<Option<Vec<u8>>>> {
+        let count = ops.len();
+        let mut values = vec![];
+        for op in &ops {
+            if let KvBatchOp::Set { value, .. } = op {
+                values.extend((value.len() as u32).to_le_bytes());
+                values.extend(value);
+            }
+        }
+        let blob = (!values.is_empty()).then_some(values);
+        match self.send(KvAction::Batch { ops, tx_id }, blob)? {
+            KvResponse::Batch(results) if results.len() == count => Ok(results),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Get several values in one message, returning the keys found, in order,
+    /// with their values, and the keys that don't exist. A value that exists
+    /// but fails to deserialize is an error, not a missing key.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{kv, our};
+    ///
+    /// let kv = kv::open::<String, u64>(our().package_id(), "scores", None)?;
+    /// let (found, missing) = kv.get_many(&["alice".to_string(), "bob".to_string()])?;
+    /// for (player, score) in found {
+    ///     println!("{}: {}", player, score);
+    /// }
+    /// for player in missing {
+    ///     println!("{}: no score", player);
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn get_many(&self, keys: &[K]) -> anyhow::Result<(Vec<(K, V)>, Vec<K>)>
+    where
+        K: Clone,
+    {
+        let ops = keys
+            .iter()
+            .map(|key| Ok(KvBatchOp::Get(serde_json::to_vec(key)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut found = vec![];
+        let mut missing = vec![];
+        for (key, value) in keys.iter().zip(self.batch(ops, None)?) {
+            match value {
+                Some(bytes) => {
+                    let value = serde_json::from_slice(&bytes).map_err(|e| {
+                        anyhow::anyhow!(
+                            "Failed to deserialize value of key {}: {}",
+                            serde_json::to_string(key).unwrap_or_default(),
+                            e
+                        )
+                    })?;
+                    found.push((key.clone(), value));
+                }
+                None => missing.push(key.clone()),
+            }
+        }
+        Ok((found, missing))
+    }
+
+    /// Set several values in one message, optionally in a transaction.
+    pub fn set_many(&self, entries: &[(K, V)], tx_id: Option<u64>) -> anyhow::Result<()> {
+        let ops = entries
+            .iter()
+            .map(|(key, value)| {
+                Ok(KvBatchOp::Set {
+                    key: serde_json::to_vec(key)?,
+                    value: serde_json::to_vec(value)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.batch(ops, tx_id)?;
+        Ok(())
+    }
+
+    /// Delete several values in one message, optionally in a transaction.
+    pub fn delete_many(&self, keys: &[K], tx_id: Option<u64>) -> anyhow::Result<()> {
+        let ops = keys
+            .iter()
+            .map(|key| Ok(KvBatchOp::Delete(serde_json::to_vec(key)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.batch(ops, tx_id)?;
+        Ok(())
+    }
+
+    /// Get the key-value pairs with keys in `range`, in ascending order, e.g.
+    /// `kv.range("ts:1700000000".to_string().."ts:1800000000".to_string())`.
+    ///
+    /// Keys are ordered by their bytes as serialized to JSON: for string keys,
+    /// this is the order of the strings, but for numeric keys it is not numeric order.
+    pub fn range<R>(&self, range: R) -> anyhow::Result<Vec<(K, V)>>
+    where
+        R: RangeBounds<K>,
+    {
+        self.range_with(range, None, false)
+    }
+
+    /// Get at most `limit` key-value pairs with keys in `range`, as [`Kv::range()`],
+    /// in descending order if `reverse` is set.
+    pub fn range_with<R>(
+        &self,
+        range: R,
+        limit: Option<u64>,
+        reverse: bool,
+    ) -> anyhow::Result<Vec<(K, V)>>
+    where
+        R: RangeBounds<K>,
+    {
+        fn bound<K: Serialize>(bound: Bound<&K>) -> anyhow::Result<Bound<Vec<u8>>> {
+            Ok(match bound {
+                Bound::Included(key) => Bound::Included(serde_json::to_vec(key)?),
+                Bound::Excluded(key) => Bound::Excluded(serde_json::to_vec(key)?),
+                Bound::Unbounded => Bound::Unbounded,
+            })
+        }
+        let start = bound(range.start_bound())?;
+        let end = bound(range.end_bound())?;
+        self.range_raw(start, end, limit, reverse)?
+            .into_iter()
+            .map(|(key, value)| {
+                Ok((
+                    serde_json::from_slice(&key)?,
+                    serde_json::from_slice(&value)
+                        .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Get the serialized key-value pairs with serialized keys in a range: see [`KvAction::Range`].
+    pub fn range_raw(
+        &self,
+        start: Bound<Vec<u8>>,
+        end: Bound<Vec<u8>>,
+        limit: Option<u64>,
+        reverse: bool,
+    ) -> anyhow::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let action = KvAction::Range {
+            start,
+            end,
+            limit,
+            reverse,
+        };
+        match self.send(action, None)? {
+            KvResponse::Range(pairs) => Ok(pairs),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Write every key-value pair of the database, as serialized, to a new file
+    /// at `path` in the VFS, replacing any existing file, reading the database
+    /// [`EXPORT_PAGE_SIZE`] pairs at a time. Returns the number of pairs written.
+    /// Restore it with [`import_from_vfs()`].
+    ///
+    /// The process must have write capability for the drive of `path`.
+    pub fn export_to_vfs(&self, path: &str) -> anyhow::Result<u64> {
+        let file = crate::vfs::create_file(path, Some(self.timeout))?;
+        let mut writer = crate::vfs::BufferedFileWriter::new(file);
+        writer.write_all(EXPORT_MAGIC)?;
+        let mut start = Bound::Unbounded;
+        let mut count = 0;
+        loop {
+            let pairs = self.range_raw(start, Bound::Unbounded, Some(EXPORT_PAGE_SIZE), false)?;
+            for (key, value) in &pairs {
+                for bytes in [key, value] {
+                    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+                    writer.write_all(bytes)?;
+                }
+            }
+            count += pairs.len() as u64;
+            match pairs.into_iter().last() {
+                Some((key, _)) if count % EXPORT_PAGE_SIZE == 0 => start = Bound::Excluded(key),
+                _ => break,
+            }
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Get the approximate number of keys and bytes of the database, in total
+    /// and for the keys starting with each of `prefixes`, matched as in
+    /// [`Kv::subscribe()`].
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{kv, our};
+    ///
+    /// let kv = kv::open::<String, Vec<u8>>(our().package_id(), "app", None)?;
+    /// let stats = kv.stats(&["users:", "sessions:"])?;
+    /// println!("{} keys, {} bytes", stats.keys, stats.bytes);
+    /// for prefix in stats.prefixes {
+    ///     println!("{}: {} keys", String::from_utf8_lossy(&prefix.prefix), prefix.keys);
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn stats(&self, prefixes: &[&str]) -> anyhow::Result<KvStats> {
+        self.stats_raw(prefixes.iter().map(|prefix| json_prefix(prefix)).collect())
+    }
+
+    /// Get the statistics of the database for serialized key prefixes: see [`KvAction::Stats`].
+    pub fn stats_raw(&self, prefixes: Vec<Vec<u8>>) -> anyhow::Result<KvStats> {
+        match self.send(KvAction::Stats { prefixes }, None)? {
+            KvResponse::Stats(stats) => Ok(stats),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Receive a [`KvNotification`] request each time a key starting with `prefix`,
+    /// as serialized, is set or deleted: parse them with [`Kv::parse_change()`].
+    /// For string keys, a prefix such as `"users:"` matches keys starting with it;
+    /// an empty prefix matches every key, whatever its type.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{await_message, kv, our};
+    ///
+    /// let kv = kv::open::<String, u64>(our().package_id(), "scores", None)?;
+    /// kv.subscribe("player:")?;
+    /// loop {
+    ///     let Ok(message) = await_message() else { continue };
+    ///     if let Some(change) = kv.parse_change(&message)? {
+    ///         println!("{:?}", change);
+    ///     }
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn subscribe(&self, prefix: &str) -> anyhow::Result<()> {
+        let action = KvAction::Subscribe {
+            prefix: json_prefix(prefix),
+        };
+        match self.send(action, None)? {
+            KvResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Stop receiving notifications set up with [`Kv::subscribe()`] for `prefix`.
+    pub fn unsubscribe(&self, prefix: &str) -> anyhow::Result<()> {
+        let action = KvAction::Unsubscribe {
+            prefix: json_prefix(prefix),
+        };
+        match self.send(action, None)? {
+            KvResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Parse `message` as a [`KvNotification`] for this database, if it is one.
+    /// Must be called before receiving another message, as a set value is in the blob.
+    pub fn parse_change(&self, message: &Message) -> anyhow::Result<Option<KvChange<K, V>>> {
+        if !message.is_request() || !message.is_local() || !message.is_process("kv:distro:sys") {
+            return Ok(None);
+        }
+        let Ok(notification) = serde_json::from_slice::<KvNotification>(message.body()) else {
+            return Ok(None);
+        };
+        if notification.package_id != self.package_id || notification.db != self.db {
+            return Ok(None);
+        }
+        let key = serde_json::from_slice(&notification.key)?;
+        if notification.deleted {
+            return Ok(Some(KvChange::Deleted(key)));
+        }
+        let Some(blob) = message.blob() else {
+            return Err(anyhow::anyhow!("kv: no blob"));
+        };
+        let value = serde_json::from_slice(&blob.bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?;
+        Ok(Some(KvChange::Set(key, value)))
+    }
+
+    /// Atomically replace the value of a key with `new`, or delete it if `new` is
+    /// `None`, if its current value is `expected`, or if it doesn't exist and
+    /// `expected` is `None`. Returns whether the value was replaced.
+    ///
+    /// Values are compared as serialized, so `V` must serialize deterministically:
+    /// e.g. use a `BTreeMap` rather than a `HashMap`.
+    pub fn compare_and_swap(
+        &self,
+        key: &K,
+        expected: Option<&V>,
+        new: Option<&V>,
+    ) -> anyhow::Result<bool> {
+        let action = KvAction::CompareAndSwap {
+            key: serde_json::to_vec(key)?,
+            expected: expected.map(serde_json::to_vec).transpose()?,
+            new: new.map(serde_json::to_vec).transpose()?,
+        };
+        match self.send(action, None)? {
+            KvResponse::CompareAndSwap { swapped } => Ok(swapped),
+            response => Err(anyhow::anyhow!("kv: unexpected response {:?}", response)),
+        }
+    }
+
+    /// Set a value if the key doesn't exist. Returns whether it was set.
+    pub fn set_if_absent(&self, key: &K, value: &V) -> anyhow::Result<bool> {
+        self.compare_and_swap(key, None, Some(value))
+    }
+
+    /// Send `action` to the database, with an optional blob, and await the response,
+    /// turning [`KvResponse::Err`] into an error.
+    fn send(&self, action: KvAction, blob: Option<Vec<u8>>) -> anyhow::Result<KvResponse> {
+        let body = serde_json::to_vec(&KvRequest {
+            package_id: self.package_id.clone(),
+            db: self.db.clone(),
+            action,
+        })?;
+        let mut request = Request::new()
+            .target(("our", "kv", "distro", "sys"))
+            .body(body);
+        if let Some(blob) = blob {
+            request = request.blob_bytes(blob);
+        }
+        let res = request.send_and_await_response(self.timeout)?;
+
+        match res {
+            Ok(Message::Response { body, .. }) => {
+                match serde_json::from_slice::<KvResponse>(&body)? {
+                    KvResponse::Err(error) => Err(error.into()),
+                    response => Ok(response),
+                }
+            }
+            _ => Err(anyhow::anyhow!("kv: unexpected message: {:?}", res)),
+        }
+    }
 }
 
 impl Kv<Vec<u8>, Vec<u8>> {
@@ -492,6 +1071,18 @@ impl Kv<Vec<u8>, Vec<u8>> {
     }
 }
 
+/// The serialized prefix of keys that serialize to JSON strings starting with
+/// `prefix`: the serialized string without its closing quote. An empty prefix
+/// matches every key, string or not.
+fn json_prefix(prefix: &str) -> Vec<u8> {
+    if prefix.is_empty() {
+        return vec![];
+    }
+    let mut bytes = serde_json::to_vec(prefix).unwrap();
+    bytes.pop();
+    bytes
+}
+
 /// Helper function to open a raw bytes key-value store
 pub fn open_raw(
     package_id: PackageId,
@@ -537,6 +1128,83 @@ where
     }
 }
 
+/// How many key-value pairs [`Kv::export_to_vfs()`] reads, and [`import_from_vfs()`]
+/// writes, per message.
+pub const EXPORT_PAGE_SIZE: u64 = 1000;
+
+/// The start of files written by [`Kv::export_to_vfs()`], followed by each key and
+/// value prefixed with its length as a little-endian `u32`.
+const EXPORT_MAGIC: &[u8] = b"kinode-kv-export-v1\n";
+
+/// Open or create the kv db `db` of `package_id` and write into it every key-value
+/// pair of the file at `path`, written by [`Kv::export_to_vfs()`], replacing
+/// existing values, in a transaction. Returns the number of pairs written.
+/// If the file is truncated or can't be read, the transaction is rolled back.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{kv, our};
+///
+/// let db = kv::open_raw(our().package_id(), "state", None)?;
+/// db.export_to_vfs("/my-app:template.os/backups/state.kv")?;
+/// // ... e.g. on another node
+/// kv::import_from_vfs(our().package_id(), "state", "/my-app:template.os/backups/state.kv", None)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn import_from_vfs(
+    package_id: PackageId,
+    db: &str,
+    path: &str,
+    timeout: Option<u64>,
+) -> anyhow::Result<u64> {
+    let kv = open_raw(package_id, db, timeout)?;
+    let file = crate::vfs::open_file(path, false, timeout)?;
+    let mut reader = crate::vfs::BufferedFileReader::new(file)?;
+    let mut magic = vec![0; EXPORT_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != EXPORT_MAGIC {
+        return Err(anyhow::anyhow!("kv: {} is not a kv export", path));
+    }
+
+    /// The next length-prefixed bytes, or `None` at the end of the file. The end
+    /// of the file anywhere else, even within the length prefix, is an error.
+    fn read_bytes(reader: &mut impl Read, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let mut len = [0; 4];
+        let mut filled = 0;
+        while filled < len.len() {
+            match reader.read(&mut len[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(anyhow::anyhow!("kv: {} is truncated", path)),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+        reader.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    kv.transaction(|tx_id| {
+        let mut ops = vec![];
+        let mut count = 0;
+        while let Some(key) = read_bytes(&mut reader, path)? {
+            let Some(value) = read_bytes(&mut reader, path)? else {
+                return Err(anyhow::anyhow!("kv: {} is truncated", path));
+            };
+            ops.push(KvBatchOp::Set { key, value });
+            count += 1;
+            if ops.len() as u64 == EXPORT_PAGE_SIZE {
+                kv.batch(std::mem::take(&mut ops), Some(tx_id))?;
+            }
+        }
+        if !ops.is_empty() {
+            kv.batch(ops, Some(tx_id))?;
+        }
+        Ok(count)
+    })
+}
+
 /// Removes and deletes a kv db.
 pub fn remove_db(package_id: PackageId, db: &str, timeout: Option<u64>) -> anyhow::Result<()> {
     let timeout = timeout.unwrap_or(5);
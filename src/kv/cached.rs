@@ -0,0 +1,159 @@
+use super::{Kv, KvBatchOp, KvError};
+use crate::lru::LruCache;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+
+/// When a [`CachedKv`] sends its writes to the database.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Send each set and delete immediately.
+    #[default]
+    WriteThrough,
+    /// Hold sets and deletes until [`CachedKv::flush()`], then send them in one
+    /// [`KvAction::Batch`](super::KvAction::Batch). Writes not flushed are lost.
+    WriteBehind,
+}
+
+/// A [`Kv`] with an in-process least-recently-used cache of values, so that
+/// repeated reads of hot keys don't each take a round trip to the kv runtime
+/// module.
+///
+/// The cache only sees writes made through this `CachedKv`: writes by other
+/// processes, or through another handle to the same database, can leave it
+/// stale until [`CachedKv::invalidate()`] or [`CachedKv::clear_cache()`].
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::kv::{self, CachedKv, WriteMode};
+/// use kinode_process_lib::our;
+///
+/// let db = kv::open::<String, u64>(our().package_id(), "counters", None)?;
+/// let mut counters = CachedKv::new(db, 100, WriteMode::WriteBehind);
+/// for _ in 0..10 {
+///     let count = counters.get(&"hits".to_string()).unwrap_or(0);
+///     counters.set(&"hits".to_string(), &(count + 1))?;
+/// }
+/// // one message for all ten writes
+/// counters.flush()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct CachedKv<K, V> {
+    kv: Kv<K, V>,
+    mode: WriteMode,
+    /// Values by serialized key.
+    cache: LruCache<Vec<u8>, V>,
+    /// Serialized values by serialized key of writes not yet flushed,
+    /// `None` for deletes.
+    pending: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<K, V> CachedKv<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned + Clone,
+{
+    /// Cache at most `capacity` values of `kv`.
+    pub fn new(kv: Kv<K, V>, capacity: usize, mode: WriteMode) -> Self {
+        CachedKv {
+            kv,
+            mode,
+            cache: LruCache::new(capacity),
+            pending: BTreeMap::new(),
+        }
+    }
+    pub fn kv(&self) -> &Kv<K, V> {
+        &self.kv
+    }
+    pub fn mode(&self) -> WriteMode {
+        self.mode
+    }
+    /// The number of writes waiting for [`CachedKv::flush()`].
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Get a value, from the cache if possible.
+    pub fn get(&mut self, key: &K) -> anyhow::Result<V> {
+        let serialized_key = serde_json::to_vec(key)?;
+        if let Some(value) = self.cache.get(&serialized_key) {
+            return Ok(value.clone());
+        }
+        let value = match self.pending.get(&serialized_key) {
+            Some(Some(bytes)) => serde_json::from_slice::<V>(bytes)
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?,
+            Some(None) => return Err(KvError::KeyNotFound.into()),
+            None => self.kv.get(key)?,
+        };
+        self.cache.insert(serialized_key, value.clone());
+        Ok(value)
+    }
+
+    /// Set a value, sending it to the database now or on the next
+    /// [`CachedKv::flush()`] depending on the [`WriteMode`].
+    pub fn set(&mut self, key: &K, value: &V) -> anyhow::Result<()> {
+        let serialized_key = serde_json::to_vec(key)?;
+        match self.mode {
+            WriteMode::WriteThrough => self.kv.set(key, value, None)?,
+            WriteMode::WriteBehind => {
+                let bytes = serde_json::to_vec(value)?;
+                self.pending.insert(serialized_key.clone(), Some(bytes));
+            }
+        }
+        self.cache.insert(serialized_key, value.clone());
+        Ok(())
+    }
+
+    /// Delete a value, sending it to the database now or on the next
+    /// [`CachedKv::flush()`] depending on the [`WriteMode`].
+    pub fn delete(&mut self, key: &K) -> anyhow::Result<()> {
+        let serialized_key = serde_json::to_vec(key)?;
+        match self.mode {
+            WriteMode::WriteThrough => self.kv.delete(key, None)?,
+            WriteMode::WriteBehind => {
+                self.pending.insert(serialized_key.clone(), None);
+            }
+        }
+        self.cache.remove(&serialized_key);
+        Ok(())
+    }
+
+    /// Send the pending writes to the database in one message.
+    /// On error, the writes stay pending.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.flush_with(None)
+    }
+
+    /// Like [`CachedKv::flush()`], in the transaction `tx_id`.
+    pub fn flush_with(&mut self, tx_id: Option<u64>) -> anyhow::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let ops = self
+            .pending
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) => KvBatchOp::Set {
+                    key: key.clone(),
+                    value: value.clone(),
+                },
+                None => KvBatchOp::Delete(key.clone()),
+            })
+            .collect();
+        self.kv.batch(ops, tx_id)?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Forget the cached value of `key`, so the next get reads the database.
+    /// Pending writes to `key` are kept.
+    pub fn invalidate(&mut self, key: &K) -> anyhow::Result<()> {
+        self.cache.remove(&serde_json::to_vec(key)?);
+        Ok(())
+    }
+
+    /// Forget every cached value. Pending writes are kept.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+}
@@ -0,0 +1,268 @@
+use super::{keyspace::prefix_end, Kv, KvBatchOp, KvError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use std::ops::Bound;
+
+/// Separates the index value from the primary key in an index entry key.
+/// JSON never contains a raw zero byte, so index values can't run into each other.
+const SEPARATOR: u8 = 0;
+
+struct Index<V> {
+    name: String,
+    /// The index values of a value, serialized.
+    extract: Box<dyn Fn(&V) -> anyhow::Result<Vec<Vec<u8>>>>,
+}
+
+/// A table of a [`Kv`] with secondary indexes, e.g. users by email, kept
+/// consistent with the table: each [`IndexedKv::set()`] or [`IndexedKv::delete()`]
+/// reads the previous value and writes the value and its index entries in one
+/// transaction, which is rolled back if any step fails.
+///
+/// Values are stored under keys prefixed `{table}:`, and the entries of each
+/// index under keys prefixed `{table}.{index}:`, as the index value and then the
+/// primary key, both serialized to JSON. Indexes added to a table that already
+/// has values must be filled with [`IndexedKv::rebuild_index()`].
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::kv::{self, IndexedKv};
+/// use kinode_process_lib::our;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     email: String,
+///     tags: Vec<String>,
+/// }
+///
+/// let db = kv::open_raw(our().package_id(), "app", None)?;
+/// let users = IndexedKv::<u64, User>::new(&db, "users")
+///     .index("email", |user: &User| vec![user.email.clone()])
+///     .index("tag", |user: &User| user.tags.clone());
+/// users.set(&1, &User { email: "a@b.c".to_string(), tags: vec!["admin".to_string()] })?;
+/// let admins = users.find_by_index("tag", &"admin")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct IndexedKv<K, V> {
+    kv: Kv<Vec<u8>, Vec<u8>>,
+    table: String,
+    indexes: Vec<Index<V>>,
+    _marker: PhantomData<K>,
+}
+
+impl<K, V> IndexedKv<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// The table `table` of the database of `kv`, with no indexes.
+    pub fn new<A, B>(kv: &Kv<A, B>, table: &str) -> Self {
+        IndexedKv {
+            kv: Kv {
+                package_id: kv.package_id.clone(),
+                db: kv.db.clone(),
+                timeout: kv.timeout,
+                _marker: PhantomData,
+            },
+            table: table.to_string(),
+            indexes: vec![],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Index the values by each of the values `extract` returns for them.
+    /// Several values may share an index value.
+    pub fn index<I, F>(mut self, name: &str, extract: F) -> Self
+    where
+        I: Serialize,
+        F: Fn(&V) -> Vec<I> + 'static,
+    {
+        self.indexes.push(Index {
+            name: name.to_string(),
+            extract: Box::new(move |value| {
+                extract(value)
+                    .iter()
+                    .map(|index_value| Ok(serde_json::to_vec(index_value)?))
+                    .collect()
+            }),
+        });
+        self
+    }
+
+    /// Get a value.
+    pub fn get(&self, key: &K) -> anyhow::Result<V> {
+        match self.get_raw(&serde_json::to_vec(key)?, None)? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e)),
+            None => Err(KvError::KeyNotFound.into()),
+        }
+    }
+
+    /// Set a value, replacing the index entries of any previous value.
+    pub fn set(&self, key: &K, value: &V) -> anyhow::Result<()> {
+        let key = serde_json::to_vec(key)?;
+        let value_bytes = serde_json::to_vec(value)?;
+        self.kv.transaction(|tx_id| {
+            let mut ops = self.remove_entries(&key, tx_id)?;
+            ops.extend(self.index_entries(&key, value)?);
+            ops.push(KvBatchOp::Set {
+                key: self.primary_key(&key),
+                value: value_bytes,
+            });
+            self.kv.batch(ops, Some(tx_id))?;
+            Ok(())
+        })
+    }
+
+    /// Delete a value and its index entries.
+    pub fn delete(&self, key: &K) -> anyhow::Result<()> {
+        let key = serde_json::to_vec(key)?;
+        self.kv.transaction(|tx_id| {
+            let mut ops = self.remove_entries(&key, tx_id)?;
+            ops.push(KvBatchOp::Delete(self.primary_key(&key)));
+            self.kv.batch(ops, Some(tx_id))?;
+            Ok(())
+        })
+    }
+
+    /// Every key-value pair whose values for index `index` include `index_value`.
+    pub fn find_by_index<I>(&self, index: &str, index_value: &I) -> anyhow::Result<Vec<(K, V)>>
+    where
+        I: Serialize + ?Sized,
+    {
+        if !self.indexes.iter().any(|i| i.name == index) {
+            return Err(anyhow::anyhow!("kv: no index {} on {}", index, self.table));
+        }
+        let mut prefix = self.index_prefix(index);
+        prefix.extend(serde_json::to_vec(index_value)?);
+        prefix.push(SEPARATOR);
+        let keys: Vec<Vec<u8>> = self
+            .kv
+            .range_raw(
+                Bound::Included(prefix.clone()),
+                prefix_end(&prefix),
+                None,
+                false,
+            )?
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect();
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let ops = keys
+            .iter()
+            .map(|key| KvBatchOp::Get(self.primary_key(key)))
+            .collect();
+        let values = self.kv.batch(ops, None)?;
+        keys.into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| Some((key, value?)))
+            .map(|(key, value)| {
+                Ok((
+                    serde_json::from_slice(&key)?,
+                    serde_json::from_slice(&value)
+                        .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Delete the entries of index `index` and write them again from every value
+    /// of the table, in one transaction. Returns the number of values indexed.
+    pub fn rebuild_index(&self, index: &str) -> anyhow::Result<u64> {
+        let Some(i) = self.indexes.iter().find(|i| i.name == index) else {
+            return Err(anyhow::anyhow!("kv: no index {} on {}", index, self.table));
+        };
+        let index_prefix = self.index_prefix(index);
+        let mut ops: Vec<KvBatchOp> = self
+            .kv
+            .range_raw(
+                Bound::Included(index_prefix.clone()),
+                prefix_end(&index_prefix),
+                None,
+                false,
+            )?
+            .into_iter()
+            .map(|(key, _)| KvBatchOp::Delete(key))
+            .collect();
+        let primary_prefix = self.primary_key(&[]);
+        let values = self.kv.range_raw(
+            Bound::Included(primary_prefix.clone()),
+            prefix_end(&primary_prefix),
+            None,
+            false,
+        )?;
+        let count = values.len() as u64;
+        for (key, value) in values {
+            let key = &key[primary_prefix.len()..];
+            let value: V = serde_json::from_slice(&value)
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?;
+            for index_value in (i.extract)(&value)? {
+                ops.push(self.index_entry(index, &index_value, key));
+            }
+        }
+        self.kv.transaction(|tx_id| {
+            self.kv.batch(ops, Some(tx_id))?;
+            Ok(count)
+        })
+    }
+
+    /// The serialized value at serialized key `key`, read in transaction `tx_id` if any.
+    fn get_raw(&self, key: &[u8], tx_id: Option<u64>) -> anyhow::Result<Option<Vec<u8>>> {
+        let mut values = self
+            .kv
+            .batch(vec![KvBatchOp::Get(self.primary_key(key))], tx_id)?;
+        Ok(values.pop().flatten())
+    }
+
+    fn primary_key(&self, key: &[u8]) -> Vec<u8> {
+        let mut primary_key = format!("{}:", self.table).into_bytes();
+        primary_key.extend(key);
+        primary_key
+    }
+
+    fn index_prefix(&self, index: &str) -> Vec<u8> {
+        format!("{}.{}:", self.table, index).into_bytes()
+    }
+
+    fn index_entry(&self, index: &str, index_value: &[u8], key: &[u8]) -> KvBatchOp {
+        let mut entry = self.index_prefix(index);
+        entry.extend(index_value);
+        entry.push(SEPARATOR);
+        entry.extend(key);
+        KvBatchOp::Set {
+            key: entry,
+            value: key.to_vec(),
+        }
+    }
+
+    /// The sets of the index entries of `value` at serialized key `key`.
+    fn index_entries(&self, key: &[u8], value: &V) -> anyhow::Result<Vec<KvBatchOp>> {
+        let mut ops = vec![];
+        for index in &self.indexes {
+            for index_value in (index.extract)(value)? {
+                ops.push(self.index_entry(&index.name, &index_value, key));
+            }
+        }
+        Ok(ops)
+    }
+
+    /// The deletes of the index entries of the current value at serialized key `key`,
+    /// if any, as read in transaction `tx_id`.
+    fn remove_entries(&self, key: &[u8], tx_id: u64) -> anyhow::Result<Vec<KvBatchOp>> {
+        let Some(bytes) = self.get_raw(key, Some(tx_id))? else {
+            return Ok(vec![]);
+        };
+        let old: V = serde_json::from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?;
+        Ok(self
+            .index_entries(key, &old)?
+            .into_iter()
+            .filter_map(|op| match op {
+                KvBatchOp::Set { key, .. } => Some(KvBatchOp::Delete(key)),
+                _ => None,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,189 @@
+use super::{Kv, KvBatchOp, KvPrefixStats};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use std::ops::Bound;
+
+/// How many keys [`Keyspace::entries()`] and [`Keyspace::clear()`] handle per message.
+const PAGE_SIZE: u64 = 1000;
+
+/// A typed view of the keys of a [`Kv`] starting with a prefix, e.g. `users:`,
+/// so that several logical tables with their own key and value types can share
+/// one database.
+///
+/// Keys are stored as the prefix followed by the key serialized to JSON, and
+/// values as JSON.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{kv, our};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let db = kv::open_raw(our().package_id(), "app", None)?;
+/// let users = db.keyspace::<u64, User>("users:");
+/// let sessions = db.keyspace::<String, u64>("sessions:");
+/// users.set(&1, &User { name: "alice".to_string() }, None)?;
+/// sessions.set(&"abc".to_string(), &1, None)?;
+/// for (id, user) in users.entries()? {
+///     println!("{}: {}", id, user.name);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Keyspace<K, V> {
+    kv: Kv<Vec<u8>, Vec<u8>>,
+    prefix: Vec<u8>,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<A, B> Kv<A, B> {
+    /// A [`Keyspace`] of this database, for keys starting with `prefix`.
+    pub fn keyspace<K, V>(&self, prefix: &str) -> Keyspace<K, V> {
+        Keyspace {
+            kv: Kv {
+                package_id: self.package_id.clone(),
+                db: self.db.clone(),
+                timeout: self.timeout,
+                _marker: PhantomData,
+            },
+            prefix: prefix.as_bytes().to_vec(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Keyspace<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// The key in the database of `key`.
+    fn full_key(&self, key: &K) -> anyhow::Result<Vec<u8>> {
+        let mut full_key = self.prefix.clone();
+        full_key.extend(serde_json::to_vec(key)?);
+        Ok(full_key)
+    }
+
+    /// Get a value.
+    pub fn get(&self, key: &K) -> anyhow::Result<V> {
+        let bytes = self.kv.get_raw(&self.full_key(key)?)?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))
+    }
+
+    /// Set a value, optionally in a transaction.
+    pub fn set(&self, key: &K, value: &V, tx_id: Option<u64>) -> anyhow::Result<()> {
+        let value = serde_json::to_vec(value)?;
+        self.kv.set_raw(&self.full_key(key)?, &value, tx_id)
+    }
+
+    /// Delete a value, optionally in a transaction.
+    pub fn delete(&self, key: &K, tx_id: Option<u64>) -> anyhow::Result<()> {
+        self.kv.delete_raw(&self.full_key(key)?, tx_id)
+    }
+
+    /// Every key-value pair of the keyspace, ordered by serialized key.
+    pub fn entries(&self) -> anyhow::Result<Vec<(K, V)>> {
+        let mut entries = vec![];
+        self.for_each_page(|pairs| {
+            for (key, value) in pairs {
+                entries.push((
+                    serde_json::from_slice(&key[self.prefix.len()..])?,
+                    serde_json::from_slice(value)
+                        .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?,
+                ));
+            }
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Every key of the keyspace, ordered by serialized key.
+    pub fn keys(&self) -> anyhow::Result<Vec<K>> {
+        let mut keys = vec![];
+        self.for_each_page(|pairs| {
+            for (key, _) in pairs {
+                keys.push(serde_json::from_slice(&key[self.prefix.len()..])?);
+            }
+            Ok(())
+        })?;
+        Ok(keys)
+    }
+
+    /// Delete every key of the keyspace, optionally in a transaction.
+    /// Returns the number of keys deleted.
+    pub fn clear(&self, tx_id: Option<u64>) -> anyhow::Result<u64> {
+        let mut keys = vec![];
+        self.for_each_page(|pairs| {
+            keys.extend(pairs.iter().map(|(key, _)| key.clone()));
+            Ok(())
+        })?;
+        for page in keys.chunks(PAGE_SIZE as usize) {
+            let ops = page.iter().cloned().map(KvBatchOp::Delete).collect();
+            self.kv.batch(ops, tx_id)?;
+        }
+        Ok(keys.len() as u64)
+    }
+
+    /// The approximate number of keys and bytes of the keyspace: see [`Kv::stats()`].
+    pub fn stats(&self) -> anyhow::Result<KvPrefixStats> {
+        self.kv
+            .stats_raw(vec![self.prefix.clone()])?
+            .prefixes
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("kv: no stats for keyspace prefix"))
+    }
+
+    /// Call `f` with each page of the serialized key-value pairs of the keyspace, in order.
+    fn for_each_page<F>(&self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&[(Vec<u8>, Vec<u8>)]) -> anyhow::Result<()>,
+    {
+        let mut start = Bound::Included(self.prefix.clone());
+        loop {
+            let pairs =
+                self.kv
+                    .range_raw(start, prefix_end(&self.prefix), Some(PAGE_SIZE), false)?;
+            f(&pairs)?;
+            match pairs.last() {
+                Some((key, _)) if pairs.len() as u64 == PAGE_SIZE => {
+                    start = Bound::Excluded(key.clone());
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+/// The exclusive upper bound of the keys starting with `prefix`.
+pub(super) fn prefix_end(prefix: &[u8]) -> Bound<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Bound::Excluded(end);
+        }
+    }
+    Bound::Unbounded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_end() {
+        assert_eq!(prefix_end(b"users:"), Bound::Excluded(b"users;".to_vec()));
+        assert_eq!(prefix_end(&[b'a', 0xff]), Bound::Excluded(b"b".to_vec()));
+        assert_eq!(prefix_end(&[0xff]), Bound::Unbounded);
+        assert_eq!(prefix_end(b""), Bound::Unbounded);
+    }
+}
@@ -0,0 +1,284 @@
+use super::{Kv, KvBatchOp, EXPORT_PAGE_SIZE};
+use crate::{timer::set_timer, Message};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{hash_map, HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Bound;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Numbers the timer contexts of [`KvMap`]s, so that each map only takes its own timers.
+static NEXT_MAP_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A [`HashMap`] of every key-value pair of a [`Kv`], loaded once, so reads never
+/// leave the process. Writes change the map and mark the key dirty; dirty keys
+/// are written to the database by [`KvMap::flush()`], when the map is dropped,
+/// and, after [`KvMap::flush_every()`], periodically.
+///
+/// Meant for small state owned by one process: writes to the database by anyone
+/// else are not seen, and are overwritten by flushes of the same keys.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{await_message, kv::{self, KvMap}, our};
+/// use std::time::Duration;
+///
+/// let db = kv::open::<String, u64>(our().package_id(), "state", None)?;
+/// let mut state = KvMap::load(db)?;
+/// state.flush_every(Duration::from_secs(10));
+/// loop {
+///     let Ok(message) = await_message() else { continue };
+///     if state.handle_message(&message) {
+///         continue;
+///     }
+///     *state.entry("messages".to_string()).or_insert(0) += 1;
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct KvMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Hash + Eq + Clone,
+    V: Serialize + DeserializeOwned,
+{
+    kv: Kv<K, V>,
+    map: HashMap<K, V>,
+    dirty: HashSet<K>,
+    flush_interval: Option<Duration>,
+    timer_context: Vec<u8>,
+}
+
+impl<K, V> KvMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Hash + Eq + Clone,
+    V: Serialize + DeserializeOwned,
+{
+    /// Read every key-value pair of `kv`, [`EXPORT_PAGE_SIZE`] at a time.
+    pub fn load(kv: Kv<K, V>) -> anyhow::Result<Self> {
+        let mut map = HashMap::new();
+        let mut start = Bound::Unbounded;
+        loop {
+            let pairs = kv.range_raw(start, Bound::Unbounded, Some(EXPORT_PAGE_SIZE), false)?;
+            let Some((last, _)) = pairs.last() else {
+                break;
+            };
+            start = Bound::Excluded(last.clone());
+            let full_page = pairs.len() as u64 == EXPORT_PAGE_SIZE;
+            for (key, value) in pairs {
+                map.insert(
+                    serde_json::from_slice(&key)?,
+                    serde_json::from_slice(&value)
+                        .map_err(|e| anyhow::anyhow!("Failed to deserialize value: {}", e))?,
+                );
+            }
+            if !full_page {
+                break;
+            }
+        }
+        let id = NEXT_MAP_ID.fetch_add(1, Ordering::Relaxed);
+        Ok(KvMap {
+            kv,
+            map,
+            dirty: HashSet::new(),
+            flush_interval: None,
+            timer_context: format!("kv:map-flush:{}", id).into_bytes(),
+        })
+    }
+    pub fn kv(&self) -> &Kv<K, V> {
+        &self.kv
+    }
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+    /// Get a value mutably, marking its key dirty.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let value = self.map.get_mut(key)?;
+        self.dirty.insert(key.clone());
+        Some(value)
+    }
+    /// The entry of `key`, as [`HashMap::entry()`]. The key is marked dirty only
+    /// when the entry gives out its value mutably, not by reading it.
+    pub fn entry(&mut self, key: K) -> KvMapEntry<'_, K, V> {
+        KvMapEntry {
+            entry: self.map.entry(key),
+            dirty: &mut self.dirty,
+        }
+    }
+    /// Insert a value, marking its key dirty. Returns the previous value, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.dirty.insert(key.clone());
+        self.map.insert(key, value)
+    }
+    /// Remove a value, marking its key dirty so the flush deletes it.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.map.remove(key)?;
+        self.dirty.insert(key.clone());
+        Some(value)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+    /// Whether any key changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Write the dirty keys to the database in one transaction.
+    /// On error, the transaction is rolled back and the keys stay dirty.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+        let ops = self
+            .dirty
+            .iter()
+            .map(|key| {
+                let serialized_key = serde_json::to_vec(key)?;
+                Ok(match self.map.get(key) {
+                    Some(value) => KvBatchOp::Set {
+                        key: serialized_key,
+                        value: serde_json::to_vec(value)?,
+                    },
+                    None => KvBatchOp::Delete(serialized_key),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.kv.transaction(|tx_id| {
+            self.kv.batch(ops, Some(tx_id))?;
+            Ok(())
+        })?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    /// Flush every `interval`, driven by `timer:distro:sys`: pass each message
+    /// to [`KvMap::handle_message()`]. Call once.
+    pub fn flush_every(&mut self, interval: Duration) {
+        self.flush_interval = Some(interval);
+        self.schedule_flush();
+    }
+    fn schedule_flush(&self) {
+        if let Some(interval) = self.flush_interval {
+            set_timer(
+                interval.as_millis() as u64,
+                Some(self.timer_context.clone()),
+            );
+        }
+    }
+    /// If `message` is the timer response of [`KvMap::flush_every()`], flush,
+    /// schedule the next flush, and return `true`; a failed flush is retried then.
+    /// Otherwise, return `false` and leave the message to the caller.
+    pub fn handle_message(&mut self, message: &Message) -> bool {
+        if message.is_request()
+            || !message.is_process("timer:distro:sys")
+            || message.context() != Some(self.timer_context.as_slice())
+        {
+            return false;
+        }
+        let _ = self.flush();
+        self.schedule_flush();
+        true
+    }
+}
+
+/// An entry of a [`KvMap`]: see [`KvMap::entry()`].
+pub struct KvMapEntry<'a, K, V> {
+    entry: hash_map::Entry<'a, K, V>,
+    dirty: &'a mut HashSet<K>,
+}
+
+impl<'a, K, V> KvMapEntry<'a, K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    pub fn key(&self) -> &K {
+        self.entry.key()
+    }
+    /// The value of the entry, if any, without marking its key dirty.
+    pub fn get(&self) -> Option<&V> {
+        match &self.entry {
+            hash_map::Entry::Occupied(entry) => Some(entry.get()),
+            hash_map::Entry::Vacant(_) => None,
+        }
+    }
+    /// Modify the value of the entry, if any, marking its key dirty.
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let hash_map::Entry::Occupied(_) = self.entry {
+            self.dirty.insert(self.entry.key().clone());
+            self.entry = self.entry.and_modify(f);
+        }
+        self
+    }
+    /// As [`hash_map::Entry::or_insert()`], marking the key dirty.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+    /// As [`hash_map::Entry::or_insert_with()`], marking the key dirty.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        self.dirty.insert(self.entry.key().clone());
+        self.entry.or_insert_with(default)
+    }
+    /// As [`hash_map::Entry::or_default()`], marking the key dirty.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<K, V> Drop for KvMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Hash + Eq + Clone,
+    V: Serialize + DeserializeOwned,
+{
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(
+        map: &'a mut HashMap<String, u64>,
+        dirty: &'a mut HashSet<String>,
+        key: &str,
+    ) -> KvMapEntry<'a, String, u64> {
+        KvMapEntry {
+            entry: map.entry(key.to_string()),
+            dirty,
+        }
+    }
+
+    #[test]
+    fn test_entry_marks_dirty_on_write() {
+        let mut map = HashMap::from([("a".to_string(), 1)]);
+        let mut dirty = HashSet::new();
+
+        assert_eq!(entry(&mut map, &mut dirty, "a").get(), Some(&1));
+        entry(&mut map, &mut dirty, "b").and_modify(|value| *value += 1);
+        assert!(dirty.is_empty());
+
+        entry(&mut map, &mut dirty, "a").and_modify(|value| *value += 1);
+        *entry(&mut map, &mut dirty, "b").or_insert(0) += 5;
+        assert_eq!(dirty, HashSet::from(["a".to_string(), "b".to_string()]));
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&5));
+    }
+}
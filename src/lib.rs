@@ -14,6 +14,9 @@
 //! For blobs, we recommend bincode to serialize and deserialize to bytes.
 //!
 pub use crate::kinode::process::standard::*;
+/// Re-exported for [`serve_embedded_ui!`].
+#[doc(hidden)]
+pub use include_dir;
 use serde_json::Value;
 
 wit_bindgen::generate!({
@@ -22,6 +25,25 @@ wit_bindgen::generate!({
     world: "lib",
 });
 
+/// Query and manage installed packages via the app store.
+///
+/// Your process must have the [`Capability`] to message and receive messages from
+/// `main:app-store:sys`, `chain:app-store:sys` and `downloads:app-store:sys`
+/// to use this module.
+pub mod app_store;
+/// A local address book mapping human-friendly aliases to node names, persisted in kv.
+///
+/// Your process must have the [`Capability`] to message and receive messages from
+/// `kv:distro:sys` to use this module.
+pub mod contacts;
+/// Cryptographic helpers: node-attributable signatures made with the
+/// node's networking key, and symmetric encryption of blobs.
+///
+/// Your process must have the [`Capability`] to message and receive messages from
+/// `net:distro:sys` to use the signature functions in this module.
+pub mod crypto;
+/// Helpers for encoding bytes as hex, base64, and bech32 text.
+pub mod encoding;
 /// Interact with the eth provider module.
 pub mod eth;
 /// Interact with the system homepage.
@@ -48,17 +70,60 @@ pub mod kimap;
 pub mod kv;
 #[cfg(feature = "logging")]
 pub mod logging;
+/// A least-recently-used cache bounded by its number of entries, e.g. of files
+/// or database values read by a process.
+pub mod lru;
+/// Counters, gauges and histograms, served in the Prometheus text format
+/// over the HTTP server.
+pub mod metrics;
 /// Interact with the networking module
 /// For configuration, debugging, and creating signatures with networking key.
 ///
 /// Your process must have the [`Capability`] to message and receive messages from
 /// `net:distro:sys` to use this module.
 pub mod net;
+/// Post user-facing notifications.
+///
+/// Your process must have the [`Capability`] to message
+/// `notifications:distro:sys` to use this module.
+pub mod notifications;
+/// Read the `manifest.json` and `metadata.json` of installed packages,
+/// including that of the package this process belongs to.
+///
+/// Your process must have the [`Capability`] to message and receive messages from
+/// `vfs:distro:sys` to use this module.
+pub mod package;
+/// Token-bucket rate limiting, e.g. of incoming messages per source [`Address`].
+pub mod rate_limit;
+/// Record incoming messages to the VFS, to reproduce bugs by replaying them
+/// through the test harness (with the `test_harness` feature enabled).
+///
+/// Your process must have the [`Capability`] to message and receive messages from
+/// `vfs:distro:sys`, and write capability for the drive recorded to, to use this module.
+pub mod recorder;
+/// Graceful shutdown: register cleanup hooks, run them on a conventional shutdown
+/// request, and notify other processes on exit via [`OnExit`].
+pub mod shutdown;
 /// Interact with the sqlite module
 ///
 /// Your process must have the [`Capability] to message and receive messages from
 /// `sqlite:distro:sys` to use this module.
 pub mod sqlite;
+/// Minimal HTML templates with named, escaped placeholders, for the HTML of
+/// widgets and of pages served over HTTP.
+pub mod template;
+/// Helpers for rich terminal output: colors and styles, tables,
+/// and progress indicators.
+pub mod terminal;
+/// An in-memory fake of the runtime, for unit testing process logic without a node.
+///
+/// Enabling the `test_harness` feature replaces the message-passing, blob, state,
+/// and capability functions generated from `kinode.wit` with fakes backed by this
+/// module: script responses per target, queue incoming messages, drive `init`
+/// with [`test_harness::run()`], and assert on the captured requests and responses.
+/// Only enable this feature for tests.
+#[cfg(feature = "test_harness")]
+pub mod test_harness;
 /// Interact with the timer runtime module.
 ///
 /// The `timer:distro:sys` module is public, so no special capabilities needed.
@@ -68,10 +133,20 @@ pub mod timer;
 /// Your process must have the [`Capability`] to message and receive messages from
 /// `vfs:distro:sys` to use this module.
 pub mod vfs;
+/// Heartbeats between supervised processes and their supervisor, which is
+/// notified when heartbeats stop.
+pub mod watchdog;
 
 /// A set of types and macros for writing "script" processes.
 pub mod scripting;
 
+#[cfg(feature = "test_harness")]
+pub use test_harness::shims::{
+    clear_state, drop_capabilities, get_blob, get_state, has_blob, last_blob, our,
+    our_capabilities, print_to_terminal, receive, save_capabilities, send_and_await_response,
+    send_request, send_requests, send_response, set_state,
+};
+
 mod types;
 pub use types::{
     address::{Address, AddressParseError},
@@ -102,6 +177,32 @@ macro_rules! call_init {
     };
 }
 
+/// Serve a UI directory embedded into the process at compile time, so that its
+/// files need not be packaged into the `pkg` drive. Each file is bound as static
+/// content on the given [`http::server::HttpServer`] at its path within the
+/// directory, and `index.html` also at each of the given roots.
+///
+/// The directory must be a string literal, and is resolved at compile time:
+/// use `$CARGO_MANIFEST_DIR` to give it relative to the process crate.
+///
+/// Example:
+/// ```ignore
+/// use kinode_process_lib::{http::server::{HttpBindingConfig, HttpServer}, serve_embedded_ui};
+///
+/// let mut server = HttpServer::new(5);
+/// serve_embedded_ui!(server, "$CARGO_MANIFEST_DIR/ui", vec!["/"], HttpBindingConfig::default())
+///     .unwrap();
+/// ```
+#[macro_export]
+macro_rules! serve_embedded_ui {
+    ($server:expr, $dir:tt, $roots:expr, $config:expr) => {{
+        // the paths emitted by `include_dir!` assume `include_dir` is in scope
+        use $crate::include_dir;
+        static UI: $crate::include_dir::Dir<'static> = $crate::include_dir::include_dir!($dir);
+        $server.serve_embedded_ui(&UI, $roots, $config)
+    }};
+}
+
 /// Override the `println!` macro to print to the terminal.
 /// Uses the `print_to_terminal` function from the WIT interface on maximally-verbose
 /// mode, i.e., this print will always show up in the terminal. To control
@@ -0,0 +1,182 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+//
+// A least-recently-used cache, bounded by its number of entries
+//
+
+/// A cache holding at most `capacity` entries: inserting into a full cache
+/// evicts the entry that was least recently read or written.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::lru::LruCache;
+///
+/// let mut cache = LruCache::new(2);
+/// cache.insert("a", 1);
+/// cache.insert("b", 2);
+/// assert_eq!(cache.get("a"), Some(&1));
+/// cache.insert("c", 3);
+/// // "b" was used least recently, so it was evicted
+/// assert_eq!(cache.get("b"), None);
+/// assert_eq!(cache.len(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    /// Each value, with the tick at which it was last used.
+    entries: HashMap<K, (V, u64)>,
+    /// Keys by the tick at which they were last used, oldest first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    /// A cache holding at most `capacity` entries. A capacity of zero holds nothing.
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Change the capacity, evicting the least recently used entries if the
+    /// cache holds more than the new capacity.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > self.capacity {
+            self.evict();
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Whether the cache holds `key`, without marking it as used.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+    /// Get the value of `key`, marking it as the most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+    /// Get the value of `key` mutably, marking it as the most recently used.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let tick = self.next_tick();
+        let (value, used) = self.entries.get_mut(key)?;
+        if let Some(key) = self.order.remove(used) {
+            self.order.insert(tick, key);
+        }
+        *used = tick;
+        Some(value)
+    }
+    /// Get the value of `key` without marking it as used.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+    /// Insert a value, marking it as the most recently used, and evicting the
+    /// least recently used entry if the cache is full. Returns the previous
+    /// value of `key`, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let previous = self.remove(&key);
+        while self.entries.len() >= self.capacity {
+            self.evict();
+        }
+        let tick = self.next_tick();
+        self.order.insert(tick, key.clone());
+        self.entries.insert(key, (value, tick));
+        previous
+    }
+    /// Remove `key` from the cache, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, used) = self.entries.remove(key)?;
+        self.order.remove(&used);
+        Some(value)
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+    /// Iterate over the entries, from least to most recently used, without
+    /// marking them as used.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.order
+            .values()
+            .filter_map(|key| self.entries.get_key_value(key))
+            .map(|(key, (value, _))| (key, value))
+    }
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+    fn evict(&mut self) {
+        if let Some((_, key)) = self.order.pop_first() {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eviction_order() {
+        let mut cache = LruCache::new(3);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        // reading or replacing an entry makes it the most recently used
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.insert("b", 20), Some(2));
+        cache.insert("d", 4);
+        assert!(!cache.contains_key("c"));
+        let order: Vec<_> = cache.iter().map(|(key, value)| (*key, *value)).collect();
+        assert_eq!(order, vec![("a", 1), ("b", 20), ("d", 4)]);
+        // peeking does not
+        assert_eq!(cache.peek("a"), Some(&1));
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.peek("d"), Some(&4));
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut cache = LruCache::new(0);
+        assert_eq!(cache.insert("a", 1), None);
+        assert!(cache.is_empty());
+    }
+}
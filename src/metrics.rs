@@ -0,0 +1,373 @@
+use crate::http::server::{
+    HttpBindingConfig, HttpResponse, HttpServer, HttpServerError, IncomingHttpRequest,
+};
+use crate::LazyLoadBlob;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::rc::Rc;
+use std::time::Duration;
+
+//
+// Counters, gauges and histograms, rendered in the Prometheus text format
+//
+
+/// The default histogram buckets, in seconds, suited to measuring handler latency.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Label names and values of one series of a metric, e.g. `&[("path", "/api")]`.
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram(Vec<f64>),
+}
+
+#[derive(Clone, Debug)]
+enum Series {
+    Value(f64),
+    Histogram {
+        /// Non-cumulative count of observations in each bucket.
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct Family {
+    help: String,
+    kind: Kind,
+    series: BTreeMap<Vec<(String, String)>, Series>,
+}
+
+/// A set of metrics belonging to a process.
+///
+/// `Registry` is a handle: clones share the same metrics, so a clone can be
+/// kept wherever metrics are recorded or served.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::metrics::Registry;
+///
+/// let registry = Registry::new();
+/// let requests = registry.counter("requests_total", "Requests handled.");
+/// requests.inc(&[("path", "/api")]);
+/// println!("{}", registry.render());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    families: Rc<RefCell<BTreeMap<String, Family>>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn register(&self, name: &str, help: &str, kind: Kind) {
+        self.families
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_insert_with(|| Family {
+                help: help.to_string(),
+                kind,
+                series: BTreeMap::new(),
+            });
+    }
+    fn update<F>(&self, name: &str, labels: Labels, update: F)
+    where
+        F: FnOnce(&Kind, &mut Option<Series>),
+    {
+        let mut families = self.families.borrow_mut();
+        let Some(family) = families.get_mut(name) else {
+            return;
+        };
+        let key = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+        let mut series = family.series.remove(&key);
+        update(&family.kind, &mut series);
+        if let Some(series) = series {
+            family.series.insert(key, series);
+        }
+    }
+    /// Register a counter, a value that only goes up, or get it if already registered.
+    pub fn counter(&self, name: &str, help: &str) -> Counter {
+        self.register(name, help, Kind::Counter);
+        Counter {
+            registry: self.clone(),
+            name: name.to_string(),
+        }
+    }
+    /// Register a gauge, a value that can go up and down, or get it if already registered.
+    pub fn gauge(&self, name: &str, help: &str) -> Gauge {
+        self.register(name, help, Kind::Gauge);
+        Gauge {
+            registry: self.clone(),
+            name: name.to_string(),
+        }
+    }
+    /// Register a histogram with the given bucket upper bounds, or get it if already
+    /// registered. Use [`DEFAULT_BUCKETS`] for latencies in seconds.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> Histogram {
+        let mut buckets = buckets.to_vec();
+        buckets.sort_by(|a, b| a.total_cmp(b));
+        buckets.dedup();
+        self.register(name, help, Kind::Histogram(buckets));
+        Histogram {
+            registry: self.clone(),
+            name: name.to_string(),
+        }
+    }
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in self.families.borrow().iter() {
+            let kind = match family.kind {
+                Kind::Counter => "counter",
+                Kind::Gauge => "gauge",
+                Kind::Histogram(_) => "histogram",
+            };
+            let _ = writeln!(out, "# HELP {} {}", name, escape_help(&family.help));
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, series) in family.series.iter() {
+                match (series, &family.kind) {
+                    (Series::Value(value), _) => {
+                        let _ = writeln!(
+                            out,
+                            "{}{} {}",
+                            name,
+                            render_labels(labels, None),
+                            render_value(*value)
+                        );
+                    }
+                    (Series::Histogram { counts, sum, count }, Kind::Histogram(buckets)) => {
+                        let mut cumulative = 0;
+                        for (bound, bucket_count) in buckets.iter().zip(counts.iter()) {
+                            cumulative += bucket_count;
+                            let _ = writeln!(
+                                out,
+                                "{}_bucket{} {}",
+                                name,
+                                render_labels(labels, Some(&render_value(*bound))),
+                                cumulative
+                            );
+                        }
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            name,
+                            render_labels(labels, Some("+Inf")),
+                            count
+                        );
+                        let labels = render_labels(labels, None);
+                        let _ = writeln!(out, "{}_sum{} {}", name, labels, render_value(*sum));
+                        let _ = writeln!(out, "{}_count{} {}", name, labels, count);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        out
+    }
+    /// An HTTP response serving the rendered metrics, for use as the result of
+    /// the HTTP handler given to [`HttpServer::handle_request()`].
+    pub fn http_response(&self) -> (HttpResponse, Option<LazyLoadBlob>) {
+        (
+            HttpResponse::new(200u16).header("Content-Type", "text/plain; version=0.0.4"),
+            Some(LazyLoadBlob::new(
+                Some("text/plain"),
+                self.render().into_bytes(),
+            )),
+        )
+    }
+    /// Bind a path on the HTTP server to serve metrics from. Requests to it must be
+    /// answered with [`Registry::http_response()`]: see [`Registry::handle()`].
+    ///
+    /// Scrapers usually cannot log in, so consider an unauthenticated,
+    /// local-only binding, e.g. `HttpBindingConfig::new(false, true, false, None)`.
+    pub fn bind(
+        &self,
+        server: &mut HttpServer,
+        path: &str,
+        config: HttpBindingConfig,
+    ) -> Result<(), HttpServerError> {
+        server.bind_http_path(path, config)
+    }
+    /// If the request is to the metrics `path`, return the response serving metrics.
+    /// Call this at the top of an HTTP handler to serve a path bound with [`Registry::bind()`].
+    pub fn handle(
+        &self,
+        path: &str,
+        request: &IncomingHttpRequest,
+    ) -> Option<(HttpResponse, Option<LazyLoadBlob>)> {
+        if request.bound_path(Some(&crate::our().process.to_string())) == path {
+            Some(self.http_response())
+        } else {
+            None
+        }
+    }
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn render_labels(labels: &[(String, String)], le: Option<&str>) -> String {
+    let mut rendered = labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", k, v)
+        })
+        .collect::<Vec<_>>();
+    if let Some(le) = le {
+        rendered.push(format!("le=\"{}\"", le));
+    }
+    if rendered.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", rendered.join(","))
+    }
+}
+
+fn render_value(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else if value.is_nan() {
+        "NaN".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// A value that only goes up, e.g. a number of requests handled.
+#[derive(Clone, Debug)]
+pub struct Counter {
+    registry: Registry,
+    name: String,
+}
+
+impl Counter {
+    pub fn inc(&self, labels: Labels) {
+        self.inc_by(labels, 1.0);
+    }
+    /// Increase the counter by `value`. Negative values are ignored.
+    pub fn inc_by(&self, labels: Labels, value: f64) {
+        if value < 0.0 {
+            return;
+        }
+        self.registry.update(&self.name, labels, |_, series| {
+            let current = match series {
+                Some(Series::Value(current)) => *current,
+                _ => 0.0,
+            };
+            *series = Some(Series::Value(current + value));
+        });
+    }
+}
+
+/// A value that can go up and down, e.g. a number of open connections.
+#[derive(Clone, Debug)]
+pub struct Gauge {
+    registry: Registry,
+    name: String,
+}
+
+impl Gauge {
+    pub fn set(&self, labels: Labels, value: f64) {
+        self.registry.update(&self.name, labels, |_, series| {
+            *series = Some(Series::Value(value));
+        });
+    }
+    pub fn add(&self, labels: Labels, value: f64) {
+        self.registry.update(&self.name, labels, |_, series| {
+            let current = match series {
+                Some(Series::Value(current)) => *current,
+                _ => 0.0,
+            };
+            *series = Some(Series::Value(current + value));
+        });
+    }
+    pub fn inc(&self, labels: Labels) {
+        self.add(labels, 1.0);
+    }
+    pub fn dec(&self, labels: Labels) {
+        self.add(labels, -1.0);
+    }
+}
+
+/// A distribution of observed values, e.g. handler latencies, counted into buckets.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    registry: Registry,
+    name: String,
+}
+
+impl Histogram {
+    pub fn observe(&self, labels: Labels, value: f64) {
+        self.registry.update(&self.name, labels, |kind, series| {
+            let Kind::Histogram(buckets) = kind else {
+                return;
+            };
+            let (mut counts, mut sum, mut count) = match series.take() {
+                Some(Series::Histogram { counts, sum, count }) => (counts, sum, count),
+                _ => (vec![0; buckets.len()], 0.0, 0),
+            };
+            if let Some(i) = buckets.iter().position(|bound| value <= *bound) {
+                counts[i] += 1;
+            }
+            sum += value;
+            count += 1;
+            *series = Some(Series::Histogram { counts, sum, count });
+        });
+    }
+    /// Observe a duration, in seconds.
+    pub fn observe_duration(&self, labels: Labels, duration: Duration) {
+        self.observe(labels, duration.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let registry = Registry::new();
+        let requests = registry.counter("requests_total", "Requests handled.");
+        requests.inc(&[("path", "/api")]);
+        requests.inc_by(&[("path", "/api")], 2.0);
+        registry.gauge("open", "Open channels.").set(&[], 4.0);
+        let latency = registry.histogram("latency_seconds", "Latency.", &[0.1, 1.0]);
+        latency.observe(&[], 0.05);
+        latency.observe(&[], 0.5);
+        latency.observe(&[], 5.0);
+        assert_eq!(
+            registry.render(),
+            "# HELP latency_seconds Latency.\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{le=\"0.1\"} 1\n\
+             latency_seconds_bucket{le=\"1\"} 2\n\
+             latency_seconds_bucket{le=\"+Inf\"} 3\n\
+             latency_seconds_sum 5.55\n\
+             latency_seconds_count 3\n\
+             # HELP open Open channels.\n\
+             # TYPE open gauge\n\
+             open 4\n\
+             # HELP requests_total Requests handled.\n\
+             # TYPE requests_total counter\n\
+             requests_total{path=\"/api\"} 3\n"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::Request;
+use serde::{Deserialize, Serialize};
+
+/// IPC body format for requests sent to `notifications:distro:sys`.
+///
+/// Notifications are namespaced by the source process of the request: a process
+/// can only replace or clear notifications it posted itself.
+/// `notifications:distro:sys` does not respond to these requests.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum NotificationsAction {
+    /// Post a notification to the user. If a notification from the source process
+    /// with the same `dedup_key` is already showing, it is replaced.
+    Push(Notification),
+    /// Clear the notification from the source process with the given dedup key.
+    Clear(String),
+    /// Clear all notifications posted by the source process.
+    ClearAll,
+}
+
+impl From<NotificationsAction> for Vec<u8> {
+    fn from(action: NotificationsAction) -> Self {
+        serde_json::to_vec(&action).unwrap()
+    }
+}
+
+/// How prominently a [`Notification`] should be surfaced to the user.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// A user-facing notification, contained in [`NotificationsAction::Push`].
+///
+/// An action path will be automatically placed underneath the namespace of the process,
+/// in the same manner as a [`crate::homepage::HomepageEntry`] path: clicking the
+/// notification navigates the user there.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::notifications::{Notification, Urgency};
+///
+/// Notification::new("New message", "alice.os sent you a message")
+///     .action_path("/inbox")
+///     .urgency(Urgency::High)
+///     .dedup_key("inbox")
+///     .send();
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub action_path: Option<String>,
+    pub urgency: Urgency,
+    pub dedup_key: Option<String>,
+}
+
+impl Notification {
+    pub fn new<T, U>(title: T, body: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        Notification {
+            title: title.into(),
+            body: body.into(),
+            action_path: None,
+            urgency: Urgency::default(),
+            dedup_key: None,
+        }
+    }
+    /// Set the path the user is taken to when interacting with the notification.
+    pub fn action_path<T>(mut self, path: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.action_path = Some(path.into());
+        self
+    }
+    pub fn urgency(mut self, urgency: Urgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+    /// Set a key to deduplicate on: pushing a notification with the same key
+    /// replaces the previous one rather than adding another, and the key can
+    /// be given to [`clear()`] to remove it.
+    pub fn dedup_key<T>(mut self, key: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.dedup_key = Some(key.into());
+        self
+    }
+    /// Post this notification. Note that the process calling this function must have
+    /// the `notifications:distro:sys` messaging [`crate::Capability`].
+    pub fn send(self) {
+        Request::to(("our", "notifications", "distro", "sys"))
+            .body(NotificationsAction::Push(self))
+            .send()
+            .unwrap();
+    }
+}
+
+/// Post a notification with the given title and body at [`Urgency::Normal`].
+/// Note that the process calling this function must have the
+/// `notifications:distro:sys` messaging [`crate::Capability`].
+///
+/// For more options, build a [`Notification`] directly.
+pub fn notify(title: &str, body: &str) {
+    Notification::new(title, body).send();
+}
+
+/// Clear the notification previously posted by the caller process with the given dedup key.
+/// Note that the process calling this function must have the `notifications:distro:sys`
+/// messaging [`crate::Capability`].
+pub fn clear(dedup_key: &str) {
+    Request::to(("our", "notifications", "distro", "sys"))
+        .body(NotificationsAction::Clear(dedup_key.to_string()))
+        .send()
+        .unwrap();
+}
+
+/// Clear all notifications previously posted by the caller process. Note that the process
+/// calling this function must have the `notifications:distro:sys` messaging [`crate::Capability`].
+pub fn clear_all() {
+    Request::to(("our", "notifications", "distro", "sys"))
+        .body(NotificationsAction::ClearAll)
+        .send()
+        .unwrap();
+}
@@ -0,0 +1,74 @@
+use crate::kernel_types::{Erc721Metadata, PackageManifestEntry};
+use crate::vfs::{open_file, VfsError};
+use crate::{our, PackageId, ProcessId};
+
+//
+// Readers for the metadata files of installed packages
+//
+
+/// The VFS path of a file in the `pkg` drive of a package,
+/// where the contents of the package zip are installed.
+fn pkg_path(package_id: &PackageId, file: &str) -> String {
+    format!("/{}/pkg/{}", package_id, file)
+}
+
+fn read_json<T>(path: &str) -> Result<T, VfsError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let contents = open_file(path, false, None)?.read()?;
+    serde_json::from_slice(&contents).map_err(|e| VfsError::ParseError {
+        error: e.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// Read and deserialize the `manifest.json` of an installed package, which lists
+/// the processes the package starts on install.
+///
+/// The process calling this function must have read capability for the `pkg` drive
+/// of the package, which every process has for its own package.
+pub fn read_manifest(package_id: &PackageId) -> Result<Vec<PackageManifestEntry>, VfsError> {
+    read_json(&pkg_path(package_id, "manifest.json"))
+}
+
+/// Read and deserialize the `metadata.json` of an installed package.
+///
+/// Packages only have this file on their `pkg` drive if they ship it inside
+/// their package zip. The process calling this function must have read capability
+/// for the `pkg` drive of the package, which every process has for its own package.
+pub fn read_metadata(package_id: &PackageId) -> Result<Erc721Metadata, VfsError> {
+    read_json(&pkg_path(package_id, "metadata.json"))
+}
+
+/// Read the `manifest.json` of the package this process belongs to.
+pub fn our_manifest() -> Result<Vec<PackageManifestEntry>, VfsError> {
+    read_manifest(&our().package_id())
+}
+
+/// Read the `metadata.json` of the package this process belongs to.
+pub fn our_metadata() -> Result<Erc721Metadata, VfsError> {
+    read_metadata(&our().package_id())
+}
+
+/// The current version of the package this process belongs to, e.g. `1.0.0`,
+/// as given in its `metadata.json`.
+pub fn our_version() -> Result<String, VfsError> {
+    Ok(our_metadata()?.properties.current_version)
+}
+
+/// The [`ProcessId`]s of all processes in the package this process belongs to,
+/// as listed in its `manifest.json`, including this process.
+pub fn sibling_processes() -> Result<Vec<ProcessId>, VfsError> {
+    let package_id = our().package_id();
+    Ok(read_manifest(&package_id)?
+        .into_iter()
+        .map(|entry| {
+            ProcessId::new(
+                Some(&entry.process_name),
+                package_id.package(),
+                package_id.publisher(),
+            )
+        })
+        .collect())
+}
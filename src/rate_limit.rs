@@ -0,0 +1,205 @@
+use crate::{Address, Message};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+//
+// Token-bucket rate limiting, e.g. of incoming messages per source
+//
+
+/// What to do with a message, as decided by a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// Within the limit: handle it now.
+    Process,
+    /// Over the limit, but a token will be available after the given delay.
+    /// The token has been reserved: handle the message once the delay has passed,
+    /// e.g. by setting a timer with [`crate::timer::set_timer()`].
+    Defer(Duration),
+    /// Over the limit by too much to defer: ignore the message.
+    Drop,
+}
+
+/// Parameters of a token bucket: each key may send `burst` messages at once,
+/// refilled at `per_second` messages per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+    /// The longest delay for which messages over the limit are deferred rather
+    /// than dropped. Zero, the default, drops every message over the limit.
+    pub max_defer: Duration,
+}
+
+impl RateLimit {
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        RateLimit {
+            per_second,
+            burst,
+            max_defer: Duration::ZERO,
+        }
+    }
+    pub fn max_defer(mut self, max_defer: Duration) -> Self {
+        self.max_defer = max_defer;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token-bucket rate limiter keyed by `K`, with one bucket per key.
+///
+/// Buckets that have refilled completely carry no information, so they are
+/// pruned whenever the number of buckets reaches `max_keys`, bounding memory
+/// use in the face of many distinct keys.
+#[derive(Clone, Debug)]
+pub struct RateLimiter<K> {
+    limit: RateLimit,
+    overrides: HashMap<K, RateLimit>,
+    buckets: HashMap<K, Bucket>,
+    max_keys: usize,
+}
+
+impl<K> RateLimiter<K>
+where
+    K: Hash + Eq + Clone,
+{
+    pub fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            overrides: HashMap::new(),
+            buckets: HashMap::new(),
+            max_keys: 10_000,
+        }
+    }
+    /// Set the number of buckets above which full buckets are pruned. Defaults to 10,000.
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = max_keys;
+        self
+    }
+    /// Use a different limit for a specific key, e.g. a trusted peer.
+    pub fn set_override(&mut self, key: K, limit: RateLimit) {
+        self.overrides.insert(key, limit);
+    }
+    pub fn remove_override(&mut self, key: &K) {
+        self.overrides.remove(key);
+    }
+    /// Decide what to do with a message from `key`, consuming a token if it is
+    /// processed or deferred.
+    pub fn check(&mut self, key: &K) -> Decision {
+        self.check_at(key, Instant::now())
+    }
+    /// As [`RateLimiter::check()`], at a given time.
+    pub fn check_at(&mut self, key: &K, now: Instant) -> Decision {
+        let limit = *self.overrides.get(key).unwrap_or(&self.limit);
+        if !self.buckets.contains_key(key) && self.buckets.len() >= self.max_keys {
+            self.prune_at(now);
+        }
+        let bucket = self.buckets.entry(key.clone()).or_insert(Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Decision::Process;
+        }
+        if limit.per_second <= 0.0 {
+            return Decision::Drop;
+        }
+        let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second);
+        if wait <= limit.max_defer {
+            bucket.tokens -= 1.0;
+            Decision::Defer(wait)
+        } else {
+            Decision::Drop
+        }
+    }
+    /// Forget the bucket of a key, restoring its full burst.
+    pub fn reset(&mut self, key: &K) {
+        self.buckets.remove(key);
+    }
+    /// Remove all buckets that have refilled completely.
+    pub fn prune(&mut self) {
+        self.prune_at(Instant::now());
+    }
+    fn prune_at(&mut self, now: Instant) {
+        let limit = self.limit;
+        let overrides = &self.overrides;
+        self.buckets.retain(|key, bucket| {
+            let limit = overrides.get(key).unwrap_or(&limit);
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * limit.per_second < limit.burst as f64
+        });
+    }
+}
+
+/// A [`RateLimiter`] keyed by the source [`Address`] of incoming messages.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{await_message, rate_limit::{Decision, MessageRateLimiter, RateLimit}};
+///
+/// let mut limiter = MessageRateLimiter::new(RateLimit::new(5.0, 20));
+/// loop {
+///     let Ok(message) = await_message() else { continue };
+///     if limiter.check_message(&message) != Decision::Process {
+///         continue;
+///     }
+///     // handle message
+/// }
+/// ```
+pub type MessageRateLimiter = RateLimiter<Address>;
+
+impl RateLimiter<Address> {
+    /// Decide what to do with an incoming message, by its source.
+    pub fn check_message(&mut self, message: &Message) -> Decision {
+        self.check(message.source())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let mut limiter =
+            RateLimiter::new(RateLimit::new(2.0, 2).max_defer(Duration::from_millis(500)));
+        let now = Instant::now();
+        assert_eq!(limiter.check_at(&"a", now), Decision::Process);
+        assert_eq!(limiter.check_at(&"a", now), Decision::Process);
+        assert_eq!(
+            limiter.check_at(&"a", now),
+            Decision::Defer(Duration::from_millis(500))
+        );
+        assert_eq!(limiter.check_at(&"a", now), Decision::Drop);
+        // other keys have their own bucket
+        assert_eq!(limiter.check_at(&"b", now), Decision::Process);
+        // refilled after a second, less the deferred token
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.check_at(&"a", later), Decision::Process);
+        assert_eq!(
+            limiter.check_at(&"a", later),
+            Decision::Defer(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut limiter = RateLimiter::new(RateLimit::new(1.0, 1)).max_keys(1);
+        let now = Instant::now();
+        assert_eq!(limiter.check_at(&"a", now), Decision::Process);
+        assert_eq!(limiter.check_at(&"b", now), Decision::Process);
+        assert_eq!(limiter.buckets.len(), 2);
+        limiter.prune_at(now + Duration::from_secs(1));
+        assert!(limiter.buckets.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::vfs::{open_file, File, VfsError};
+use crate::{LazyLoadBlob, Message};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+//
+// Record incoming messages to the VFS, and replay them in tests
+//
+
+/// A single incoming [`Message`] as recorded by a [`Recorder`].
+///
+/// The blob of the message is always identified by its SHA-256 hash. The blob
+/// itself is only kept if the [`Recorder`] was created with `record_blobs` set.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Position of this message in the recording, starting from 0.
+    pub seq: u64,
+    pub message: Message,
+    /// Hex-encoded SHA-256 hash of the blob bytes, if the message had a blob.
+    pub blob_hash: Option<String>,
+    pub blob_mime: Option<String>,
+    pub blob: Option<Vec<u8>>,
+}
+
+impl RecordedMessage {
+    /// The recorded blob, if the message had one and it was recorded.
+    pub fn lazy_load_blob(&self) -> Option<LazyLoadBlob> {
+        self.blob
+            .as_ref()
+            .map(|bytes| LazyLoadBlob::new(self.blob_mime.clone(), bytes.clone()))
+    }
+}
+
+/// Records every message it is given to a file in the VFS, one JSON-encoded
+/// [`RecordedMessage`] per line.
+///
+/// Writing to the VFS replaces the blob of the message currently being handled,
+/// so fetch the blob with [`Message::blob()`] before recording, and use that copy.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{await_message, recorder::Recorder};
+///
+/// let mut recorder = Recorder::new("/my-package:publisher.os/recording.jsonl", true).unwrap();
+/// loop {
+///     let Ok(message) = await_message() else { continue };
+///     let blob = message.blob();
+///     recorder.record(&message, blob.as_ref()).unwrap();
+///     // handle message, using `blob`
+/// }
+/// ```
+pub struct Recorder {
+    file: File,
+    seq: u64,
+    record_blobs: bool,
+}
+
+impl Recorder {
+    /// Open a recording at the given VFS path, creating it if it does not exist.
+    /// New messages are appended to any already recorded there.
+    ///
+    /// The process must have write capability for the drive the path is in.
+    pub fn new(path: &str, record_blobs: bool) -> Result<Self, VfsError> {
+        let file = open_file(path, true, None)?;
+        let seq = match last_line(&file)? {
+            None => 0,
+            Some(line) => {
+                #[derive(Deserialize)]
+                struct Seq {
+                    seq: u64,
+                }
+                let last: Seq =
+                    serde_json::from_slice(&line).map_err(|e| VfsError::ParseError {
+                        error: e.to_string(),
+                        path: path.to_string(),
+                    })?;
+                last.seq + 1
+            }
+        };
+        Ok(Recorder {
+            file,
+            seq,
+            record_blobs,
+        })
+    }
+    /// Append a message, along with its blob, to the recording.
+    pub fn record(
+        &mut self,
+        message: &Message,
+        blob: Option<&LazyLoadBlob>,
+    ) -> Result<(), VfsError> {
+        let recorded = RecordedMessage {
+            seq: self.seq,
+            message: message.clone(),
+            blob_hash: blob.map(|blob| hash(&blob.bytes)),
+            blob_mime: blob.and_then(|blob| blob.mime.clone()),
+            blob: blob
+                .filter(|_| self.record_blobs)
+                .map(|blob| blob.bytes.clone()),
+        };
+        let mut line = serde_json::to_vec(&recorded).map_err(|e| VfsError::ParseError {
+            error: e.to_string(),
+            path: self.file.path.clone(),
+        })?;
+        line.push(b'\n');
+        self.file.append(&line)?;
+        self.seq += 1;
+        Ok(())
+    }
+}
+
+/// How many bytes [`last_line()`] reads at a time, from the end of the file.
+const TAIL_CHUNK_SIZE: u64 = 4096;
+
+/// The last non-empty line of `file`, read backwards from its end so that the
+/// rest of the file is never loaded.
+fn last_line(file: &File) -> Result<Option<Vec<u8>>, VfsError> {
+    let mut offset = file.metadata()?.len;
+    let mut tail: Vec<u8> = vec![];
+    while offset > 0 {
+        let start = offset.saturating_sub(TAIL_CHUNK_SIZE);
+        let mut chunk = file.read_range(start, offset - start)?;
+        chunk.extend(tail);
+        tail = chunk;
+        offset = start;
+        while tail.last().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            tail.pop();
+        }
+        if let Some(newline) = tail.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(Some(tail.split_off(newline + 1)));
+        }
+    }
+    Ok((!tail.is_empty()).then_some(tail))
+}
+
+fn hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Parse a recording, as written by a [`Recorder`], from its contents.
+pub fn parse_recording(contents: &str) -> serde_json::Result<Vec<RecordedMessage>> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// Load a recording, as written by a [`Recorder`], from the VFS.
+pub fn load_recording(path: &str) -> Result<Vec<RecordedMessage>, VfsError> {
+    let contents = open_file(path, false, None)?.read_to_string()?;
+    parse_recording(&contents).map_err(|e| VfsError::ParseError {
+        error: e.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// Queue recorded messages, in recorded order, in the inbox of the process under
+/// test, to be driven with [`crate::test_harness::run()`].
+///
+/// Messages whose blob was not recorded are replayed without one.
+#[cfg(feature = "test_harness")]
+pub fn replay(recording: &[RecordedMessage]) {
+    let mut recording = recording.iter().collect::<Vec<_>>();
+    recording.sort_by_key(|recorded| recorded.seq);
+    for recorded in recording {
+        crate::test_harness::push_message(recorded.message.clone(), recorded.lazy_load_blob());
+    }
+}
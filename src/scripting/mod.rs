@@ -68,24 +68,119 @@ macro_rules! script {
 ///     </html>"#.to_string();
 /// }
 /// ```
+///
+/// To put data into the HTML without splicing it in with `format!`, render it
+/// with a [`crate::template::Template`], which escapes values.
+///
+/// To show live data, pass a refresh interval in milliseconds as a third argument.
+/// The process will then stay alive, re-rendering the widget with
+/// [`crate::homepage::update_widget()`] each time a timer fires:
+/// ```no_run
+/// kinode_process_lib::widget!("My widget", create_widget, 60_000);
+/// ```
 macro_rules! widget {
     ($widget_label:expr, $create_widget_func:ident) => {
         struct Component;
         impl Guest for Component {
             fn init(_our: String) {
-                use kinode_process_lib::Request;
-                Request::to(("our", "homepage", "homepage", "sys"))
-                    .body(
-                        serde_json::json!({
-                            "Add": {
-                                "label": $widget_label,
-                                "widget": $create_widget_func(),
-                            }
-                        })
-                        .to_string(),
-                    )
-                    .send()
-                    .unwrap();
+                use kinode_process_lib::homepage::add_entry;
+                add_entry($widget_label, None, None, Some(&$create_widget_func()));
+            }
+        }
+        export!(Component);
+    };
+    ($widget_label:expr, $create_widget_func:ident, $refresh_ms:expr) => {
+        struct Component;
+        impl Guest for Component {
+            fn init(_our: String) {
+                use kinode_process_lib::{
+                    await_message,
+                    homepage::{add_entry, update_widget},
+                    timer::set_timer,
+                };
+                add_entry($widget_label, None, None, Some(&$create_widget_func()));
+                set_timer($refresh_ms, None);
+                loop {
+                    let Ok(message) = await_message() else {
+                        continue;
+                    };
+                    if !message.is_request() && message.is_process("timer:distro:sys") {
+                        update_widget(&$create_widget_func());
+                        set_timer($refresh_ms, None);
+                    }
+                }
+            }
+        }
+        export!(Component);
+    };
+}
+
+#[macro_export]
+/// A macro for writing a long-running "daemon" process with persistent state:
+/// the counterpart to [`crate::script!`] for background processes. Using this
+/// will create the standard `init` function, which:
+/// 1. Parses the `our` string into an `Address` object.
+/// 2. Loads the state saved by a previous run of the process, or uses `Default` if none.
+/// 3. Waits for messages in a loop, passing each to the handler you provide along
+///    with a mutable reference to the state.
+/// 4. Saves the state, serialized to JSON, whenever the handler has changed it.
+///
+/// The state type must implement `Default`, `serde::Serialize` and `serde::Deserialize`,
+/// and the process must depend on `serde_json`.
+/// The handler must be a function `fn(&Address, &mut State, Message) -> anyhow::Result<()>`.
+/// Errors returned by the handler and errors receiving messages are printed,
+/// and the loop continues.
+///
+/// Example:
+/// ```no_run
+/// wit_bindgen::generate!({
+///     path: "target/wit",
+///     world: "process-v0",
+/// });
+///
+/// use kinode_process_lib::{Address, Message};
+///
+/// #[derive(Default, serde::Serialize, serde::Deserialize)]
+/// struct State {
+///     messages_seen: u64,
+/// }
+///
+/// kinode_process_lib::daemon!(State, handle_message);
+///
+/// fn handle_message(_our: &Address, state: &mut State, _message: Message) -> anyhow::Result<()> {
+///     state.messages_seen += 1;
+///     Ok(())
+/// }
+/// ```
+macro_rules! daemon {
+    ($state_type:ty, $handle_message_func:ident) => {
+        struct Component;
+        impl Guest for Component {
+            fn init(our: String) {
+                use kinode_process_lib::{
+                    await_message, get_typed_state, println, set_state, Address,
+                };
+                let our: Address = our.parse().unwrap();
+                let mut state: $state_type =
+                    get_typed_state(|bytes| serde_json::from_slice(bytes)).unwrap_or_default();
+                let mut saved = serde_json::to_vec(&state).unwrap();
+                loop {
+                    let message = match await_message() {
+                        Ok(message) => message,
+                        Err(e) => {
+                            println!("{}: {e:?}", our.process());
+                            continue;
+                        }
+                    };
+                    if let Err(e) = $handle_message_func(&our, &mut state, message) {
+                        println!("{}: {e:?}", our.process());
+                    }
+                    let current = serde_json::to_vec(&state).unwrap();
+                    if current != saved {
+                        set_state(&current);
+                        saved = current;
+                    }
+                }
             }
         }
         export!(Component);
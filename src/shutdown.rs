@@ -0,0 +1,135 @@
+use crate::{our, Address, Message, OnExit, Request};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+
+//
+// Graceful shutdown: cleanup hooks and conventional shutdown messages
+//
+
+/// IPC body format for requests asking a process to shut down gracefully.
+///
+/// Only honored by [`handle_shutdown_request()`] when sent from the same node.
+/// No response is expected.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownRequest {
+    Shutdown { reason: Option<String> },
+}
+
+impl From<ShutdownRequest> for Vec<u8> {
+    fn from(request: ShutdownRequest) -> Self {
+        serde_json::to_vec(&request).unwrap()
+    }
+}
+
+/// IPC body format for the conventional "shutting down" message, sent on exit
+/// by processes that call [`notify_on_exit()`] to the processes they name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownNotice {
+    /// The given process has exited, whether gracefully or not.
+    ShuttingDown { process: Address },
+}
+
+impl From<ShutdownNotice> for Vec<u8> {
+    fn from(notice: ShutdownNotice) -> Self {
+        serde_json::to_vec(&notice).unwrap()
+    }
+}
+
+/// A named cleanup closure registered with [`on_shutdown()`].
+type Hook = (String, Box<dyn FnOnce()>);
+
+thread_local! {
+    static HOOKS: RefCell<Vec<Hook>> = RefCell::new(Vec::new());
+}
+
+/// Register a cleanup closure to be run by [`run_shutdown_hooks()`], e.g. to flush
+/// logs, close WebSocket channels, or persist state. The name is printed at
+/// verbosity 2 when the hook runs.
+///
+/// Hooks run in the reverse of the order they were registered in, so a hook may
+/// rely on anything set up before it was registered.
+pub fn on_shutdown<T, F>(name: T, hook: F)
+where
+    T: Into<String>,
+    F: FnOnce() + 'static,
+{
+    HOOKS.with(|hooks| hooks.borrow_mut().push((name.into(), Box::new(hook))));
+}
+
+/// Run and remove all registered shutdown hooks. Hooks registered while running
+/// hooks are also run.
+///
+/// The runtime cannot run code in a process after it has exited, so this must be
+/// called before returning from `init()`: see [`handle_shutdown_request()`].
+pub fn run_shutdown_hooks() {
+    while let Some((name, hook)) = HOOKS.with(|hooks| hooks.borrow_mut().pop()) {
+        crate::print_to_terminal(2, &format!("shutdown: running hook {}", name));
+        hook();
+    }
+}
+
+/// Set the [`OnExit`] behavior of this process to send a [`ShutdownNotice`] to
+/// each of `targets` when it exits, for any reason, including a crash.
+///
+/// This replaces any previously set `OnExit` behavior: in particular, a process
+/// that notifies on exit is not restarted.
+pub fn notify_on_exit(targets: &[Address]) {
+    let process = our();
+    OnExit::Requests(
+        targets
+            .iter()
+            .map(|target| {
+                Request::to(target).body(ShutdownNotice::ShuttingDown {
+                    process: process.clone(),
+                })
+            })
+            .collect(),
+    )
+    .set()
+    .unwrap();
+}
+
+/// If `message` is a [`ShutdownRequest`] from our node, run the shutdown hooks
+/// and return `true`, in which case the process should return from `init()`.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{await_message, shutdown};
+///
+/// loop {
+///     let Ok(message) = await_message() else { continue };
+///     if shutdown::handle_shutdown_request(&message) {
+///         return;
+///     }
+///     // handle message
+/// }
+/// ```
+pub fn handle_shutdown_request(message: &Message) -> bool {
+    if !message.is_request() || message.source().node != our().node {
+        return false;
+    }
+    let Ok(ShutdownRequest::Shutdown { reason }) =
+        serde_json::from_slice::<ShutdownRequest>(message.body())
+    else {
+        return false;
+    };
+    crate::print_to_terminal(
+        1,
+        &format!(
+            "shutdown: shutting down{}",
+            reason.map(|r| format!(": {}", r)).unwrap_or_default()
+        ),
+    );
+    run_shutdown_hooks();
+    true
+}
+
+/// Ask a process to shut down gracefully by sending it a [`ShutdownRequest`].
+pub fn request_shutdown(target: &Address, reason: Option<&str>) {
+    Request::to(target)
+        .body(ShutdownRequest::Shutdown {
+            reason: reason.map(|r| r.to_string()),
+        })
+        .send()
+        .unwrap();
+}
@@ -1,8 +1,18 @@
 use crate::{get_blob, Message, PackageId, Request};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
 
+pub mod query;
+pub mod schema;
+
+pub use query::*;
+pub use schema::*;
+
+/// The most parameters [`Sqlite::insert_many()`] binds in one statement: the
+/// lowest default limit of SQLite versions in use.
+pub const MAX_PARAMS: usize = 999;
+
 /// Actions are sent to a specific SQLite database. `db` is the name,
 /// `package_id` is the [`PackageId`] that created the database. Capabilities
 /// are checked: you can access another process's database if it has given
@@ -84,6 +94,68 @@ pub enum SqliteAction {
     /// A successful commit will respond with [`SqliteResponse::Ok`]. Any error will be
     /// contained in the [`SqliteResponse::Err`] variant.
     Commit { tx_id: u64 },
+    /// Discards all operations in the specified transaction.
+    ///
+    /// # Parameters
+    /// * `tx_id` - The ID of the transaction to roll back
+    ///
+    /// A successful rollback will respond with [`SqliteResponse::Ok`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    Rollback { tx_id: u64 },
+    /// Parses a statement once, keeping it for [`SqliteAction::QueryPrepared`] and
+    /// [`SqliteAction::WritePrepared`] until [`SqliteAction::Finalize`].
+    ///
+    /// A successful prepare will respond with [`SqliteResponse::Prepare`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    Prepare(String),
+    /// Executes a prepared read query, as [`SqliteAction::Query`].
+    ///
+    /// * blob: Vec<SqlValue> - Parameters for the SQL query
+    ///
+    /// Using this action requires the sender to have the read capability
+    /// for the database.
+    ///
+    /// A successful query will respond with [`SqliteResponse::Read`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    QueryPrepared { statement_id: u64 },
+    /// Executes a prepared write statement, as [`SqliteAction::Write`].
+    ///
+    /// * blob: Vec<SqlValue> - Parameters for the SQL statement
+    ///
+    /// Using this action requires the sender to have the write capability
+    /// for the database.
+    ///
+    /// A successful write will respond with [`SqliteResponse::Ok`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    WritePrepared {
+        statement_id: u64,
+        tx_id: Option<u64>,
+    },
+    /// Releases a prepared statement.
+    ///
+    /// A successful finalize will respond with [`SqliteResponse::Ok`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    Finalize { statement_id: u64 },
+    /// Copies the database to a new file at `path` in the VFS, replacing any
+    /// existing file, with SQLite's online backup, so the copy is consistent
+    /// even while other processes write.
+    ///
+    /// Using this action requires the sender to have the read capability for
+    /// the database and the write capability for the drive of `path`.
+    ///
+    /// A successful backup will respond with [`SqliteResponse::Ok`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    Backup { path: String },
+    /// Replaces the database with the backup at `path` in the VFS, as written by
+    /// [`SqliteAction::Backup`], creating the database if it doesn't exist.
+    /// Requires `package_id` in [`SqliteRequest`] to match the package ID of the sender.
+    ///
+    /// Using this action requires the sender to have the read capability for
+    /// the drive of `path`.
+    ///
+    /// A successful restore will respond with [`SqliteResponse::Ok`]. Any error will be
+    /// contained in the [`SqliteResponse::Err`] variant.
+    Restore { path: String },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -106,6 +178,8 @@ pub enum SqliteResponse {
     /// # Fields
     /// * `tx_id` - The ID of the newly created transaction
     BeginTx { tx_id: u64 },
+    /// Returns the ID of a statement prepared with [`SqliteAction::Prepare`].
+    Prepare { statement_id: u64 },
     /// Indicates an error occurred during the operation.
     Err(SqliteError),
 }
@@ -147,6 +221,8 @@ pub enum SqliteError {
     RusqliteError(String),
     #[error("IO error: {0}")]
     IOError(String),
+    #[error("no prepared statement {0} found")]
+    NoStatement(u64),
 }
 
 /// The JSON parameters contained in all capabilities issued by `sqlite:distro:sys`.
@@ -307,6 +383,593 @@ impl Sqlite {
             _ => Err(anyhow::anyhow!("sqlite: unexpected message: {:?}", res)),
         }
     }
+
+    /// Roll back a transaction, discarding its writes.
+    pub fn rollback_tx(&self, tx_id: u64) -> anyhow::Result<()> {
+        match self.send(SqliteAction::Rollback { tx_id }, None)? {
+            SqliteResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!(
+                "sqlite: unexpected response {:?}",
+                response
+            )),
+        }
+    }
+
+    /// Run `f` in a new transaction, committing it if `f` succeeds and rolling
+    /// it back if `f` fails, returning the result of `f`.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{our, sqlite};
+    /// use serde_json::json;
+    ///
+    /// let db = sqlite::open(our().package_id(), "bank", None)?;
+    /// db.transaction(|tx| {
+    ///     tx.write(
+    ///         "UPDATE accounts SET balance = balance - ? WHERE id = ?".to_string(),
+    ///         vec![json!(10), json!("alice")],
+    ///     )?;
+    ///     tx.write(
+    ///         "UPDATE accounts SET balance = balance + ? WHERE id = ?".to_string(),
+    ///         vec![json!(10), json!("bob")],
+    ///     )
+    /// })?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn transaction<T, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        F: FnOnce(&Transaction) -> anyhow::Result<T>,
+    {
+        let tx = Transaction {
+            sqlite: self,
+            id: self.begin_tx()?,
+        };
+        match f(&tx) {
+            Ok(result) => {
+                self.commit_tx(tx.id)?;
+                Ok(result)
+            }
+            Err(error) => match self.rollback_tx(tx.id) {
+                Ok(()) => Err(error),
+                Err(rollback_error) => Err(error.context(format!(
+                    "sqlite: rollback of transaction {} failed: {}",
+                    tx.id, rollback_error
+                ))),
+            },
+        }
+    }
+
+    /// Insert `rows`, each with a value for each of `columns`, in one transaction,
+    /// as few multi-row `INSERT` statements as [`MAX_PARAMS`] allows.
+    /// Returns the number of rows inserted.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{our, sqlite};
+    /// use serde_json::json;
+    ///
+    /// let db = sqlite::open(our().package_id(), "indexer", None)?;
+    /// let rows = (0..10_000)
+    ///     .map(|block| vec![json!(block), json!("...")])
+    ///     .collect::<Vec<_>>();
+    /// db.insert_many("events", &["block", "data"], rows)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn insert_many(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: Vec<Vec<serde_json::Value>>,
+    ) -> anyhow::Result<u64> {
+        if columns.is_empty() || columns.len() > MAX_PARAMS {
+            return Err(anyhow::anyhow!(
+                "sqlite: insert_many needs between 1 and {} columns, got {}",
+                MAX_PARAMS,
+                columns.len()
+            ));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != columns.len()) {
+            return Err(anyhow::anyhow!(
+                "sqlite: insert_many row has {} values for {} columns",
+                row.len(),
+                columns.len()
+            ));
+        }
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        let rows_per_statement = MAX_PARAMS / columns.len();
+        self.transaction(|tx| {
+            for chunk in rows.chunks(rows_per_statement) {
+                tx.write(
+                    insert_statement(table, columns, chunk.len()),
+                    chunk.concat(),
+                )?;
+            }
+            Ok(rows.len() as u64)
+        })
+    }
+
+    /// Copy the database to a new file at `path` in the VFS, replacing any
+    /// existing file: see [`SqliteAction::Backup`]. Restore it with [`restore_from_vfs()`].
+    pub fn backup_to_vfs(&self, path: &str) -> anyhow::Result<()> {
+        let action = SqliteAction::Backup {
+            path: path.to_string(),
+        };
+        match self.send(action, None)? {
+            SqliteResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!(
+                "sqlite: unexpected response {:?}",
+                response
+            )),
+        }
+    }
+
+    /// Query database a page of `page_size` rows at a time, by appending
+    /// `ORDER BY {order_by} LIMIT ? OFFSET ?` to `query`, so a large result isn't
+    /// sent in one message. Only allows sqlite read keywords.
+    ///
+    /// The columns of `order_by` are quoted with [`quote_identifier()`].
+    /// `query` must not have its own `ORDER BY` or `LIMIT`, and `order_by` should
+    /// make the order of rows total, e.g. by ending with a unique column:
+    /// otherwise, pages may overlap or skip rows. Writes between pages can
+    /// also shift rows across pages.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{our, sqlite::{self, Order}};
+    ///
+    /// let db = sqlite::open(our().package_id(), "indexer", None)?;
+    /// let order_by = [("block", Order::Desc), ("id", Order::Asc)];
+    /// let pages = db.read_paged("SELECT * FROM events", &order_by, vec![], 1000);
+    /// for page in pages {
+    ///     for row in page? {
+    ///         println!("{:?}", row);
+    ///     }
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn read_paged(
+        &self,
+        query: &str,
+        order_by: &[(&str, Order)],
+        params: Vec<serde_json::Value>,
+        page_size: u64,
+    ) -> PagedRead {
+        PagedRead {
+            sqlite: self.clone(),
+            query: format!(
+                "{}{} LIMIT ? OFFSET ?",
+                query.trim().trim_end_matches(';'),
+                order_by_clause(order_by)
+            ),
+            params,
+            page_size: page_size.max(1),
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Query database, deserializing each row into a `T` whose fields are named
+    /// as the columns of the result. Only allows sqlite read keywords.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{our, sqlite};
+    /// use serde::Deserialize;
+    /// use serde_json::json;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Event {
+    ///     block: u64,
+    ///     data: String,
+    /// }
+    ///
+    /// let db = sqlite::open(our().package_id(), "indexer", None)?;
+    /// let events: Vec<Event> = db.query_as(
+    ///     "SELECT block, data FROM events WHERE block > ?".to_string(),
+    ///     vec![json!(100)],
+    /// )?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn query_as<T>(
+        &self,
+        query: String,
+        params: Vec<serde_json::Value>,
+    ) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.read(query, params)?
+            .into_iter()
+            .map(deserialize_row)
+            .collect()
+    }
+
+    /// Query database, converting each row with [`FromRow`].
+    /// Only allows sqlite read keywords.
+    pub fn query_rows<T>(
+        &self,
+        query: String,
+        params: Vec<serde_json::Value>,
+    ) -> anyhow::Result<Vec<T>>
+    where
+        T: FromRow,
+    {
+        self.read(query, params)?
+            .into_iter()
+            .map(T::from_row)
+            .collect()
+    }
+
+    /// Parse `statement` once in the runtime module, to execute repeatedly with
+    /// different parameters. The statement is released when the [`Statement`] is dropped.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::{our, sqlite};
+    /// use serde_json::json;
+    ///
+    /// let db = sqlite::open(our().package_id(), "indexer", None)?;
+    /// let insert = db.prepare("INSERT INTO events (block, data) VALUES (?, ?)")?;
+    /// for block in 0..100 {
+    ///     insert.write(vec![json!(block), json!("...")], None)?;
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn prepare(&self, statement: &str) -> anyhow::Result<Statement> {
+        match self.send(SqliteAction::Prepare(statement.to_string()), None)? {
+            SqliteResponse::Prepare { statement_id } => Ok(Statement {
+                sqlite: self.clone(),
+                id: statement_id,
+                statement: statement.to_string(),
+            }),
+            response => Err(anyhow::anyhow!(
+                "sqlite: unexpected response {:?}",
+                response
+            )),
+        }
+    }
+
+    /// Send `action`, with `blob` if given, and await the response, mapping
+    /// [`SqliteResponse::Err`] to an error.
+    fn send(&self, action: SqliteAction, blob: Option<Vec<u8>>) -> anyhow::Result<SqliteResponse> {
+        let body = serde_json::to_vec(&SqliteRequest {
+            package_id: self.package_id.clone(),
+            db: self.db.clone(),
+            action,
+        })?;
+        let mut request = Request::new()
+            .target(("our", "sqlite", "distro", "sys"))
+            .body(body);
+        if let Some(blob) = blob {
+            request = request.blob_bytes(blob);
+        }
+        let res = request.send_and_await_response(self.timeout)?;
+
+        match res {
+            Ok(Message::Response { body, .. }) => {
+                match serde_json::from_slice::<SqliteResponse>(&body)? {
+                    SqliteResponse::Err(error) => Err(error.into()),
+                    response => Ok(response),
+                }
+            }
+            _ => Err(anyhow::anyhow!("sqlite: unexpected message: {:?}", res)),
+        }
+    }
+}
+
+/// A transaction of [`Sqlite::transaction()`].
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    sqlite: &'a Sqlite,
+    id: u64,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// The database, for reads, which are not part of the transaction.
+    pub fn sqlite(&self) -> &'a Sqlite {
+        self.sqlite
+    }
+    /// Execute a statement in the transaction. Only allows sqlite write keywords.
+    pub fn write(&self, statement: String, params: Vec<serde_json::Value>) -> anyhow::Result<()> {
+        self.sqlite.write(statement, params, Some(self.id))
+    }
+    /// Execute a prepared statement in the transaction.
+    pub fn write_prepared(
+        &self,
+        statement: &Statement,
+        params: Vec<serde_json::Value>,
+    ) -> anyhow::Result<()> {
+        statement.write(params, Some(self.id))
+    }
+}
+
+/// The direction in which [`Sqlite::read_paged()`] orders rows by a column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// The pages of a query, fetched one message each: see [`Sqlite::read_paged()`].
+/// Iterates over the pages, stopping after the first error.
+#[derive(Debug)]
+pub struct PagedRead {
+    sqlite: Sqlite,
+    query: String,
+    params: Vec<serde_json::Value>,
+    page_size: u64,
+    offset: u64,
+    done: bool,
+}
+
+impl PagedRead {
+    /// The number of rows fetched so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// Fetch the next page, or `None` if the previous page was the last.
+    pub fn next_page(&mut self) -> anyhow::Result<Option<Vec<Row>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut params = self.params.clone();
+        params.push(self.page_size.into());
+        params.push(self.offset.into());
+        let rows = match self.sqlite.read(self.query.clone(), params) {
+            Ok(rows) => rows,
+            Err(e) => {
+                self.done = true;
+                return Err(e);
+            }
+        };
+        self.offset += rows.len() as u64;
+        if (rows.len() as u64) < self.page_size {
+            self.done = true;
+            if rows.is_empty() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(rows))
+    }
+    /// Fetch the next page, deserializing each row as [`Sqlite::query_as()`].
+    pub fn next_page_as<T>(&mut self) -> anyhow::Result<Option<Vec<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let Some(rows) = self.next_page()? else {
+            return Ok(None);
+        };
+        rows.into_iter()
+            .map(deserialize_row)
+            .collect::<anyhow::Result<_>>()
+            .map(Some)
+    }
+}
+
+impl Iterator for PagedRead {
+    type Item = anyhow::Result<Vec<Row>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_page().transpose()
+    }
+}
+
+/// A row of a query result, by column name.
+pub type Row = HashMap<String, serde_json::Value>;
+
+/// Conversion from a [`Row`], for types that don't map to their columns through
+/// serde, as [`Sqlite::query_as()`] requires: see [`Sqlite::query_rows()`].
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::sqlite::{FromRow, Row};
+///
+/// struct Balance {
+///     address: String,
+///     wei: u128,
+/// }
+///
+/// impl FromRow for Balance {
+///     fn from_row(row: Row) -> anyhow::Result<Self> {
+///         let text = |column: &str| {
+///             row.get(column)
+///                 .and_then(|value| value.as_str())
+///                 .ok_or_else(|| anyhow::anyhow!("missing {}", column))
+///         };
+///         Ok(Balance {
+///             address: text("address")?.to_string(),
+///             // stored as TEXT, as it overflows an INTEGER
+///             wei: text("wei")?.parse()?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromRow: Sized {
+    fn from_row(row: Row) -> anyhow::Result<Self>;
+}
+
+fn deserialize_row<T: DeserializeOwned>(row: Row) -> anyhow::Result<T> {
+    serde_json::from_value(serde_json::Value::Object(row.into_iter().collect()))
+        .map_err(|e| anyhow::anyhow!("sqlite: failed to deserialize row: {}", e))
+}
+
+/// The rows in the blob of a [`SqliteResponse::Read`].
+fn read_rows() -> anyhow::Result<Vec<HashMap<String, serde_json::Value>>> {
+    let blob = get_blob().ok_or_else(|| SqliteError::MalformedRequest)?;
+    let rows = serde_json::from_slice::<Vec<HashMap<String, serde_json::Value>>>(&blob.bytes)
+        .map_err(|_| SqliteError::MalformedRequest)?;
+    Ok(rows)
+}
+
+/// A statement prepared with [`Sqlite::prepare()`], released when dropped.
+#[derive(Debug)]
+pub struct Statement {
+    sqlite: Sqlite,
+    id: u64,
+    statement: String,
+}
+
+impl Statement {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn statement(&self) -> &str {
+        &self.statement
+    }
+
+    /// Execute the statement as a query. Only allows sqlite read keywords.
+    pub fn read(
+        &self,
+        params: Vec<serde_json::Value>,
+    ) -> anyhow::Result<Vec<HashMap<String, serde_json::Value>>> {
+        let action = SqliteAction::QueryPrepared {
+            statement_id: self.id,
+        };
+        match self
+            .sqlite
+            .send(action, Some(serde_json::to_vec(&params)?))?
+        {
+            SqliteResponse::Read => read_rows(),
+            response => Err(anyhow::anyhow!(
+                "sqlite: unexpected response {:?}",
+                response
+            )),
+        }
+    }
+
+    /// Execute the statement as a query, deserializing each row as [`Sqlite::query_as()`].
+    pub fn read_as<T>(&self, params: Vec<serde_json::Value>) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.read(params)?
+            .into_iter()
+            .map(deserialize_row)
+            .collect()
+    }
+
+    /// Execute the statement as a write. Only allows sqlite write keywords.
+    pub fn write(&self, params: Vec<serde_json::Value>, tx_id: Option<u64>) -> anyhow::Result<()> {
+        let action = SqliteAction::WritePrepared {
+            statement_id: self.id,
+            tx_id,
+        };
+        match self
+            .sqlite
+            .send(action, Some(serde_json::to_vec(&params)?))?
+        {
+            SqliteResponse::Ok => Ok(()),
+            response => Err(anyhow::anyhow!(
+                "sqlite: unexpected response {:?}",
+                response
+            )),
+        }
+    }
+}
+
+impl Drop for Statement {
+    fn drop(&mut self) {
+        let _ = Request::new()
+            .target(("our", "sqlite", "distro", "sys"))
+            .body(
+                serde_json::to_vec(&SqliteRequest {
+                    package_id: self.sqlite.package_id.clone(),
+                    db: self.sqlite.db.clone(),
+                    action: SqliteAction::Finalize {
+                        statement_id: self.id,
+                    },
+                })
+                .unwrap(),
+            )
+            .send();
+    }
+}
+
+/// Replace the database `db` of `package_id` with the backup at `path` in the VFS,
+/// written by [`Sqlite::backup_to_vfs()`], creating the database if it doesn't exist.
+/// Returns the restored database.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{our, sqlite};
+///
+/// let db = sqlite::open(our().package_id(), "indexer", None)?;
+/// db.backup_to_vfs("/my-app:template.os/backups/indexer.db")?;
+/// // ... e.g. on another node, or after corruption
+/// let db = sqlite::restore_from_vfs(
+///     our().package_id(),
+///     "indexer",
+///     "/my-app:template.os/backups/indexer.db",
+///     None,
+/// )?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn restore_from_vfs(
+    package_id: PackageId,
+    db: &str,
+    path: &str,
+    timeout: Option<u64>,
+) -> anyhow::Result<Sqlite> {
+    let sqlite = Sqlite {
+        package_id,
+        db: db.to_string(),
+        timeout: timeout.unwrap_or(5),
+    };
+    let action = SqliteAction::Restore {
+        path: path.to_string(),
+    };
+    match sqlite.send(action, None)? {
+        SqliteResponse::Ok => Ok(sqlite),
+        response => Err(anyhow::anyhow!(
+            "sqlite: unexpected response {:?}",
+            response
+        )),
+    }
+}
+
+/// Quote `identifier`, e.g. a table or column name, for use in a statement.
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// ` ORDER BY "column" ASC, ...`, or nothing if `order_by` is empty.
+fn order_by_clause(order_by: &[(&str, Order)]) -> String {
+    if order_by.is_empty() {
+        return String::new();
+    }
+    let columns = order_by
+        .iter()
+        .map(|(column, order)| {
+            let direction = match order {
+                Order::Asc => "ASC",
+                Order::Desc => "DESC",
+            };
+            format!("{} {}", quote_identifier(column), direction)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(" ORDER BY {}", columns)
+}
+
+/// `INSERT INTO table (columns) VALUES (?, ...), ...` for `rows` rows.
+fn insert_statement(table: &str, columns: &[&str], rows: usize) -> String {
+    let columns_list = columns
+        .iter()
+        .map(|column| quote_identifier(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let row = format!("({})", vec!["?"; columns.len()].join(", "));
+    format!(
+        "INSERT INTO {} ({}) VALUES {}",
+        quote_identifier(table),
+        columns_list,
+        vec![row; rows].join(", ")
+    )
 }
 
 /// Open or create sqlite database.
@@ -372,3 +1035,25 @@ pub fn remove_db(package_id: PackageId, db: &str, timeout: Option<u64>) -> anyho
         _ => Err(anyhow::anyhow!("sqlite: unexpected message: {:?}", res)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_statement() {
+        assert_eq!(
+            insert_statement("events", &["block", "da\"ta"], 2),
+            r#"INSERT INTO "events" ("block", "da""ta") VALUES (?, ?), (?, ?)"#
+        );
+    }
+
+    #[test]
+    fn test_order_by_clause() {
+        assert_eq!(order_by_clause(&[]), "");
+        assert_eq!(
+            order_by_clause(&[("block", Order::Desc), ("id; DROP TABLE x", Order::Asc)]),
+            r#" ORDER BY "block" DESC, "id; DROP TABLE x" ASC"#
+        );
+    }
+}
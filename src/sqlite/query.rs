@@ -0,0 +1,248 @@
+use super::{deserialize_row, quote_identifier, Row, SqlValue, Sqlite};
+use serde::de::DeserializeOwned;
+
+/// A comparison of a [`Query::filter()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+}
+
+impl Op {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Like => "LIKE",
+        }
+    }
+}
+
+/// A `SELECT` of one table, built into a statement with `?` parameters for
+/// every value, and quoted identifiers, so that values never end up in the SQL.
+/// Filters are joined with `AND`.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::sqlite::{Op::*, Query, SqlValue};
+///
+/// let (sql, params) = Query::select("events")
+///     .filter("block", Gt, 100)
+///     .filter("kind", Eq, "transfer")
+///     .order_by("block")
+///     .limit(100)
+///     .build();
+/// assert_eq!(
+///     sql,
+///     r#"SELECT * FROM "events" WHERE "block" > ? AND "kind" = ? ORDER BY "block" ASC LIMIT 100"#
+/// );
+/// assert_eq!(params, vec![SqlValue::Integer(100), SqlValue::Text("transfer".to_string())]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    table: String,
+    columns: Vec<String>,
+    filters: Vec<(String, Op, SqlValue)>,
+    order_by: Vec<(String, bool)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl Query {
+    /// Select every column of `table`.
+    pub fn select(table: &str) -> Self {
+        Query {
+            table: table.to_string(),
+            columns: vec![],
+            filters: vec![],
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        }
+    }
+    /// Select only `columns`.
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+    /// Keep the rows whose `column` compares to `value` with `op`.
+    pub fn filter<T>(mut self, column: &str, op: Op, value: T) -> Self
+    where
+        T: Into<SqlValue>,
+    {
+        self.filters.push((column.to_string(), op, value.into()));
+        self
+    }
+    /// Order by `column`, ascending, after any previous orderings.
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.order_by.push((column.to_string(), false));
+        self
+    }
+    /// Order by `column`, descending, after any previous orderings.
+    pub fn order_by_desc(mut self, column: &str) -> Self {
+        self.order_by.push((column.to_string(), true));
+        self
+    }
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// The statement and its parameters.
+    pub fn build(&self) -> (String, Vec<SqlValue>) {
+        let columns = if self.columns.is_empty() {
+            "*".to_string()
+        } else {
+            self.columns
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut sql = format!("SELECT {} FROM {}", columns, quote_identifier(&self.table));
+        let mut params = vec![];
+        for (i, (column, op, value)) in self.filters.iter().enumerate() {
+            sql.push_str(if i == 0 { " WHERE " } else { " AND " });
+            sql.push_str(&format!("{} {} ?", quote_identifier(column), op.as_sql()));
+            params.push(value.clone());
+        }
+        for (i, (column, descending)) in self.order_by.iter().enumerate() {
+            sql.push_str(if i == 0 { " ORDER BY " } else { ", " });
+            sql.push_str(&quote_identifier(column));
+            sql.push_str(if *descending { " DESC" } else { " ASC" });
+        }
+        // SQLite only takes an OFFSET after a LIMIT: -1 is no limit
+        match (self.limit, self.offset) {
+            (Some(limit), Some(offset)) => {
+                sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset))
+            }
+            (Some(limit), None) => sql.push_str(&format!(" LIMIT {}", limit)),
+            (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {}", offset)),
+            (None, None) => {}
+        }
+        (sql, params)
+    }
+
+    /// Run the query on `sqlite`.
+    pub fn read(&self, sqlite: &Sqlite) -> anyhow::Result<Vec<Row>> {
+        let (sql, params) = self.build();
+        sqlite.read(sql, params.into_iter().map(SqlValue::into_json).collect())
+    }
+
+    /// Run the query on `sqlite`, deserializing each row as [`Sqlite::query_as()`].
+    pub fn read_as<T>(&self, sqlite: &Sqlite) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.read(sqlite)?
+            .into_iter()
+            .map(deserialize_row)
+            .collect()
+    }
+}
+
+impl SqlValue {
+    /// The value as a statement parameter of [`Sqlite::read()`] or [`Sqlite::write()`].
+    /// A blob becomes an array of its bytes.
+    pub fn into_json(self) -> serde_json::Value {
+        match self {
+            SqlValue::Integer(i) => i.into(),
+            SqlValue::Real(f) => f.into(),
+            SqlValue::Text(s) => s.into(),
+            SqlValue::Blob(b) => b.into(),
+            SqlValue::Boolean(b) => b.into(),
+            SqlValue::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(i: i64) -> Self {
+        SqlValue::Integer(i)
+    }
+}
+
+impl From<i32> for SqlValue {
+    fn from(i: i32) -> Self {
+        SqlValue::Integer(i.into())
+    }
+}
+
+impl From<u32> for SqlValue {
+    fn from(i: u32) -> Self {
+        SqlValue::Integer(i.into())
+    }
+}
+
+impl From<f64> for SqlValue {
+    fn from(f: f64) -> Self {
+        SqlValue::Real(f)
+    }
+}
+
+impl From<bool> for SqlValue {
+    fn from(b: bool) -> Self {
+        SqlValue::Boolean(b)
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(s: String) -> Self {
+        SqlValue::Text(s)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(s: &str) -> Self {
+        SqlValue::Text(s.to_string())
+    }
+}
+
+impl From<Vec<u8>> for SqlValue {
+    fn from(b: Vec<u8>) -> Self {
+        SqlValue::Blob(b)
+    }
+}
+
+impl<T> From<Option<T>> for SqlValue
+where
+    T: Into<SqlValue>,
+{
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(SqlValue::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let (sql, params) = Query::select("events")
+            .columns(&["block", "data"])
+            .filter("data", Op::Like, "%x%")
+            .order_by_desc("block")
+            .order_by("id")
+            .offset(10)
+            .build();
+        assert_eq!(
+            sql,
+            r#"SELECT "block", "data" FROM "events" WHERE "data" LIKE ? ORDER BY "block" DESC, "id" ASC LIMIT -1 OFFSET 10"#
+        );
+        assert_eq!(params, vec![SqlValue::Text("%x%".to_string())]);
+    }
+}
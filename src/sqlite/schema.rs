@@ -0,0 +1,116 @@
+use super::Sqlite;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// A column of a table, as returned by [`Sqlite::columns()`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// The declared type, e.g. `INTEGER`, or empty if none was declared.
+    pub type_name: String,
+    pub not_null: bool,
+    /// The default value, as SQL, e.g. `0` or `'none'`.
+    pub default: Option<String>,
+    /// The 1-based position of the column in the primary key, or 0 if it isn't part of it.
+    pub primary_key: u32,
+}
+
+/// An index of a table, as returned by [`Sqlite::indexes()`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub name: String,
+    pub unique: bool,
+    /// How the index was made: `c` by `CREATE INDEX`, `u` by a `UNIQUE`
+    /// constraint, or `pk` by a `PRIMARY KEY` constraint.
+    pub origin: String,
+    /// Whether the index has a `WHERE` clause.
+    pub partial: bool,
+    /// The indexed columns, in order. Expressions are omitted.
+    pub columns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PragmaColumn {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    notnull: i64,
+    dflt_value: Option<String>,
+    pk: i64,
+}
+
+#[derive(Deserialize)]
+struct PragmaIndexColumn {
+    name: String,
+    unique: i64,
+    origin: String,
+    partial: i64,
+    column: Option<String>,
+}
+
+impl Sqlite {
+    /// The names of the tables of the database, in order, without SQLite's
+    /// internal tables.
+    pub fn tables(&self) -> anyhow::Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Table {
+            name: String,
+        }
+        let tables: Vec<Table> = self.query_as(
+            "SELECT name FROM sqlite_master \
+             WHERE type = 'table' AND substr(name, 1, 7) != 'sqlite_' ORDER BY name"
+                .to_string(),
+            vec![],
+        )?;
+        Ok(tables.into_iter().map(|table| table.name).collect())
+    }
+
+    /// The columns of `table`, in order. Empty if there is no such table.
+    pub fn columns(&self, table: &str) -> anyhow::Result<Vec<ColumnInfo>> {
+        let columns: Vec<PragmaColumn> = self.query_as(
+            "SELECT name, type, \"notnull\", dflt_value, pk \
+             FROM pragma_table_info(?) ORDER BY cid"
+                .to_string(),
+            vec![json!(table)],
+        )?;
+        Ok(columns
+            .into_iter()
+            .map(|column| ColumnInfo {
+                name: column.name,
+                type_name: column.type_name,
+                not_null: column.notnull != 0,
+                default: column.dflt_value,
+                primary_key: column.pk as u32,
+            })
+            .collect())
+    }
+
+    /// The indexes of `table`, by name, including those SQLite made for
+    /// `UNIQUE` and `PRIMARY KEY` constraints. Empty if there is no such table.
+    pub fn indexes(&self, table: &str) -> anyhow::Result<Vec<IndexInfo>> {
+        let rows: Vec<PragmaIndexColumn> = self.query_as(
+            "SELECT il.name AS name, il.\"unique\" AS \"unique\", il.origin AS origin, \
+             il.partial AS partial, ii.name AS column \
+             FROM pragma_index_list(?) AS il, pragma_index_info(il.name) AS ii \
+             ORDER BY il.name, ii.seqno"
+                .to_string(),
+            vec![json!(table)],
+        )?;
+        let mut indexes: Vec<IndexInfo> = vec![];
+        for row in rows {
+            if indexes.last().map(|index| &index.name) != Some(&row.name) {
+                indexes.push(IndexInfo {
+                    name: row.name,
+                    unique: row.unique != 0,
+                    origin: row.origin,
+                    partial: row.partial != 0,
+                    columns: vec![],
+                });
+            }
+            if let (Some(index), Some(column)) = (indexes.last_mut(), row.column) {
+                index.columns.push(column);
+            }
+        }
+        Ok(indexes)
+    }
+}
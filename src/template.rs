@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use thiserror::Error;
+
+//
+// Minimal HTML templates with named placeholders, escaped by default
+//
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("unclosed placeholder at byte {0}")]
+    Unclosed(usize),
+    #[error("empty placeholder name at byte {0}")]
+    EmptyName(usize),
+    #[error("no value for placeholder {0}")]
+    MissingValue(String),
+}
+
+/// Escape text for use in HTML, both in element content and in quoted attribute values.
+///
+/// ```
+/// use kinode_process_lib::template::escape_html;
+///
+/// assert_eq!(
+///     escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+///     "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+/// );
+/// ```
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    /// A placeholder, and whether its value is inserted without escaping.
+    Value {
+        name: String,
+        raw: bool,
+    },
+}
+
+/// An HTML template with named placeholders, e.g. for the HTML of a widget
+/// or of a page served over HTTP.
+///
+/// `{{name}}` is replaced by the value of `name`, HTML-escaped, so that user
+/// data cannot inject markup. `{{{name}}}` is replaced by the value as is, for
+/// trusted HTML such as another rendered template. Whitespace around names is
+/// ignored. A template is parsed once and can be rendered many times.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::template::Template;
+///
+/// let template = Template::new("<h1>{{ title }}</h1><ul>{{{ items }}}</ul>").unwrap();
+/// let item = Template::new("<li>{{item}}</li>").unwrap();
+/// let items: String = ["milk", "<script>"]
+///     .iter()
+///     .map(|i| item.render([("item", i)]).unwrap())
+///     .collect();
+/// assert_eq!(
+///     template.render([("title", "Groceries"), ("items", &items)]).unwrap(),
+///     "<h1>Groceries</h1><ul><li>milk</li><li>&lt;script&gt;</li></ul>"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    /// Parse a template.
+    pub fn new(source: &str) -> Result<Self, TemplateError> {
+        let mut pieces = vec![];
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                pieces.push(Piece::Text(rest[..start].to_string()));
+            }
+            let offset = source.len() - rest.len() + start;
+            let raw = rest[start..].starts_with("{{{");
+            let (open, close) = if raw { ("{{{", "}}}") } else { ("{{", "}}") };
+            let after = &rest[start + open.len()..];
+            let Some(end) = after.find(close) else {
+                return Err(TemplateError::Unclosed(offset));
+            };
+            let name = after[..end].trim();
+            if name.is_empty() {
+                return Err(TemplateError::EmptyName(offset));
+            }
+            pieces.push(Piece::Value {
+                name: name.to_string(),
+                raw,
+            });
+            rest = &after[end + close.len()..];
+        }
+        if !rest.is_empty() {
+            pieces.push(Piece::Text(rest.to_string()));
+        }
+        Ok(Template { pieces })
+    }
+
+    /// The names of the placeholders in the template, in order of appearance.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for piece in &self.pieces {
+            if let Piece::Value { name, .. } = piece {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Render the template with the given values, by placeholder name.
+    /// Every placeholder must have a value; extra values are ignored.
+    pub fn render<I, K, V>(&self, values: I) -> Result<String, TemplateError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Display,
+    {
+        let values: HashMap<String, String> = values
+            .into_iter()
+            .map(|(name, value)| (name.as_ref().to_string(), value.to_string()))
+            .collect();
+        let mut output = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => output.push_str(text),
+                Piece::Value { name, raw } => {
+                    let Some(value) = values.get(name) else {
+                        return Err(TemplateError::MissingValue(name.clone()));
+                    };
+                    if *raw {
+                        output.push_str(value);
+                    } else {
+                        output.push_str(&escape_html(value));
+                    }
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Template::new(source)
+    }
+}
+
+/// Parse and render a template in one go: see [`Template`].
+pub fn render<I, K, V>(source: &str, values: I) -> Result<String, TemplateError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Display,
+{
+    Template::new(source)?.render(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = Template::new("a {{x}} b {{{ y }}} {{x}}").unwrap();
+        assert_eq!(template.names(), vec!["x", "y"]);
+        assert_eq!(
+            template.render([("x", "<"), ("y", "<b>")]).unwrap(),
+            "a &lt; b <b> &lt;"
+        );
+        assert_eq!(
+            template.render([("x", "1")]),
+            Err(TemplateError::MissingValue("y".to_string()))
+        );
+        assert_eq!(render("{{n}}", [("n", 3)]).unwrap(), "3");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Template::new("ok {{x"), Err(TemplateError::Unclosed(3)));
+        assert_eq!(Template::new("{{ }}"), Err(TemplateError::EmptyName(0)));
+        assert_eq!(Template::new("no placeholders").unwrap().names().len(), 0);
+    }
+}
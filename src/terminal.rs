@@ -0,0 +1,400 @@
+use crate::print_to_terminal;
+use std::fmt;
+
+//
+// Helpers for producing rich output in the Kinode terminal
+//
+
+/// ANSI escape sequence that resets all colors and styles.
+pub const RESET: &str = "\x1b[0m";
+
+/// A foreground color understood by the Kinode terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    fn code(&self) -> u8 {
+        match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+        }
+    }
+}
+
+/// A text style understood by the Kinode terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Dim,
+    Italic,
+    Underline,
+}
+
+impl Style {
+    fn code(&self) -> u8 {
+        match self {
+            Style::Bold => 1,
+            Style::Dim => 2,
+            Style::Italic => 3,
+            Style::Underline => 4,
+        }
+    }
+}
+
+/// Text with a color and/or set of styles applied. Renders to a string
+/// containing ANSI escape sequences via [`fmt::Display`].
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{println, terminal::{Color, Styled}};
+///
+/// println!("{}", Styled::new("error").color(Color::Red).bold());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Styled {
+    text: String,
+    color: Option<Color>,
+    styles: Vec<Style>,
+}
+
+impl Styled {
+    pub fn new<T>(text: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Styled {
+            text: text.into(),
+            color: None,
+            styles: vec![],
+        }
+    }
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+    pub fn style(mut self, style: Style) -> Self {
+        if !self.styles.contains(&style) {
+            self.styles.push(style);
+        }
+        self
+    }
+    pub fn bold(self) -> Self {
+        self.style(Style::Bold)
+    }
+    pub fn dim(self) -> Self {
+        self.style(Style::Dim)
+    }
+    pub fn italic(self) -> Self {
+        self.style(Style::Italic)
+    }
+    pub fn underline(self) -> Self {
+        self.style(Style::Underline)
+    }
+}
+
+impl fmt::Display for Styled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let codes: Vec<String> = self
+            .styles
+            .iter()
+            .map(|style| style.code())
+            .chain(self.color.map(|color| color.code()))
+            .map(|code| code.to_string())
+            .collect();
+        if codes.is_empty() {
+            return write!(f, "{}", self.text);
+        }
+        write!(f, "\x1b[{}m{}{}", codes.join(";"), self.text, RESET)
+    }
+}
+
+/// Color a string for display in the terminal.
+pub fn colored(text: &str, color: Color) -> String {
+    Styled::new(text).color(color).to_string()
+}
+
+/// Make a string bold for display in the terminal.
+pub fn bold(text: &str) -> String {
+    Styled::new(text).bold().to_string()
+}
+
+/// Make a string dim for display in the terminal.
+pub fn dim(text: &str) -> String {
+    Styled::new(text).dim().to_string()
+}
+
+/// Underline a string for display in the terminal.
+pub fn underline(text: &str) -> String {
+    Styled::new(text).underline().to_string()
+}
+
+/// Remove all ANSI escape sequences from a string, leaving only the
+/// characters that will actually be displayed.
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            chars.next();
+            // skip parameters until the final byte of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// The number of characters a string will occupy when displayed,
+/// ignoring ANSI escape sequences.
+pub fn display_width(text: &str) -> usize {
+    strip_ansi(text).chars().count()
+}
+
+/// A simple text table with a header row, rendered with columns padded to
+/// the widest cell. Cells may contain [`Styled`] text: escape sequences
+/// are not counted towards column widths.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{println, terminal::Table};
+///
+/// let table = Table::new(vec!["name", "status"])
+///     .row(vec!["foo.os", "online"])
+///     .row(vec!["bar.os", "offline"]);
+/// println!("{table}");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<T>(headers: Vec<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        Table {
+            headers: headers.into_iter().map(|h| h.into()).collect(),
+            rows: vec![],
+        }
+    }
+    /// Add a row to the table. Rows shorter than the header row are padded
+    /// with empty cells.
+    pub fn row<T>(mut self, cells: Vec<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        self.push_row(cells);
+        self
+    }
+    /// Add a row to the table in-place.
+    pub fn push_row<T>(&mut self, cells: Vec<T>)
+    where
+        T: Into<String>,
+    {
+        self.rows
+            .push(cells.into_iter().map(|c| c.into()).collect());
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// Render the table to a string, one line per row, with a separator
+    /// line beneath the headers.
+    pub fn render(&self) -> String {
+        let columns = self
+            .rows
+            .iter()
+            .map(|row| row.len())
+            .chain(std::iter::once(self.headers.len()))
+            .max()
+            .unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in std::iter::once(&self.headers).chain(self.rows.iter()) {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(display_width(cell));
+            }
+        }
+        let render_row = |row: &Vec<String>| -> String {
+            (0..columns)
+                .map(|i| {
+                    let cell = row.get(i).map(|c| c.as_str()).unwrap_or("");
+                    let padding = widths[i] - display_width(cell);
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+        let mut lines = vec![];
+        if !self.headers.is_empty() {
+            lines.push(render_row(&self.headers.iter().map(|h| bold(h)).collect()));
+            lines.push(
+                widths
+                    .iter()
+                    .map(|w| "-".repeat(*w))
+                    .collect::<Vec<_>>()
+                    .join("  "),
+            );
+        }
+        lines.extend(self.rows.iter().map(render_row));
+        lines.join("\n")
+    }
+    /// Print the table to the terminal at the given verbosity level.
+    pub fn print(&self, verbosity: u8) {
+        print_to_terminal(verbosity, &self.render());
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+/// ANSI escape sequence that moves the cursor up a line and clears it,
+/// so that the next print replaces the previous one.
+const REWRITE_LINE: &str = "\x1b[1A\x1b[2K";
+
+/// A progress indicator that rewrites its own line in the terminal as it
+/// advances, printing via [`print_to_terminal()`] at a given verbosity level.
+///
+/// Note that if any other prints occur between updates, the progress
+/// indicator will overwrite the most recent of them.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::terminal::Progress;
+///
+/// let mut progress = Progress::new("downloading", 100);
+/// for _ in 0..100 {
+///     progress.inc(1);
+/// }
+/// progress.finish();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Progress {
+    label: String,
+    current: u64,
+    total: u64,
+    width: usize,
+    verbosity: u8,
+    printed: bool,
+}
+
+impl Progress {
+    /// Create a new progress indicator. It will not print until it
+    /// is first updated.
+    pub fn new<T>(label: T, total: u64) -> Self
+    where
+        T: Into<String>,
+    {
+        Progress {
+            label: label.into(),
+            current: 0,
+            total,
+            width: 30,
+            verbosity: 0,
+            printed: false,
+        }
+    }
+    /// Set the verbosity level the progress indicator prints at. Defaults to 0,
+    /// i.e., always shown.
+    pub fn verbosity(mut self, verbosity: u8) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+    /// Set the width of the bar, in characters. Defaults to 30.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+    /// Set the current position and reprint.
+    pub fn set(&mut self, current: u64) {
+        self.current = current.min(self.total);
+        self.print();
+    }
+    /// Advance the current position by `n` and reprint.
+    pub fn inc(&mut self, n: u64) {
+        self.set(self.current.saturating_add(n));
+    }
+    /// Set the position to the total and reprint.
+    pub fn finish(&mut self) {
+        self.set(self.total);
+    }
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.total
+    }
+    /// Render the current state of the progress indicator, without
+    /// any cursor movement.
+    pub fn render(&self) -> String {
+        let filled = (self.width as u64 * self.current)
+            .checked_div(self.total)
+            .unwrap_or(self.width as u64) as usize;
+        let percent = (100 * self.current).checked_div(self.total).unwrap_or(100);
+        format!(
+            "{} [{}{}] {}/{} ({}%)",
+            self.label,
+            "#".repeat(filled),
+            " ".repeat(self.width - filled),
+            self.current,
+            self.total,
+            percent,
+        )
+    }
+    fn print(&mut self) {
+        let prefix = if self.printed { REWRITE_LINE } else { "" };
+        print_to_terminal(self.verbosity, &format!("{}{}", prefix, self.render()));
+        self.printed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_ansi_removes_styles() {
+        let styled = Styled::new("hi").color(Color::Green).bold().to_string();
+        assert_eq!(styled, "\x1b[1;32mhi\x1b[0m");
+        assert_eq!(strip_ansi(&styled), "hi");
+        assert_eq!(display_width(&styled), 2);
+    }
+
+    #[test]
+    fn table_pads_columns() {
+        let table = Table::new(Vec::<String>::new())
+            .row(vec!["a", "bb"])
+            .row(vec![colored("ccc", Color::Red).as_str(), "d"]);
+        let rendered = strip_ansi(&table.render());
+        assert_eq!(rendered, "a    bb\nccc  d");
+    }
+
+    #[test]
+    fn progress_renders_bar() {
+        let mut progress = Progress::new("x", 4).width(4);
+        progress.current = 1;
+        assert_eq!(progress.render(), "x [#   ] 1/4 (25%)");
+    }
+}
@@ -0,0 +1,256 @@
+use super::{push_message, shims::our, MockResponse};
+use crate::http::server::{HttpServerRequest, IncomingHttpRequest, WsMessageType};
+use crate::kv::KvResponse;
+use crate::vfs::VfsResponse;
+use crate::{Address, Capability, LazyLoadBlob, Message};
+use std::collections::HashMap;
+
+//
+// Builders for realistic runtime messages, to use as test inputs
+//
+
+/// The [`Address`] of `http-server:distro:sys` on the node under test.
+fn http_server() -> Address {
+    Address::new(our().node, ("http-server", "distro", "sys"))
+}
+
+/// Builder for an [`IncomingHttpRequest`], as delivered by `http-server:distro:sys`.
+///
+/// The URL may be given in full or as a path, in which case it is placed on
+/// `http://localhost:8080`. Query parameters are parsed out of the URL, and the
+/// bound path defaults to the URL path.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::test_harness::fixtures::HttpRequestFixture;
+///
+/// HttpRequestFixture::new("POST", "/my:process:pkg/api?verbose=true")
+///     .header("Content-Type", "application/json")
+///     .body(br#"{"ping":1}"#.to_vec())
+///     .push();
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpRequestFixture {
+    method: String,
+    url: String,
+    bound_path: Option<String>,
+    source_socket_addr: Option<String>,
+    headers: HashMap<String, String>,
+    url_params: HashMap<String, String>,
+    query_params: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl HttpRequestFixture {
+    pub fn new(method: &str, url: &str) -> Self {
+        let url = if url.starts_with('/') {
+            format!("http://localhost:8080{}", url)
+        } else {
+            url.to_string()
+        };
+        let query_params = url::Url::parse(&url)
+            .map(|url| url.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+        HttpRequestFixture {
+            method: method.to_uppercase(),
+            url,
+            bound_path: None,
+            source_socket_addr: Some("127.0.0.1:50000".to_string()),
+            headers: HashMap::new(),
+            url_params: HashMap::new(),
+            query_params,
+            body: vec![],
+        }
+    }
+    /// Set the path the process bound that this request matched, e.g. `/api/:id`.
+    pub fn bound_path(mut self, path: &str) -> Self {
+        self.bound_path = Some(path.to_string());
+        self
+    }
+    pub fn source_socket_addr(mut self, addr: &str) -> Self {
+        self.source_socket_addr = Some(addr.to_string());
+        self
+    }
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.headers.insert(key.to_string(), value.to_string());
+        self
+    }
+    /// Set a parameter matched from the bound path, e.g. `id` for `/api/:id`.
+    pub fn url_param(mut self, key: &str, value: &str) -> Self {
+        self.url_params.insert(key.to_string(), value.to_string());
+        self
+    }
+    pub fn query_param(mut self, key: &str, value: &str) -> Self {
+        self.query_params.insert(key.to_string(), value.to_string());
+        self
+    }
+    /// Set the request body, delivered as the [`LazyLoadBlob`] of the message.
+    pub fn body<T>(mut self, body: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        self.body = body.into();
+        self
+    }
+    /// Set the request body to the given value serialized to JSON, and set the
+    /// `Content-Type` header accordingly.
+    pub fn json<T>(self, body: &T) -> Self
+    where
+        T: serde::Serialize,
+    {
+        self.header("Content-Type", "application/json")
+            .body(serde_json::to_vec(body).unwrap())
+    }
+    /// Build the [`IncomingHttpRequest`] alone, e.g. to pass to a handler function.
+    pub fn build(&self) -> IncomingHttpRequest {
+        let bound_path = self.bound_path.clone().unwrap_or_else(|| {
+            url::Url::parse(&self.url)
+                .map(|url| url.path().to_string())
+                .unwrap_or_default()
+        });
+        // fields are private to the http module, so build via the wire format
+        serde_json::from_value(serde_json::json!({
+            "source_socket_addr": self.source_socket_addr,
+            "method": self.method,
+            "url": self.url,
+            "bound_path": bound_path,
+            "headers": self.headers,
+            "url_params": self.url_params,
+            "query_params": self.query_params,
+        }))
+        .unwrap()
+    }
+    /// Build the full [`Message`] and body blob that `http-server:distro:sys` would send.
+    pub fn message(&self) -> (Message, Option<LazyLoadBlob>) {
+        let (message, _) = server_request(HttpServerRequest::Http(self.build()));
+        let blob = LazyLoadBlob::new(None::<String>, self.body.clone());
+        (message, Some(blob))
+    }
+    /// Queue this request in the inbox of the process under test.
+    pub fn push(&self) {
+        let (message, blob) = self.message();
+        push_message(message, blob);
+    }
+}
+
+fn server_request(request: HttpServerRequest) -> (Message, Option<LazyLoadBlob>) {
+    let message = Message::Request {
+        source: http_server(),
+        expects_response: Some(15),
+        body: serde_json::to_vec(&request).unwrap(),
+        metadata: None,
+        capabilities: vec![],
+    };
+    (message, None)
+}
+
+/// An [`HttpServerRequest::WebSocketOpen`] message, as delivered by `http-server:distro:sys`.
+pub fn ws_open(path: &str, channel_id: u32) -> (Message, Option<LazyLoadBlob>) {
+    server_request(HttpServerRequest::WebSocketOpen {
+        path: path.to_string(),
+        channel_id,
+    })
+}
+
+/// An [`HttpServerRequest::WebSocketPush`] message, as delivered by `http-server:distro:sys`,
+/// with the pushed bytes as its blob.
+pub fn ws_push<T>(
+    channel_id: u32,
+    message_type: WsMessageType,
+    bytes: T,
+) -> (Message, Option<LazyLoadBlob>)
+where
+    T: Into<Vec<u8>>,
+{
+    let (message, _) = server_request(HttpServerRequest::WebSocketPush {
+        channel_id,
+        message_type,
+    });
+    (message, Some(LazyLoadBlob::new(None::<String>, bytes)))
+}
+
+/// An [`HttpServerRequest::WebSocketClose`] message, as delivered by `http-server:distro:sys`.
+pub fn ws_close(channel_id: u32) -> (Message, Option<LazyLoadBlob>) {
+    server_request(HttpServerRequest::WebSocketClose(channel_id))
+}
+
+/// An [`HttpServerRequest::SseOpen`] message, as delivered by `http-server:distro:sys`.
+pub fn sse_open(path: &str, channel_id: u32) -> (Message, Option<LazyLoadBlob>) {
+    server_request(HttpServerRequest::SseOpen {
+        path: path.to_string(),
+        channel_id,
+    })
+}
+
+/// An [`HttpServerRequest::SseClose`] message, as delivered by `http-server:distro:sys`.
+pub fn sse_close(channel_id: u32) -> (Message, Option<LazyLoadBlob>) {
+    server_request(HttpServerRequest::SseClose(channel_id))
+}
+
+/// A [`Message::Request`] from `source` that expects a response.
+pub fn request<T, U>(source: T, body: U) -> Message
+where
+    T: Into<Address>,
+    U: Into<Vec<u8>>,
+{
+    Message::Request {
+        source: source.into(),
+        expects_response: Some(5),
+        body: body.into(),
+        metadata: None,
+        capabilities: vec![],
+    }
+}
+
+/// A [`Message::Request`] from `source` carrying the given capabilities.
+pub fn request_with_capabilities<T, U>(source: T, body: U, capabilities: Vec<Capability>) -> Message
+where
+    T: Into<Address>,
+    U: Into<Vec<u8>>,
+{
+    Message::Request {
+        source: source.into(),
+        expects_response: Some(5),
+        body: body.into(),
+        metadata: None,
+        capabilities,
+    }
+}
+
+/// A [`Message::Response`] from `source`, carrying the context of the request it answers.
+pub fn response<T, U>(source: T, body: U, context: Option<Vec<u8>>) -> Message
+where
+    T: Into<Address>,
+    U: Into<Vec<u8>>,
+{
+    Message::Response {
+        source: source.into(),
+        body: body.into(),
+        metadata: None,
+        context,
+        capabilities: vec![],
+    }
+}
+
+/// A response from `vfs:distro:sys`. For [`VfsResponse::Read`], attach the
+/// file contents with [`MockResponse::blob_bytes()`].
+pub fn vfs_response(response: VfsResponse) -> MockResponse {
+    MockResponse::json(&response)
+}
+
+/// A response from `kv:distro:sys`. For [`KvResponse::Get`], prefer [`kv_get()`],
+/// which attaches the value as the blob.
+pub fn kv_response(response: KvResponse) -> MockResponse {
+    MockResponse::json(&response)
+}
+
+/// A [`KvResponse::Get`] from `kv:distro:sys` for the given key, with the
+/// value serialized as the [`crate::kv::Kv`] client expects.
+pub fn kv_get<K, V>(key: &K, value: &V) -> MockResponse
+where
+    K: serde::Serialize,
+    V: serde::Serialize,
+{
+    kv_response(KvResponse::Get(serde_json::to_vec(key).unwrap()))
+        .blob_bytes(serde_json::to_vec(value).unwrap())
+}
@@ -0,0 +1,564 @@
+use crate::kinode::process::standard as wit;
+use crate::{Address, Capability, LazyLoadBlob, Message, SendErrorKind};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+/// Builders for realistic runtime messages, to use as test inputs.
+pub mod fixtures;
+
+//
+// In-memory fake of the runtime, for unit testing process logic off-node
+//
+
+/// The default [`Address`] of the process under test. Change it with [`reset()`].
+pub const DEFAULT_OUR: &str = "test.os@test:test:sys";
+
+thread_local! {
+    static RUNTIME: RefCell<MockRuntime> = RefCell::new(MockRuntime::new(default_our()));
+}
+
+fn default_our() -> Address {
+    DEFAULT_OUR.parse().unwrap()
+}
+
+struct MockRuntime {
+    our: Address,
+    responses: HashMap<Address, VecDeque<Scripted>>,
+    inbox: VecDeque<Incoming>,
+    sent_requests: Vec<SentRequest>,
+    sent_responses: Vec<SentResponse>,
+    prints: Vec<(u8, String)>,
+    current_blob: Option<LazyLoadBlob>,
+    last_blob: Option<LazyLoadBlob>,
+    state: Option<Vec<u8>>,
+    capabilities: Vec<Capability>,
+}
+
+enum Scripted {
+    Response(MockResponse),
+    Error(SendErrorKind),
+}
+
+/// An entry of the inbox, returned by the next [`shims::receive()`].
+enum Incoming {
+    Message(Message, Option<LazyLoadBlob>),
+    /// A send error of a request sent with [`crate::Request::send()`], with its context.
+    SendError(wit::SendError, Option<Vec<u8>>),
+}
+
+impl MockRuntime {
+    fn new(our: Address) -> Self {
+        MockRuntime {
+            our,
+            responses: HashMap::new(),
+            inbox: VecDeque::new(),
+            sent_requests: vec![],
+            sent_responses: vec![],
+            prints: vec![],
+            current_blob: None,
+            last_blob: None,
+            state: None,
+            capabilities: vec![],
+        }
+    }
+    /// Addresses on node `our` are keyed by our actual node ID.
+    fn normalize(&self, address: &Address) -> Address {
+        if address.node == "our" {
+            Address::new(&self.our.node, address.process.clone())
+        } else {
+            address.clone()
+        }
+    }
+    fn next_scripted(&mut self, target: &Address) -> Option<Scripted> {
+        let target = self.normalize(target);
+        self.responses.get_mut(&target)?.pop_front()
+    }
+    fn set_blob(&mut self, blob: Option<LazyLoadBlob>) {
+        if blob.is_some() {
+            self.last_blob = blob.clone();
+        }
+        self.current_blob = blob;
+    }
+}
+
+/// A response scripted to be returned to the process under test when it sends a
+/// request to a given target. Enqueue with [`enqueue_response()`].
+#[derive(Clone, Debug, Default)]
+pub struct MockResponse {
+    pub body: Vec<u8>,
+    pub metadata: Option<String>,
+    pub blob: Option<LazyLoadBlob>,
+    pub capabilities: Vec<Capability>,
+}
+
+impl MockResponse {
+    pub fn new<T>(body: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        MockResponse {
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+    /// Create a response whose body is the given value serialized to JSON.
+    pub fn json<T>(body: &T) -> Self
+    where
+        T: serde::Serialize,
+    {
+        Self::new(serde_json::to_vec(body).unwrap())
+    }
+    pub fn metadata(mut self, metadata: &str) -> Self {
+        self.metadata = Some(metadata.to_string());
+        self
+    }
+    pub fn blob(mut self, blob: LazyLoadBlob) -> Self {
+        self.blob = Some(blob);
+        self
+    }
+    pub fn blob_bytes<T>(mut self, bytes: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        self.blob = Some(LazyLoadBlob::new(None::<String>, bytes));
+        self
+    }
+    pub fn capabilities(mut self, capabilities: Vec<Capability>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+}
+
+/// A request sent by the process under test, captured for assertions.
+#[derive(Clone, Debug)]
+pub struct SentRequest {
+    pub target: Address,
+    pub inherit: bool,
+    pub expects_response: Option<u64>,
+    pub body: Vec<u8>,
+    pub metadata: Option<String>,
+    pub capabilities: Vec<Capability>,
+    pub context: Option<Vec<u8>>,
+    pub blob: Option<LazyLoadBlob>,
+}
+
+impl SentRequest {
+    /// Deserialize the body of the request from JSON.
+    pub fn body_json<T>(&self) -> serde_json::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// A response sent by the process under test, captured for assertions.
+#[derive(Clone, Debug)]
+pub struct SentResponse {
+    pub inherit: bool,
+    pub body: Vec<u8>,
+    pub metadata: Option<String>,
+    pub capabilities: Vec<Capability>,
+    pub blob: Option<LazyLoadBlob>,
+}
+
+impl SentResponse {
+    /// Deserialize the body of the response from JSON.
+    pub fn body_json<T>(&self) -> serde_json::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// Clear all scripted responses, queued messages, captured output, state and
+/// capabilities, and set the [`Address`] of the process under test.
+///
+/// The fake runtime is thread-local, and each test runs on its own thread,
+/// so calling this is only necessary when reusing a runtime within one test.
+pub fn reset<T>(our: T)
+where
+    T: Into<Address>,
+{
+    RUNTIME.with(|rt| *rt.borrow_mut() = MockRuntime::new(our.into()));
+}
+
+/// Script a response to be returned to the next request the process under test sends
+/// to `target`. Responses to the same target are returned in the order they were enqueued.
+///
+/// A target on node `"our"` matches requests sent to our node, whichever way they are
+/// addressed. For requests awaited with [`crate::Request::send_and_await_response()`],
+/// the response is returned directly; for requests sent with [`crate::Request::send()`]
+/// that expect a response, it is placed in the inbox to be received. The same goes for
+/// errors scripted with [`enqueue_send_error()`].
+pub fn enqueue_response<T>(target: T, response: MockResponse)
+where
+    T: Into<Address>,
+{
+    enqueue(target.into(), Scripted::Response(response));
+}
+
+/// Script a [`crate::SendError`] to be returned to the next request the process under
+/// test sends to `target`, in place of a response.
+pub fn enqueue_send_error<T>(target: T, kind: SendErrorKind)
+where
+    T: Into<Address>,
+{
+    enqueue(target.into(), Scripted::Error(kind));
+}
+
+fn enqueue(target: Address, scripted: Scripted) {
+    RUNTIME.with(|rt| {
+        let mut rt = rt.borrow_mut();
+        let target = rt.normalize(&target);
+        rt.responses.entry(target).or_default().push_back(scripted);
+    });
+}
+
+/// Queue an incoming message, along with its blob, to be returned by
+/// [`crate::await_message()`] in the process under test.
+pub fn push_message(message: Message, blob: Option<LazyLoadBlob>) {
+    RUNTIME.with(|rt| {
+        rt.borrow_mut()
+            .inbox
+            .push_back(Incoming::Message(message, blob))
+    });
+}
+
+/// All requests sent by the process under test so far, in order.
+pub fn sent_requests() -> Vec<SentRequest> {
+    RUNTIME.with(|rt| rt.borrow().sent_requests.clone())
+}
+
+/// Remove and return all requests sent by the process under test so far, in order.
+pub fn take_sent_requests() -> Vec<SentRequest> {
+    RUNTIME.with(|rt| std::mem::take(&mut rt.borrow_mut().sent_requests))
+}
+
+/// All responses sent by the process under test so far, in order.
+pub fn sent_responses() -> Vec<SentResponse> {
+    RUNTIME.with(|rt| rt.borrow().sent_responses.clone())
+}
+
+/// Remove and return all responses sent by the process under test so far, in order.
+pub fn take_sent_responses() -> Vec<SentResponse> {
+    RUNTIME.with(|rt| std::mem::take(&mut rt.borrow_mut().sent_responses))
+}
+
+/// All prints made by the process under test so far, with their verbosity levels.
+pub fn prints() -> Vec<(u8, String)> {
+    RUNTIME.with(|rt| rt.borrow().prints.clone())
+}
+
+/// Grant a capability to the process under test.
+pub fn grant_capability(capability: Capability) {
+    RUNTIME.with(|rt| rt.borrow_mut().capabilities.push(capability));
+}
+
+/// Panic payload used to unwind out of the process under test once its inbox is empty.
+struct InboxEmpty;
+
+/// Run an `init` function against the fake runtime, as the kernel would with
+/// [`crate::call_init!`]. Returns once `init` returns, or once it awaits a message
+/// when the inbox is empty, so a typical `loop { await_message() ... }` process
+/// handles every queued message and then stops.
+///
+/// Stopping on an empty inbox is done by unwinding out of `init`, so the default
+/// panic hook will print a message for it, which can be ignored. Any other panic
+/// in `init` propagates to the caller, failing the test.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{test_harness, Address, Message};
+///
+/// fn init(our: Address) { /* ... */ }
+///
+/// test_harness::enqueue_response(
+///     ("our", "kv", "distro", "sys"),
+///     test_harness::MockResponse::new(b"\"Ok\"".to_vec()),
+/// );
+/// test_harness::run(init);
+/// assert_eq!(test_harness::sent_requests().len(), 1);
+/// ```
+pub fn run<F>(init: F)
+where
+    F: FnOnce(Address),
+{
+    let our = RUNTIME.with(|rt| rt.borrow().our.clone());
+    if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| init(our))) {
+        if !payload.is::<InboxEmpty>() {
+            std::panic::resume_unwind(payload);
+        }
+    }
+}
+
+fn to_wit_send_error(
+    kind: SendErrorKind,
+    target: &Address,
+    request: &wit::Request,
+    lazy_load_blob: Option<&LazyLoadBlob>,
+) -> wit::SendError {
+    wit::SendError {
+        kind: match kind {
+            SendErrorKind::Offline => wit::SendErrorKind::Offline,
+            SendErrorKind::Timeout => wit::SendErrorKind::Timeout,
+        },
+        target: target.clone(),
+        message: wit::Message::Request(request.clone()),
+        lazy_load_blob: lazy_load_blob.cloned(),
+    }
+}
+
+fn to_wit_message(message: Message) -> (Address, wit::Message) {
+    match message {
+        Message::Request {
+            source,
+            expects_response,
+            body,
+            metadata,
+            capabilities,
+        } => (
+            source,
+            wit::Message::Request(wit::Request {
+                inherit: false,
+                expects_response,
+                body,
+                metadata,
+                capabilities,
+            }),
+        ),
+        Message::Response {
+            source,
+            body,
+            metadata,
+            context,
+            capabilities,
+        } => (
+            source,
+            wit::Message::Response((
+                wit::Response {
+                    inherit: false,
+                    body,
+                    metadata,
+                    capabilities,
+                },
+                context,
+            )),
+        ),
+    }
+}
+
+/// Replacements for the runtime functions generated from `kinode.wit`, re-exported at
+/// the crate root in place of the originals when the `test_harness` feature is enabled.
+/// Signatures must match the originals exactly, so large error variants are kept.
+#[doc(hidden)]
+#[allow(clippy::result_large_err)]
+pub mod shims {
+    use super::*;
+    use crate::kinode::process::standard::Context;
+
+    pub fn our() -> Address {
+        RUNTIME.with(|rt| rt.borrow().our.clone())
+    }
+
+    pub fn print_to_terminal(verbosity: u8, message: &str) {
+        RUNTIME.with(|rt| {
+            rt.borrow_mut()
+                .prints
+                .push((verbosity, message.to_string()))
+        });
+    }
+
+    pub fn get_state() -> Option<Vec<u8>> {
+        RUNTIME.with(|rt| rt.borrow().state.clone())
+    }
+
+    pub fn set_state(bytes: &[u8]) {
+        RUNTIME.with(|rt| rt.borrow_mut().state = Some(bytes.to_vec()));
+    }
+
+    pub fn clear_state() {
+        RUNTIME.with(|rt| rt.borrow_mut().state = None);
+    }
+
+    pub fn save_capabilities(caps: &[Capability]) {
+        RUNTIME.with(|rt| {
+            let mut rt = rt.borrow_mut();
+            for cap in caps {
+                if !rt.capabilities.contains(cap) {
+                    rt.capabilities.push(cap.clone());
+                }
+            }
+        });
+    }
+
+    pub fn drop_capabilities(caps: &[Capability]) {
+        RUNTIME.with(|rt| {
+            rt.borrow_mut()
+                .capabilities
+                .retain(|cap| !caps.contains(cap))
+        });
+    }
+
+    pub fn our_capabilities() -> Vec<Capability> {
+        RUNTIME.with(|rt| rt.borrow().capabilities.clone())
+    }
+
+    pub fn receive() -> Result<(Address, wit::Message), (wit::SendError, Option<Context>)> {
+        let next = RUNTIME.with(|rt| {
+            let mut rt = rt.borrow_mut();
+            let incoming = rt.inbox.pop_front()?;
+            if let Incoming::Message(_, blob) = &incoming {
+                rt.set_blob(blob.clone());
+            }
+            Some(incoming)
+        });
+        match next {
+            Some(Incoming::Message(message, _)) => Ok(to_wit_message(message)),
+            Some(Incoming::SendError(error, context)) => Err((error, context)),
+            None => std::panic::panic_any(InboxEmpty),
+        }
+    }
+
+    pub fn has_blob() -> bool {
+        RUNTIME.with(|rt| rt.borrow().current_blob.is_some())
+    }
+
+    pub fn get_blob() -> Option<LazyLoadBlob> {
+        RUNTIME.with(|rt| rt.borrow().current_blob.clone())
+    }
+
+    pub fn last_blob() -> Option<LazyLoadBlob> {
+        RUNTIME.with(|rt| rt.borrow().last_blob.clone())
+    }
+
+    pub fn send_request(
+        target: &Address,
+        request: &wit::Request,
+        context: Option<&Context>,
+        lazy_load_blob: Option<&LazyLoadBlob>,
+    ) {
+        RUNTIME.with(|rt| {
+            let mut rt = rt.borrow_mut();
+            rt.sent_requests.push(SentRequest {
+                target: target.clone(),
+                inherit: request.inherit,
+                expects_response: request.expects_response,
+                body: request.body.clone(),
+                metadata: request.metadata.clone(),
+                capabilities: request.capabilities.clone(),
+                context: context.map(|c| c.to_vec()),
+                blob: lazy_load_blob.cloned(),
+            });
+            if request.expects_response.is_none() {
+                return;
+            }
+            match rt.next_scripted(target) {
+                Some(Scripted::Response(response)) => {
+                    let message = Message::Response {
+                        source: target.clone(),
+                        body: response.body,
+                        metadata: response.metadata,
+                        context: context.map(|c| c.to_vec()),
+                        capabilities: response.capabilities,
+                    };
+                    rt.inbox
+                        .push_back(Incoming::Message(message, response.blob));
+                }
+                Some(Scripted::Error(kind)) => {
+                    let error = to_wit_send_error(kind, target, request, lazy_load_blob);
+                    rt.inbox
+                        .push_back(Incoming::SendError(error, context.map(|c| c.to_vec())));
+                }
+                None => {}
+            }
+        });
+    }
+
+    pub fn send_requests(
+        requests: &[(Address, wit::Request, Option<Context>, Option<LazyLoadBlob>)],
+    ) {
+        for (target, request, context, blob) in requests {
+            send_request(target, request, context.as_ref(), blob.as_ref());
+        }
+    }
+
+    pub fn send_response(response: &wit::Response, lazy_load_blob: Option<&LazyLoadBlob>) {
+        RUNTIME.with(|rt| {
+            rt.borrow_mut().sent_responses.push(SentResponse {
+                inherit: response.inherit,
+                body: response.body.clone(),
+                metadata: response.metadata.clone(),
+                capabilities: response.capabilities.clone(),
+                blob: lazy_load_blob.cloned(),
+            })
+        });
+    }
+
+    pub fn send_and_await_response(
+        target: &Address,
+        request: &wit::Request,
+        lazy_load_blob: Option<&LazyLoadBlob>,
+    ) -> Result<(Address, wit::Message), wit::SendError> {
+        RUNTIME.with(|rt| {
+            let mut rt = rt.borrow_mut();
+            rt.sent_requests.push(SentRequest {
+                target: target.clone(),
+                inherit: request.inherit,
+                expects_response: request.expects_response,
+                body: request.body.clone(),
+                metadata: request.metadata.clone(),
+                capabilities: request.capabilities.clone(),
+                context: None,
+                blob: lazy_load_blob.cloned(),
+            });
+            match rt.next_scripted(target) {
+                Some(Scripted::Response(response)) => {
+                    rt.set_blob(response.blob);
+                    Ok((
+                        target.clone(),
+                        wit::Message::Response((
+                            wit::Response {
+                                inherit: false,
+                                body: response.body,
+                                metadata: response.metadata,
+                                capabilities: response.capabilities,
+                            },
+                            None,
+                        )),
+                    ))
+                }
+                Some(Scripted::Error(kind)) => {
+                    Err(to_wit_send_error(kind, target, request, lazy_load_blob))
+                }
+                None => panic!(
+                    "test_harness: no response scripted for request to {}",
+                    target
+                ),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{await_message, Request};
+
+    #[test]
+    fn test_send_error_reaches_inbox() {
+        let target: Address = ("our", "kv", "distro", "sys").into();
+        enqueue_send_error(target.clone(), SendErrorKind::Offline);
+        Request::to(target.clone())
+            .body(b"ping".to_vec())
+            .context(b"ctx".to_vec())
+            .expects_response(5)
+            .send()
+            .unwrap();
+        let error = await_message().unwrap_err();
+        assert!(matches!(error.kind(), SendErrorKind::Offline));
+        assert_eq!(error.target(), &target);
+        assert_eq!(error.context(), Some(b"ctx".as_slice()));
+    }
+}
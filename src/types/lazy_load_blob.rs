@@ -26,6 +26,17 @@ impl LazyLoadBlob {
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
     }
+    /// Read the bytes from a `LazyLoadBlob` as a UTF-8 string.
+    pub fn as_str(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.bytes)
+    }
+    /// Deserialize the bytes from a `LazyLoadBlob` as JSON.
+    pub fn as_json<T>(&self) -> serde_json::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        serde_json::from_slice(&self.bytes)
+    }
 }
 
 impl std::default::Default for LazyLoadBlob {
@@ -8,3 +8,5 @@ pub mod process_id;
 pub mod request;
 pub mod response;
 pub mod send_error;
+#[cfg(feature = "trace_sends")]
+pub(crate) mod trace;
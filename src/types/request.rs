@@ -271,6 +271,8 @@ impl Request {
         let Some(body) = self.body else {
             return Err(BuildError::NoBody);
         };
+        #[cfg(feature = "trace_sends")]
+        crate::types::trace::request_sent(&target, &body, self.timeout);
         crate::send_request(
             &target,
             &crate::kinode::process::standard::Request {
@@ -297,7 +299,9 @@ impl Request {
         let Some(body) = self.body else {
             return Err(BuildError::NoBody);
         };
-        match crate::send_and_await_response(
+        #[cfg(feature = "trace_sends")]
+        let sent = crate::types::trace::request_sent(&target, &body, Some(timeout));
+        let result = match crate::send_and_await_response(
             &target,
             &crate::kinode::process::standard::Request {
                 inherit: self.inherit,
@@ -308,9 +312,16 @@ impl Request {
             },
             self.blob.as_ref(),
         ) {
-            Ok((source, message)) => Ok(Ok(_wit_message_to_message(source, message))),
-            Err(send_err) => Ok(Err(_wit_send_error_to_send_error(send_err, self.context))),
-        }
+            Ok((source, message)) => Ok(_wit_message_to_message(source, message)),
+            Err(send_err) => Err(_wit_send_error_to_send_error(send_err, self.context)),
+        };
+        #[cfg(feature = "trace_sends")]
+        crate::types::trace::response_received(
+            &target,
+            sent,
+            result.as_ref().err().map(|e| &e.kind),
+        );
+        Ok(result)
     }
 }
 
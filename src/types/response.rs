@@ -165,6 +165,8 @@ impl Response {
     /// the `Response` has not yet been set using `body()` or `try_body()`.
     pub fn send(self) -> Result<(), BuildError> {
         if let Some(body) = self.body {
+            #[cfg(feature = "trace_sends")]
+            crate::types::trace::response_sent(&body);
             crate::send_response(
                 &crate::kinode::process::standard::Response {
                     inherit: self.inherit,
@@ -0,0 +1,132 @@
+use crate::Address;
+use std::cell::Cell;
+
+//
+// Tracing of outgoing messages, enabled with the `trace_sends` feature
+//
+
+thread_local! {
+    /// Set while an event is being emitted, so that messages sent by the
+    /// subscriber itself (e.g. the [`crate::logging`] file writer) are not traced,
+    /// which would recurse forever.
+    static EMITTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Best-effort name of the action a message body carries, for JSON bodies
+/// of the shape produced by serde for enums: a unit variant is a string,
+/// other variants are an object with a single key. Bodies wrapping their
+/// action in an `action` field, like [`crate::vfs::VfsRequest`], are unwrapped.
+pub(crate) fn action_discriminant(body: &[u8]) -> Option<String> {
+    fn discriminant(value: &serde_json::Value) -> Option<String> {
+        match value {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Object(map) => match map.get("action") {
+                Some(action) => discriminant(action),
+                None if map.len() == 1 => map.keys().next().cloned(),
+                None => None,
+            },
+            _ => None,
+        }
+    }
+    discriminant(&serde_json::from_slice(body).ok()?)
+}
+
+fn emit<F>(event: F)
+where
+    F: FnOnce(),
+{
+    if EMITTING.with(|emitting| emitting.replace(true)) {
+        return;
+    }
+    event();
+    EMITTING.with(|emitting| emitting.set(false));
+}
+
+/// What [`response_received()`] logs of the request it answers, taken by
+/// [`request_sent()`] so the request body need not be kept.
+pub(crate) struct SentRequest {
+    action: Option<String>,
+    body_size: usize,
+    start: std::time::Instant,
+}
+
+pub(crate) fn request_sent(
+    target: &Address,
+    body: &[u8],
+    expects_response: Option<u64>,
+) -> SentRequest {
+    let sent = SentRequest {
+        action: action_discriminant(body),
+        body_size: body.len(),
+        start: std::time::Instant::now(),
+    };
+    emit(|| {
+        tracing::debug!(
+            target: "kinode_process_lib::send",
+            %target,
+            action = sent.action,
+            body_size = sent.body_size,
+            expects_response,
+            "request sent",
+        )
+    });
+    sent
+}
+
+pub(crate) fn response_received(
+    target: &Address,
+    sent: SentRequest,
+    error: Option<&crate::SendErrorKind>,
+) {
+    let latency_ms = sent.start.elapsed().as_millis() as u64;
+    emit(|| match error {
+        None => tracing::debug!(
+            target: "kinode_process_lib::send",
+            %target,
+            action = sent.action,
+            body_size = sent.body_size,
+            latency_ms,
+            "response received",
+        ),
+        Some(kind) => tracing::debug!(
+            target: "kinode_process_lib::send",
+            %target,
+            action = sent.action,
+            body_size = sent.body_size,
+            latency_ms,
+            error = ?kind,
+            "request failed",
+        ),
+    });
+}
+
+pub(crate) fn response_sent(body: &[u8]) {
+    emit(|| {
+        tracing::debug!(
+            target: "kinode_process_lib::send",
+            action = action_discriminant(body),
+            body_size = body.len(),
+            "response sent",
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::action_discriminant;
+
+    #[test]
+    fn test_action_discriminant() {
+        assert_eq!(
+            action_discriminant(br#""GetPeers""#),
+            Some("GetPeers".into())
+        );
+        assert_eq!(action_discriminant(br#"{"Get":[1,2]}"#), Some("Get".into()));
+        assert_eq!(
+            action_discriminant(br#"{"path":"/a/b","action":{"Read":null}}"#),
+            Some("Read".into())
+        );
+        assert_eq!(action_discriminant(br#"{"a":1,"b":2}"#), None);
+        assert_eq!(action_discriminant(&[0x92, 0x01]), None);
+    }
+}
@@ -0,0 +1,376 @@
+use super::{metadata, open_dir, path::validate_component, File, FileType, VfsError};
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+
+//
+// Creating and extracting zip archives of VFS files
+//
+
+fn zip_error(e: zip::result::ZipError) -> VfsError {
+    VfsError::IOError(e.to_string())
+}
+
+/// VFS paths are given both with and without a leading `/`: compare them without.
+fn normalize(path: &str) -> &str {
+    path.trim_start_matches('/').trim_end_matches('/')
+}
+
+/// Zip the contents of the directory at `src_dir`, recursively, into a new zip
+/// file at `dest_zip`, replacing any existing file. Entries are named by their
+/// path relative to `src_dir`, and files are compressed with deflate.
+///
+/// The archive is built in the memory of the process before being written. The
+/// process must have read capability for the drive of `src_dir` and write
+/// capability for the drive of `dest_zip`, which must not be inside `src_dir`.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::zip_dir;
+///
+/// zip_dir("/my-app:template.os/notes", "/my-app:template.os/exports/notes.zip", None)?;
+/// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+/// ```
+pub fn zip_dir(src_dir: &str, dest_zip: &str, timeout: Option<u64>) -> Result<(), VfsError> {
+    let timeout = timeout.unwrap_or(5);
+    let root = normalize(src_dir);
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    let mut dirs = vec![root.to_string()];
+    while let Some(dir) = dirs.pop() {
+        for entry in open_dir(&format!("/{}", dir), false, Some(timeout))?.read()? {
+            let path = normalize(&entry.path);
+            let Some(name) = path
+                .strip_prefix(root)
+                .map(|name| name.trim_start_matches('/'))
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            match entry.file_type {
+                FileType::Directory => {
+                    writer.add_directory(name, options()).map_err(zip_error)?;
+                    dirs.push(path.to_string());
+                }
+                FileType::File => {
+                    let data = File::new(format!("/{}", path), timeout).read()?;
+                    add_file(&mut writer, name, &data)?;
+                }
+                _ => {}
+            }
+        }
+    }
+    write_zip(writer, dest_zip, timeout)
+}
+
+/// Zip the files at `paths` into a new zip file at `dest_zip`, replacing any
+/// existing file. Entries are named by the last segment of their path, so
+/// files with the same name overwrite each other: use [`zip_dir()`] to keep
+/// directory structure.
+pub fn zip_files(paths: &[&str], dest_zip: &str, timeout: Option<u64>) -> Result<(), VfsError> {
+    let timeout = timeout.unwrap_or(5);
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    let mut names = std::collections::HashSet::new();
+    for path in paths {
+        let path = normalize(path);
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if !names.insert(name) {
+            continue;
+        }
+        let data = File::new(format!("/{}", path), timeout).read()?;
+        add_file(&mut writer, name, &data)?;
+    }
+    write_zip(writer, dest_zip, timeout)
+}
+
+fn options() -> SimpleFileOptions {
+    SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated)
+}
+
+fn add_file(
+    writer: &mut zip::ZipWriter<Cursor<Vec<u8>>>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), VfsError> {
+    writer
+        .start_file(
+            name,
+            options().large_file(data.len() as u64 >= u32::MAX as u64),
+        )
+        .map_err(zip_error)?;
+    writer
+        .write_all(data)
+        .map_err(|e| VfsError::IOError(e.to_string()))
+}
+
+fn write_zip(
+    mut writer: zip::ZipWriter<Cursor<Vec<u8>>>,
+    dest_zip: &str,
+    timeout: u64,
+) -> Result<(), VfsError> {
+    let bytes = writer.finish().map_err(zip_error)?.into_inner();
+    File::new(dest_zip, timeout).write(&bytes)
+}
+
+/// Which entries of a zip archive [`unzip()`] extracts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnzipOptions {
+    /// Extract only entries whose name starts with one of these prefixes, or
+    /// matches one of these globs, where `*` matches any characters but `/`,
+    /// `**` any characters, and `?` one character. Empty extracts every entry.
+    pub include: Vec<String>,
+    /// Strip this prefix from entry names before extracting them.
+    pub strip_prefix: Option<String>,
+    /// Skip files that already exist, instead of overwriting them.
+    pub skip_existing: bool,
+}
+
+impl UnzipOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn include<T>(mut self, pattern: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.include.push(pattern.into());
+        self
+    }
+    pub fn strip_prefix<T>(mut self, prefix: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.strip_prefix = Some(prefix.into());
+        self
+    }
+    pub fn skip_existing(mut self, skip_existing: bool) -> Self {
+        self.skip_existing = skip_existing;
+        self
+    }
+
+    fn includes(&self, name: &str) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| name.starts_with(pattern.as_str()) || glob_match(pattern, name))
+    }
+}
+
+/// Progress of [`unzip()`], given to its callback after each entry is extracted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnzipProgress<'a> {
+    /// The name of the entry in the archive.
+    pub name: &'a str,
+    /// The VFS path it was extracted to.
+    pub path: &'a str,
+    /// The uncompressed size of the entry.
+    pub size: u64,
+    /// How many entries have been extracted so far, including this one.
+    pub extracted: usize,
+    /// How many entries of the archive are extracted in total.
+    pub total: usize,
+}
+
+/// Extract the zip file at `zip_path` into the directory at `dest_dir`, creating
+/// it if needed, and calling `on_progress` after each entry. Returns the number
+/// of entries extracted.
+///
+/// Unlike `AddZip`, which extracts a whole archive in the VFS, this extracts
+/// entry by entry in the process, so entries can be selected with `options`
+/// and progress reported. Entries whose names are absolute or contain `..`,
+/// which would be extracted outside `dest_dir`, fail with [`VfsError::UnzipError`]
+/// before anything is written.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::{unzip, UnzipOptions};
+///
+/// unzip(
+///     "/my-app:template.os/downloads/pkg.zip",
+///     "/my-app:template.os/pkg",
+///     &UnzipOptions::new().include("ui/").include("*.wasm"),
+///     None,
+///     |progress| println!("{}/{}: {}", progress.extracted, progress.total, progress.name),
+/// )?;
+/// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+/// ```
+pub fn unzip<F>(
+    zip_path: &str,
+    dest_dir: &str,
+    options: &UnzipOptions,
+    timeout: Option<u64>,
+    mut on_progress: F,
+) -> Result<usize, VfsError>
+where
+    F: FnMut(UnzipProgress<'_>),
+{
+    let timeout = timeout.unwrap_or(5);
+    let bytes = File::new(zip_path, timeout).read()?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+
+    // select entries and check their names before writing anything
+    let dest_dir = format!("/{}", normalize(dest_dir));
+    let mut entries = vec![];
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(zip_error)?;
+        if !options.includes(entry.name()) {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else {
+            return Err(VfsError::UnzipError);
+        };
+        let name = name.to_string_lossy().replace('\\', "/");
+        let Some(components) = entry_components(&name, options.strip_prefix.as_deref())? else {
+            continue;
+        };
+        let path = format!("{}/{}", dest_dir, components.join("/"));
+        if options.skip_existing && !entry.is_dir() && metadata(&path, Some(timeout)).is_ok() {
+            continue;
+        }
+        entries.push((index, name, path, entry.is_dir()));
+    }
+
+    open_dir(&dest_dir, true, Some(timeout))?;
+    let total = entries.len();
+    for (extracted, (index, name, path, is_dir)) in entries.into_iter().enumerate() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        if is_dir {
+            open_dir(&path, true, Some(timeout))?;
+        } else {
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                open_dir(parent, true, Some(timeout))?;
+            }
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            entry
+                .read_to_end(&mut data)
+                .map_err(|e| VfsError::IOError(e.to_string()))?;
+            File::new(path.as_str(), timeout).write(&data)?;
+        }
+        on_progress(UnzipProgress {
+            name: &name,
+            path: &path,
+            size: entry.size(),
+            extracted: extracted + 1,
+            total,
+        });
+    }
+    Ok(total)
+}
+
+/// The path components of the entry `name` under the extraction directory, after
+/// removing `strip_prefix`, or `None` if the entry is skipped. Fails if any component
+/// is empty, `.` or `..`, so that no entry is extracted outside the directory.
+fn entry_components<'a>(
+    name: &'a str,
+    strip_prefix: Option<&str>,
+) -> Result<Option<Vec<&'a str>>, VfsError> {
+    let name = match strip_prefix {
+        Some(prefix) => match name.strip_prefix(prefix) {
+            Some(name) => name.trim_start_matches('/'),
+            None => return Ok(None),
+        },
+        None => name,
+    };
+    let name = name.trim_end_matches('/');
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let components: Vec<&str> = name.split('/').collect();
+    if components
+        .iter()
+        .any(|component| validate_component(component).is_err())
+    {
+        return Err(VfsError::UnzipError);
+    }
+    Ok(Some(components))
+}
+
+/// Back up a drive, e.g. `/my-app:template.os/data`, to a new zip file at
+/// `dest_zip`, which must be outside the drive: see [`zip_dir()`].
+pub fn export_drive(drive: &str, dest_zip: &str, timeout: Option<u64>) -> Result<(), VfsError> {
+    zip_dir(drive, dest_zip, timeout)
+}
+
+/// Restore a drive from a zip file made with [`export_drive()`], creating the
+/// drive's directories as needed. Returns the number of entries extracted.
+///
+/// Files in the archive replace existing files if `overwrite` is set, and are
+/// skipped otherwise. Files in the drive but not in the archive are kept.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::{export_drive, import_drive};
+///
+/// export_drive("/my-app:template.os/data", "/my-app:template.os/backups/data.zip", None)?;
+/// // ...
+/// import_drive("/my-app:template.os/backups/data.zip", "/my-app:template.os/data", true, None)?;
+/// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+/// ```
+pub fn import_drive(
+    src_zip: &str,
+    drive: &str,
+    overwrite: bool,
+    timeout: Option<u64>,
+) -> Result<usize, VfsError> {
+    unzip(
+        src_zip,
+        drive,
+        &UnzipOptions::new().skip_existing(!overwrite),
+        timeout,
+        |_| {},
+    )
+}
+
+/// Match `name` against `pattern`, where `*` matches any characters but `/`,
+/// `**` any characters, and `?` one character other than `/`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[u8], name: &[u8]) -> bool {
+        match pattern {
+            [] => name.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            [b'*', rest @ ..] => (0..=name.len())
+                .take_while(|&i| i == 0 || name[i - 1] != b'/')
+                .any(|i| matches(rest, &name[i..])),
+            [b'?', rest @ ..] => {
+                matches!(name.first(), Some(c) if *c != b'/') && matches(rest, &name[1..])
+            }
+            [c, rest @ ..] => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), name.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.wasm", "app.wasm"));
+        assert!(!glob_match("*.wasm", "pkg/app.wasm"));
+        assert!(glob_match("**/*.wasm", "pkg/bin/app.wasm"));
+        assert!(glob_match("ui/?.js", "ui/a.js"));
+        assert!(!glob_match("ui/?.js", "ui/ab.js"));
+
+        let options = UnzipOptions::new().include("ui/").include("*.wasm");
+        assert!(options.includes("ui/index.html"));
+        assert!(options.includes("app.wasm"));
+        assert!(!options.includes("pkg/manifest.json"));
+        assert!(UnzipOptions::new().includes("anything"));
+    }
+
+    #[test]
+    fn test_entry_components() {
+        assert_eq!(
+            entry_components("a/b/c.txt", Some("a/")).unwrap(),
+            Some(vec!["b", "c.txt"])
+        );
+        assert_eq!(entry_components("ui/", None).unwrap(), Some(vec!["ui"]));
+        assert_eq!(entry_components("a/", Some("a")).unwrap(), None);
+        assert_eq!(entry_components("b/c.txt", Some("a/")).unwrap(), None);
+        assert!(entry_components("a/../../x", Some("a/")).is_err());
+        assert!(entry_components("a/../../x", Some("a")).is_err());
+        assert!(entry_components("a/../b", None).is_err());
+        assert!(entry_components("a//b", None).is_err());
+        assert!(entry_components("./b", None).is_err());
+    }
+}
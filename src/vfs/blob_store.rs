@@ -0,0 +1,115 @@
+use super::{metadata, open_dir, remove_file, write_atomic, File, FileType, VfsError};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// A content-addressed store of byte blobs in a VFS directory: each blob is
+/// stored once, in a file named by its SHA-256 hash, so identical content put
+/// by different parts of an app is deduplicated.
+///
+/// Blobs are spread over subdirectories named by the first byte of their hash.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::BlobStore;
+/// use std::collections::HashSet;
+///
+/// let store = BlobStore::open("/my-app:template.os/blobs", None)?;
+/// let hash = store.put(b"a picture")?;
+/// assert_eq!(store.get(&hash)?, b"a picture");
+/// // remove every blob but this one
+/// store.gc(&HashSet::from([hash]))?;
+/// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlobStore {
+    dir: String,
+    timeout: u64,
+}
+
+impl BlobStore {
+    /// Open the store in the directory at `dir`, creating it if needed.
+    pub fn open(dir: &str, timeout: Option<u64>) -> Result<Self, VfsError> {
+        let dir = open_dir(dir.trim_end_matches('/'), true, timeout)?;
+        Ok(Self {
+            dir: dir.path.clone(),
+            timeout: dir.timeout,
+        })
+    }
+
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+
+    /// The hash under which `bytes` are stored.
+    pub fn hash(bytes: &[u8]) -> [u8; 32] {
+        Sha256::digest(bytes).into()
+    }
+
+    fn path(&self, hash: &[u8; 32]) -> String {
+        let name = hex::encode(hash);
+        format!("{}/{}/{}", self.dir, &name[..2], name)
+    }
+
+    /// Store `bytes`, unless already stored, returning their hash. Blobs are
+    /// written atomically, so a blob in the store is always complete.
+    pub fn put(&self, bytes: &[u8]) -> Result<[u8; 32], VfsError> {
+        let hash = Self::hash(bytes);
+        if !self.has(&hash)? {
+            let path = self.path(&hash);
+            let (parent, _) = path.rsplit_once('/').unwrap();
+            open_dir(parent, true, Some(self.timeout))?;
+            write_atomic(&path, bytes, Some(self.timeout))?;
+        }
+        Ok(hash)
+    }
+
+    /// The blob stored under `hash`.
+    pub fn get(&self, hash: &[u8; 32]) -> Result<Vec<u8>, VfsError> {
+        File::new(self.path(hash), self.timeout).read()
+    }
+
+    pub fn has(&self, hash: &[u8; 32]) -> Result<bool, VfsError> {
+        match metadata(&self.path(hash), Some(self.timeout)) {
+            Ok(_) => Ok(true),
+            Err(e @ (VfsError::SendError(_) | VfsError::NoReadCap)) => Err(e),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Remove the blob stored under `hash`, if any.
+    pub fn remove(&self, hash: &[u8; 32]) -> Result<(), VfsError> {
+        if self.has(hash)? {
+            remove_file(&self.path(hash), Some(self.timeout))?;
+        }
+        Ok(())
+    }
+
+    /// Remove every blob whose hash is not in `referenced`, returning how many were removed.
+    pub fn gc(&self, referenced: &HashSet<[u8; 32]>) -> Result<usize, VfsError> {
+        let mut removed = 0;
+        for subdir in open_dir(&self.dir, false, Some(self.timeout))?.read()? {
+            if subdir.file_type != FileType::Directory {
+                continue;
+            }
+            let subdir = open_dir(
+                &format!("/{}", subdir.path.trim_start_matches('/')),
+                false,
+                Some(self.timeout),
+            )?;
+            for entry in subdir.read()? {
+                let name = entry.path.rsplit('/').next().unwrap_or_default();
+                let Some(hash) = hex::decode(name)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                else {
+                    continue;
+                };
+                if entry.file_type == FileType::File && !referenced.contains(&hash) {
+                    remove_file(&self.path(&hash), Some(self.timeout))?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
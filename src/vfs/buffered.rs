@@ -0,0 +1,169 @@
+use super::{File, VfsError};
+use std::io;
+
+/// Default capacity of [`BufferedFileWriter`] and [`BufferedFileReader`]: 64 KiB.
+const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+fn io_error(e: VfsError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// Wrapper over a [`File`] implementing [`io::Write`], which batches small writes
+/// into one `Append` request per `capacity` bytes, rather than one per write.
+///
+/// Buffered data is appended when the buffer fills, on [`io::Write::flush()`],
+/// and on drop, where errors are ignored: flush before dropping to see them.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::{create_file, BufferedFileWriter};
+/// use std::io::Write;
+///
+/// let file = create_file("/my-app:template.os/export/items.csv", None)?;
+/// let mut writer = BufferedFileWriter::new(file);
+/// for i in 0..1000 {
+///     writeln!(writer, "{},item {}", i, i)?;
+/// }
+/// writer.flush()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct BufferedFileWriter {
+    file: File,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl BufferedFileWriter {
+    pub fn new(file: File) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, file)
+    }
+
+    pub fn with_capacity(capacity: usize, file: File) -> Self {
+        Self {
+            file,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+
+    /// The data written but not yet appended to the file.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn flush_buffer(&mut self) -> Result<(), VfsError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.file.append(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl io::Write for BufferedFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buffer.len() + buf.len() > self.capacity {
+            self.flush_buffer().map_err(io_error)?;
+        }
+        if buf.len() >= self.capacity {
+            self.file.append(buf).map_err(io_error)?;
+        } else {
+            self.buffer.extend_from_slice(buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buffer().map_err(io_error)
+    }
+}
+
+impl Drop for BufferedFileWriter {
+    fn drop(&mut self) {
+        let _ = self.flush_buffer();
+    }
+}
+
+/// Wrapper over a [`File`] implementing [`io::Read`] and [`io::BufRead`], which
+/// reads the file from its start in chunks of `capacity` bytes, one request per
+/// chunk, rather than one per read.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::{open_file, BufferedFileReader};
+/// use std::io::BufRead;
+///
+/// let file = open_file("/my-app:template.os/export/items.csv", false, None)?;
+/// for line in BufferedFileReader::new(file)?.lines() {
+///     println!("{}", line?);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct BufferedFileReader {
+    file: File,
+    buffer: Vec<u8>,
+    /// How much of `buffer` has been consumed.
+    position: usize,
+    capacity: usize,
+    /// The length of the file, and how much of it has been read into buffers.
+    len: u64,
+    offset: u64,
+}
+
+impl BufferedFileReader {
+    pub fn new(file: File) -> Result<Self, VfsError> {
+        Self::with_capacity(DEFAULT_CAPACITY, file)
+    }
+
+    /// Read `file` from the start in chunks of `capacity` bytes.
+    pub fn with_capacity(capacity: usize, file: File) -> Result<Self, VfsError> {
+        let len = file.metadata()?.len;
+        Ok(Self {
+            file,
+            buffer: vec![],
+            position: 0,
+            capacity: capacity.max(1),
+            len,
+            offset: 0,
+        })
+    }
+
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl io::Read for BufferedFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = io::BufRead::fill_buf(self)?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        io::BufRead::consume(self, len);
+        Ok(len)
+    }
+}
+
+impl io::BufRead for BufferedFileReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position >= self.buffer.len() && self.offset < self.len {
+            let chunk = (self.len - self.offset).min(self.capacity as u64);
+            self.buffer = self.file.read_range(self.offset, chunk).map_err(io_error)?;
+            self.position = 0;
+            self.offset += self.buffer.len() as u64;
+            if self.buffer.is_empty() {
+                // the file shrank since it was opened
+                self.len = self.offset;
+            }
+        }
+        Ok(&self.buffer[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.buffer.len());
+    }
+}
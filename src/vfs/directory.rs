@@ -1,4 +1,7 @@
-use super::{parse_response, vfs_request, DirEntry, FileType, VfsAction, VfsError, VfsResponse};
+use super::{
+    parse_response, path::validate_component, vfs_request, DirEntry, FileMetadata, FileType,
+    VfsAction, VfsError, VfsResponse,
+};
 
 /// VFS (Virtual File System) helper struct for a directory.
 /// Opening or creating a directory will give you a `Result<Directory>`.
@@ -26,6 +29,86 @@ impl Directory {
             }),
         }
     }
+
+    /// Iterates through children of `Directory`, as [`Directory::read()`], returning
+    /// the metadata of each child alongside it, in one request. Children can then be
+    /// sorted, e.g. by [`FileMetadata::modified`].
+    pub fn read_with_metadata(&self) -> Result<Vec<(DirEntry, FileMetadata)>, VfsError> {
+        let message = vfs_request(&self.path, VfsAction::ReadDirWithMetadata)
+            .send_and_await_response(self.timeout)
+            .unwrap()
+            .map_err(|e| VfsError::SendError(e.kind))?;
+
+        match parse_response(message.body())? {
+            VfsResponse::ReadDirWithMetadata(entries) => Ok(entries),
+            VfsResponse::Err(e) => Err(e),
+            _ => Err(VfsError::ParseError {
+                error: "unexpected response".to_string(),
+                path: self.path.clone(),
+            }),
+        }
+    }
+
+    /// Iterates through children of `Directory`, as [`Directory::read()`], sorted by path.
+    pub fn entries_sorted(&self) -> Result<Vec<DirEntry>, VfsError> {
+        let mut entries = self.read()?;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    /// Whether the `Directory` has a child, file or directory, called `name`.
+    pub fn contains(&self, name: &str) -> Result<bool, VfsError> {
+        Ok(self
+            .read()?
+            .iter()
+            .any(|entry| entry.path.trim_end_matches('/').rsplit('/').next() == Some(name)))
+    }
+
+    /// Creates a child directory called `name`, if it doesn't exist, and returns it.
+    pub fn create_subdir(&self, name: &str) -> Result<Directory, VfsError> {
+        open_dir(&self.child_path(name)?, true, Some(self.timeout))
+    }
+
+    /// Removes the child called `name`: a file, or a directory with everything in it.
+    pub fn remove_entry(&self, name: &str) -> Result<(), VfsError> {
+        let path = self.child_path(name)?;
+        let action = match super::metadata(&path, Some(self.timeout))?.file_type {
+            FileType::Directory => VfsAction::RemoveDirAll,
+            _ => VfsAction::RemoveFile,
+        };
+        self.send_ok(&path, action)
+    }
+
+    /// Renames the child called `from` to `to`, replacing any file called `to`.
+    pub fn rename_entry(&self, from: &str, to: &str) -> Result<(), VfsError> {
+        let new_path = self.child_path(to)?;
+        self.send_ok(&self.child_path(from)?, VfsAction::Rename { new_path })
+    }
+
+    /// The path of the child called `name`, which must be a single path component.
+    fn child_path(&self, name: &str) -> Result<String, VfsError> {
+        validate_component(name).map_err(|error| VfsError::ParseError {
+            error: error.to_string(),
+            path: name.to_string(),
+        })?;
+        Ok(format!("{}/{}", self.path.trim_end_matches('/'), name))
+    }
+
+    fn send_ok(&self, path: &str, action: VfsAction) -> Result<(), VfsError> {
+        let message = vfs_request(path, action)
+            .send_and_await_response(self.timeout)
+            .unwrap()
+            .map_err(|e| VfsError::SendError(e.kind))?;
+
+        match parse_response(message.body())? {
+            VfsResponse::Ok => Ok(()),
+            VfsResponse::Err(e) => Err(e),
+            _ => Err(VfsError::ParseError {
+                error: "unexpected response".to_string(),
+                path: path.to_string(),
+            }),
+        }
+    }
 }
 
 /// Opens or creates a `Directory` at path.
@@ -1,5 +1,6 @@
 use super::{
-    parse_response, vfs_request, FileMetadata, SeekFrom, VfsAction, VfsError, VfsResponse,
+    parse_response, vfs_request, FileMetadata, HashAlgorithm, SeekFrom, VfsAction, VfsError,
+    VfsResponse,
 };
 use crate::{get_blob, PackageId};
 
@@ -97,6 +98,47 @@ impl File {
         }
     }
 
+    /// Reads up to `length` bytes starting at `offset`, in one request, without
+    /// moving the cursor. Returns fewer bytes if the file ends first.
+    pub fn read_range(&self, offset: u64, length: u64) -> Result<Vec<u8>, VfsError> {
+        let message = vfs_request(&self.path, VfsAction::ReadAt { offset, length })
+            .send_and_await_response(self.timeout)
+            .unwrap()
+            .map_err(|e| VfsError::SendError(e.kind))?;
+
+        match parse_response(message.body())? {
+            VfsResponse::Read => Ok(get_blob().unwrap_or_default().bytes),
+            VfsResponse::Err(e) => Err(e),
+            _ => Err(VfsError::ParseError {
+                error: "unexpected response".to_string(),
+                path: self.path.clone(),
+            }),
+        }
+    }
+
+    /// Iterate over the file from its start in chunks of `chunk_size` bytes, one
+    /// request per chunk, until the end of the file. The cursor is not moved.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use kinode_process_lib::vfs::open_file;
+    ///
+    /// let file = open_file("/my-app:template.os/videos/intro.mp4", false, None)?;
+    /// for chunk in file.chunks(1024 * 1024) {
+    ///     let chunk = chunk?;
+    ///     // ...
+    /// }
+    /// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+    /// ```
+    pub fn chunks(&self, chunk_size: u64) -> FileChunks<'_> {
+        FileChunks {
+            file: self,
+            offset: 0,
+            chunk_size: chunk_size.max(1),
+            done: false,
+        }
+    }
+
     /// Reads until end of file from current cursor position
     /// Returns a vector of bytes.
     pub fn read_to_end(&self) -> Result<Vec<u8>, VfsError> {
@@ -134,6 +176,44 @@ impl File {
         }
     }
 
+    /// Reads the entire file and deserializes it from JSON.
+    pub fn read_json<T>(&self) -> Result<T, VfsError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        serde_json::from_slice(&self.read()?).map_err(|e| VfsError::ParseError {
+            error: format!("failed to deserialize JSON: {}", e),
+            path: self.path.clone(),
+        })
+    }
+
+    /// Serializes `value` to JSON and writes it as the new file, as [`File::write()`].
+    pub fn write_json<T>(&self, value: &T) -> Result<(), VfsError>
+    where
+        T: serde::Serialize,
+    {
+        self.write(&self.to_json(value)?)
+    }
+
+    /// Serializes `value` to JSON and writes it as the new file, atomically, as
+    /// [`File::write_atomic()`].
+    pub fn write_json_atomic<T>(&self, value: &T) -> Result<(), VfsError>
+    where
+        T: serde::Serialize,
+    {
+        self.write_atomic(&self.to_json(value)?)
+    }
+
+    fn to_json<T>(&self, value: &T) -> Result<Vec<u8>, VfsError>
+    where
+        T: serde::Serialize,
+    {
+        serde_json::to_vec_pretty(value).map_err(|e| VfsError::ParseError {
+            error: format!("failed to serialize JSON: {}", e),
+            path: self.path.clone(),
+        })
+    }
+
     /// Write entire slice as the new file.
     /// Truncates anything that existed at path before.
     pub fn write(&self, buffer: &[u8]) -> Result<(), VfsError> {
@@ -153,6 +233,11 @@ impl File {
         }
     }
 
+    /// Write entire slice as the new file, atomically: see [`write_atomic()`].
+    pub fn write_atomic(&self, buffer: &[u8]) -> Result<(), VfsError> {
+        write_atomic(&self.path, buffer, Some(self.timeout))
+    }
+
     /// Write buffer to file at current position, overwriting any existing data.
     pub fn write_all(&mut self, buffer: &[u8]) -> Result<(), VfsError> {
         let message = vfs_request(&self.path, VfsAction::WriteAll)
@@ -171,6 +256,25 @@ impl File {
         }
     }
 
+    /// Write buffer to file starting at `offset`, in one request, overwriting any
+    /// existing data and extending the file if needed. The cursor is not moved.
+    pub fn write_at(&self, offset: u64, buffer: &[u8]) -> Result<(), VfsError> {
+        let message = vfs_request(&self.path, VfsAction::WriteAt { offset })
+            .blob_bytes(buffer)
+            .send_and_await_response(self.timeout)
+            .unwrap()
+            .map_err(|e| VfsError::SendError(e.kind))?;
+
+        match parse_response(message.body())? {
+            VfsResponse::Ok => Ok(()),
+            VfsResponse::Err(e) => Err(e),
+            _ => Err(VfsError::ParseError {
+                error: "unexpected response".to_string(),
+                path: self.path.clone(),
+            }),
+        }
+    }
+
     /// Write buffer to the end position of file.
     pub fn append(&mut self, buffer: &[u8]) -> Result<(), VfsError> {
         let message = vfs_request(&self.path, VfsAction::Append)
@@ -189,6 +293,28 @@ impl File {
         }
     }
 
+    /// Write entire slice as the new file, as [`File::write()`], without awaiting a response.
+    ///
+    /// No response is received, so the blob of the message being handled is
+    /// left in place, and errors, e.g. a missing write capability, go unreported.
+    pub fn write_no_response(&self, buffer: &[u8]) {
+        vfs_request(&self.path, VfsAction::Write)
+            .blob_bytes(buffer)
+            .send()
+            .unwrap();
+    }
+
+    /// Write buffer to the end position of file, as [`File::append()`], without awaiting a response.
+    ///
+    /// No response is received, so the blob of the message being handled is
+    /// left in place, and errors, e.g. a missing write capability, go unreported.
+    pub fn append_no_response(&self, buffer: &[u8]) {
+        vfs_request(&self.path, VfsAction::Append)
+            .blob_bytes(buffer)
+            .send()
+            .unwrap();
+    }
+
     /// Seek file to position.
     /// Returns the new position.
     pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, VfsError> {
@@ -268,6 +394,37 @@ impl File {
         }
     }
 
+    /// Hash `length` bytes of the file starting at `offset`, or the rest of the
+    /// file if `length` is `None`, with the given algorithm. The hash is computed
+    /// by the VFS, so the data is never read into the process.
+    pub fn hash_range(
+        &self,
+        offset: u64,
+        length: Option<u64>,
+        algorithm: HashAlgorithm,
+    ) -> Result<[u8; 32], VfsError> {
+        let message = vfs_request(
+            &self.path,
+            VfsAction::HashRange {
+                offset,
+                length,
+                algorithm,
+            },
+        )
+        .send_and_await_response(self.timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+
+        match parse_response(message.body())? {
+            VfsResponse::Hash(hash) => Ok(hash),
+            VfsResponse::Err(e) => Err(e),
+            _ => Err(VfsError::ParseError {
+                error: "unexpected response".to_string(),
+                path: self.path.clone(),
+            }),
+        }
+    }
+
     /// Syncs path file buffers to disk.
     pub fn sync_all(&self) -> Result<(), VfsError> {
         let message = vfs_request(&self.path, VfsAction::SyncAll)
@@ -286,6 +443,46 @@ impl File {
     }
 }
 
+/// Iterator over the chunks of a [`File`]: see [`File::chunks()`].
+pub struct FileChunks<'a> {
+    file: &'a File,
+    offset: u64,
+    chunk_size: u64,
+    done: bool,
+}
+
+impl FileChunks<'_> {
+    /// The offset in the file of the next chunk.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl Iterator for FileChunks<'_> {
+    type Item = Result<Vec<u8>, VfsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.file.read_range(self.offset, self.chunk_size) {
+            Ok(chunk) if chunk.is_empty() => {
+                self.done = true;
+                None
+            }
+            Ok(chunk) => {
+                self.offset += chunk.len() as u64;
+                self.done = (chunk.len() as u64) < self.chunk_size;
+                Some(Ok(chunk))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 impl Drop for File {
     fn drop(&mut self) {
         vfs_request(&self.path, VfsAction::CloseFile)
@@ -382,3 +579,51 @@ pub fn remove_file(path: &str, timeout: Option<u64>) -> Result<(), VfsError> {
         }),
     }
 }
+
+/// Write entire slice as the new file at path, replacing any existing file, atomically:
+/// the data is written to a temporary file next to path, which is then renamed
+/// over path. If the write fails or times out, path is left as it was.
+pub fn write_atomic(path: &str, buffer: &[u8], timeout: Option<u64>) -> Result<(), VfsError> {
+    let timeout = timeout.unwrap_or(5);
+    let temp_path = format!("{}.tmp-{:016x}", path, rand::random::<u64>());
+
+    let message = vfs_request(&temp_path, VfsAction::Write)
+        .blob_bytes(buffer)
+        .send_and_await_response(timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+    match parse_response(message.body())? {
+        VfsResponse::Ok => {}
+        VfsResponse::Err(e) => {
+            let _ = remove_file(&temp_path, Some(timeout));
+            return Err(e);
+        }
+        _ => {
+            return Err(VfsError::ParseError {
+                error: "unexpected response".to_string(),
+                path: temp_path,
+            })
+        }
+    }
+
+    let message = vfs_request(
+        &temp_path,
+        VfsAction::Rename {
+            new_path: path.to_string(),
+        },
+    )
+    .send_and_await_response(timeout)
+    .unwrap()
+    .map_err(|e| VfsError::SendError(e.kind))?;
+    match parse_response(message.body())? {
+        VfsResponse::Ok => Ok(()),
+        VfsResponse::Err(e) => {
+            let _ = remove_file(&temp_path, Some(timeout));
+            Err(e)
+        }
+        _ => Err(VfsError::ParseError {
+            error: "unexpected response".to_string(),
+            path: path.to_string(),
+        }),
+    }
+}
@@ -0,0 +1,209 @@
+use super::{open_file, BufferedFileReader, File, VfsError};
+use std::io::Read;
+
+//
+// Append-only log of checksummed records, e.g. for event sourcing or durable queues
+//
+
+/// Length of the header of each record: sequence number, data length, CRC-32 of the data.
+const HEADER_LEN: usize = 8 + 4 + 4;
+
+/// A record of a [`LogFile`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub data: Vec<u8>,
+}
+
+/// An append-only log in a VFS file. Each record is framed with its sequence
+/// number, length and a CRC-32 checksum, so that a record cut short by a crash
+/// is detected: [`LogFile::open()`] truncates the log after the last intact record.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::LogFile;
+///
+/// let mut log = LogFile::open("/my-app:template.os/data/events.log", None)?;
+/// let seq = log.append(b"user signed up")?;
+/// for record in log.replay_from(0)? {
+///     let record = record?;
+///     println!("{}: {:?}", record.seq, record.data);
+/// }
+/// log.truncate_before(seq)?;
+/// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+/// ```
+pub struct LogFile {
+    file: File,
+    next_seq: u64,
+}
+
+impl LogFile {
+    /// Open the log at `path`, creating it if needed, and recover from a torn
+    /// or corrupt tail by truncating the file after the last intact record.
+    pub fn open(path: &str, timeout: Option<u64>) -> Result<Self, VfsError> {
+        let mut file = open_file(path, true, timeout)?;
+        let mut next_seq = 0;
+        let mut valid_len = 0;
+        let len = file.metadata()?.len;
+        let mut replay = LogReplay::new(File::new(path, file.timeout), 0)?;
+        while let Some(Ok(record)) = replay.next() {
+            next_seq = record.seq + 1;
+            valid_len += (HEADER_LEN + record.data.len()) as u64;
+        }
+        if valid_len < len {
+            file.set_len(valid_len)?;
+        }
+        Ok(Self { file, next_seq })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.file.path
+    }
+
+    /// The sequence number the next appended record will get.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Append a record, returning its sequence number.
+    pub fn append(&mut self, data: &[u8]) -> Result<u64, VfsError> {
+        let seq = self.next_seq;
+        self.file.append(&encode_record(seq, data))?;
+        self.next_seq += 1;
+        Ok(seq)
+    }
+
+    /// Iterate over the records with sequence number `seq` or above, in order.
+    pub fn replay_from(&self, seq: u64) -> Result<LogReplay, VfsError> {
+        LogReplay::new(File::new(self.file.path.clone(), self.file.timeout), seq)
+    }
+
+    /// Remove the records with sequence numbers below `seq`, e.g. once they are
+    /// reflected in a snapshot. Sequence numbers of later records are unchanged.
+    pub fn truncate_before(&mut self, seq: u64) -> Result<(), VfsError> {
+        self.compact(|record| record.seq >= seq)
+    }
+
+    /// Rewrite the log keeping only the records for which `keep` returns true,
+    /// atomically: see [`super::write_atomic()`]. Sequence numbers are unchanged.
+    pub fn compact<F>(&mut self, mut keep: F) -> Result<(), VfsError>
+    where
+        F: FnMut(&LogRecord) -> bool,
+    {
+        let mut bytes = vec![];
+        for record in self.replay_from(0)? {
+            let record = record?;
+            if keep(&record) {
+                bytes.extend_from_slice(&encode_record(record.seq, &record.data));
+            }
+        }
+        self.file.write_atomic(&bytes)?;
+        // close the handle the VFS holds to the file replaced by the rename
+        self.file = File::new(self.file.path.clone(), self.file.timeout);
+        Ok(())
+    }
+}
+
+/// Iterator over the records of a [`LogFile`]: see [`LogFile::replay_from()`].
+/// Ends at the end of the log, or with an error at a corrupt record.
+pub struct LogReplay {
+    reader: BufferedFileReader,
+    from: u64,
+    done: bool,
+}
+
+impl LogReplay {
+    fn new(file: File, from: u64) -> Result<Self, VfsError> {
+        Ok(Self {
+            reader: BufferedFileReader::new(file)?,
+            from,
+            done: false,
+        })
+    }
+
+    fn corrupt(&self, error: &str) -> VfsError {
+        VfsError::ParseError {
+            error: error.to_string(),
+            path: self.reader.get_ref().path.clone(),
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<LogRecord>, VfsError> {
+        let mut header = [0; HEADER_LEN];
+        let mut read = 0;
+        while read < HEADER_LEN {
+            match self.reader.read(&mut header[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(self.corrupt("truncated log record header")),
+                Ok(n) => read += n,
+                Err(e) => return Err(VfsError::IOError(e.to_string())),
+            }
+        }
+        let seq = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let len = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        let mut data = vec![0; len];
+        self.reader
+            .read_exact(&mut data)
+            .map_err(|_| self.corrupt("truncated log record"))?;
+        if crc32(&data) != checksum {
+            return Err(self.corrupt("log record checksum mismatch"));
+        }
+        Ok(Some(LogRecord { seq, data }))
+    }
+}
+
+impl Iterator for LogReplay {
+    type Item = Result<LogRecord, VfsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.next_record() {
+                Ok(Some(record)) if record.seq < self.from => continue,
+                Ok(Some(record)) => return Some(Ok(record)),
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn encode_record(seq: u64, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + data.len());
+    bytes.extend_from_slice(&seq.to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&crc32(data).to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// CRC-32 (IEEE) of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_record() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        let bytes = encode_record(7, b"hello");
+        assert_eq!(bytes.len(), HEADER_LEN + 5);
+        assert_eq!(u64::from_le_bytes(bytes[0..8].try_into().unwrap()), 7);
+        assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()), 5);
+        assert_eq!(&bytes[HEADER_LEN..], b"hello");
+    }
+}
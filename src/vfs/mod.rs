@@ -2,11 +2,25 @@ use crate::Request;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod archive;
+pub mod blob_store;
+pub mod buffered;
 pub mod directory;
 pub mod file;
+pub mod log_file;
+pub mod path;
+pub mod temp;
+pub mod watch;
 
+pub use archive::*;
+pub use blob_store::*;
+pub use buffered::*;
 pub use directory::*;
 pub use file::*;
+pub use log_file::*;
+pub use path::*;
+pub use temp::*;
+pub use watch::*;
 
 /// IPC body format for requests sent to vfs runtime module.
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +31,8 @@ pub struct VfsRequest {
     pub action: VfsAction,
 }
 
+// keep the short variants on one line, as rustfmt splits them all for `HashRange`
+#[rustfmt::skip]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum VfsAction {
     CreateDrive,
@@ -28,11 +44,14 @@ pub enum VfsAction {
     Write,
     WriteAll,
     Append,
+    WriteAt { offset: u64 },
     SyncAll,
     Read,
     ReadDir,
+    ReadDirWithMetadata,
     ReadToEnd,
     ReadExact { length: u64 },
+    ReadAt { offset: u64, length: u64 },
     ReadToString,
     Seek(SeekFrom),
     RemoveFile,
@@ -45,6 +64,22 @@ pub enum VfsAction {
     Len,
     SetLen(u64),
     Hash,
+    HashRange {
+        offset: u64,
+        length: Option<u64>,
+        algorithm: HashAlgorithm,
+    },
+    Usage,
+    Watch,
+    Unwatch,
+}
+
+/// Hash function used by [`VfsAction::HashRange`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -66,6 +101,42 @@ pub enum FileType {
 pub struct FileMetadata {
     pub file_type: FileType,
     pub len: u64,
+    /// Creation time in milliseconds since the UNIX epoch, if the platform reports it.
+    #[serde(default)]
+    pub created: Option<u64>,
+    /// Last modification time in milliseconds since the UNIX epoch.
+    #[serde(default)]
+    pub modified: Option<u64>,
+    /// Last access time in milliseconds since the UNIX epoch, if the platform reports it.
+    #[serde(default)]
+    pub accessed: Option<u64>,
+}
+
+impl FileMetadata {
+    /// The last modification time as a [`std::time::SystemTime`], if known.
+    pub fn modified_time(&self) -> Option<std::time::SystemTime> {
+        self.modified
+            .map(|ms| std::time::UNIX_EPOCH + std::time::Duration::from_millis(ms))
+    }
+}
+
+/// Disk usage of a drive, or of all drives of a package: see [`drive_usage()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriveUsage {
+    /// Total length of the files, in bytes.
+    pub bytes: u64,
+    pub files: u64,
+}
+
+impl std::ops::Add for DriveUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes + other.bytes,
+            files: self.files + other.files,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -81,10 +152,12 @@ pub enum VfsResponse {
     Read,
     SeekFrom { new_offset: u64 },
     ReadDir(Vec<DirEntry>),
+    ReadDirWithMetadata(Vec<(DirEntry, FileMetadata)>),
     ReadToString(String),
     Metadata(FileMetadata),
     Len(u64),
     Hash([u8; 32]),
+    Usage(DriveUsage),
 }
 
 #[derive(Clone, Debug, Error, Serialize, Deserialize)]
@@ -142,6 +215,48 @@ pub fn metadata(path: &str, timeout: Option<u64>) -> Result<FileMetadata, VfsErr
     }
 }
 
+/// Total length and number of the files in a drive, e.g. `/my-app:template.os/cache`,
+/// counted recursively by the VFS.
+pub fn drive_usage(drive_path: &str, timeout: Option<u64>) -> Result<DriveUsage, VfsError> {
+    let timeout = timeout.unwrap_or(5);
+
+    let message = vfs_request(drive_path, VfsAction::Usage)
+        .send_and_await_response(timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+
+    match parse_response(message.body())? {
+        VfsResponse::Usage(usage) => Ok(usage),
+        VfsResponse::Err(e) => Err(e),
+        _ => Err(VfsError::ParseError {
+            error: "unexpected response".to_string(),
+            path: drive_path.to_string(),
+        }),
+    }
+}
+
+/// [`drive_usage()`] of each drive of `package_id`, by drive path, and their total.
+///
+/// The process must have read capability for every drive of the package.
+pub fn package_usage(
+    package_id: &crate::PackageId,
+    timeout: Option<u64>,
+) -> Result<(DriveUsage, Vec<(String, DriveUsage)>), VfsError> {
+    let package_dir = open_dir(&format!("/{}", package_id), false, timeout)?;
+    let mut total = DriveUsage::default();
+    let mut drives = vec![];
+    for entry in package_dir.read()? {
+        if entry.file_type != FileType::Directory {
+            continue;
+        }
+        let drive_path = format!("/{}", entry.path.trim_start_matches('/'));
+        let usage = drive_usage(&drive_path, timeout)?;
+        total = total + usage;
+        drives.push((drive_path, usage));
+    }
+    Ok((total, drives))
+}
+
 /// Removes a path, if it's either a directory or a file.
 pub fn remove_path(path: &str, timeout: Option<u64>) -> Result<(), VfsError> {
     let meta = metadata(path, timeout)?;
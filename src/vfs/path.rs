@@ -0,0 +1,195 @@
+use super::VfsError;
+use crate::PackageId;
+
+/// A VFS path: a drive of a package, e.g. `/my-app:template.os/data`, and path
+/// components relative to the drive. Converts to the string form expected by
+/// [`super::VfsRequest`], so it can be passed wherever a path is.
+///
+/// Example:
+/// ```
+/// use kinode_process_lib::{vfs::VfsPath, PackageId};
+///
+/// let path = VfsPath::drive(PackageId::new("my-app", "template.os"), "data")
+///     .join("users")
+///     .join("1.json");
+/// assert_eq!(path.to_string(), "/my-app:template.os/data/users/1.json");
+/// assert_eq!(path.file_name(), Some("1.json"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VfsPath {
+    package_id: PackageId,
+    drive: String,
+    components: Vec<String>,
+}
+
+fn invalid(error: &str, path: &str) -> VfsError {
+    VfsError::ParseError {
+        error: error.to_string(),
+        path: path.to_string(),
+    }
+}
+
+/// Check one component of a path, which may not be empty, `.` or `..`, nor contain a separator.
+pub(super) fn validate_component(component: &str) -> Result<(), &'static str> {
+    match component {
+        "" => Err("empty path component"),
+        "." | ".." => Err("relative path component"),
+        _ if component.contains(['/', '\\']) => Err("separator in path component"),
+        _ => Ok(()),
+    }
+}
+
+impl VfsPath {
+    /// The root of `drive` of `package_id`.
+    ///
+    /// # Panics
+    ///
+    /// If `drive` is not a single valid path component: see [`VfsPath::try_join()`].
+    pub fn drive<T>(package_id: PackageId, drive: T) -> Self
+    where
+        T: Into<String>,
+    {
+        let drive = drive.into();
+        if let Err(error) = validate_component(&drive) {
+            panic!("invalid VFS drive {:?}: {}", drive, error);
+        }
+        Self {
+            package_id,
+            drive,
+            components: vec![],
+        }
+    }
+
+    /// Append `path`, one or more components separated by `/`.
+    ///
+    /// # Panics
+    ///
+    /// If a component is `.` or `..`: see [`VfsPath::try_join()`].
+    pub fn join<T>(&self, path: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        match self.try_join(path.as_ref()) {
+            Ok(joined) => joined,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Append `path`, one or more components separated by `/`. Empty components,
+    /// e.g. from a leading or doubled `/`, are skipped. Fails if a component is
+    /// `.` or `..`, or contains a `\`, so that a path cannot escape its drive.
+    pub fn try_join(&self, path: &str) -> Result<Self, VfsError> {
+        let mut joined = self.clone();
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            validate_component(component).map_err(|error| invalid(error, path))?;
+            joined.components.push(component.to_string());
+        }
+        Ok(joined)
+    }
+
+    pub fn package_id(&self) -> &PackageId {
+        &self.package_id
+    }
+
+    pub fn drive_name(&self) -> &str {
+        &self.drive
+    }
+
+    /// The path of the root of the drive.
+    pub fn drive_path(&self) -> String {
+        format!("/{}/{}", self.package_id, self.drive)
+    }
+
+    /// The components after the drive.
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    /// The last component, or `None` for the root of the drive.
+    pub fn file_name(&self) -> Option<&str> {
+        self.components.last().map(|component| component.as_str())
+    }
+
+    /// The path without its last component, or `None` for the root of the drive.
+    pub fn parent(&self) -> Option<Self> {
+        let mut parent = self.clone();
+        parent.components.pop()?;
+        Some(parent)
+    }
+
+    pub fn is_drive_root(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+impl std::fmt::Display for VfsPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}/{}", self.package_id, self.drive)?;
+        for component in &self.components {
+            write!(f, "/{}", component)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for VfsPath {
+    type Err = VfsError;
+    /// Parse a path of the form `/package:publisher/drive/more/components`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut segments = input.trim_start_matches('/').splitn(3, '/');
+        let package_id = segments
+            .next()
+            .and_then(|package_id| package_id.parse::<PackageId>().ok())
+            .ok_or_else(|| invalid("invalid package ID", input))?;
+        let drive = segments
+            .next()
+            .filter(|drive| validate_component(drive).is_ok())
+            .ok_or_else(|| invalid("missing or invalid drive", input))?;
+        Self {
+            package_id,
+            drive: drive.to_string(),
+            components: vec![],
+        }
+        .try_join(segments.next().unwrap_or_default())
+    }
+}
+
+impl From<VfsPath> for String {
+    fn from(path: VfsPath) -> Self {
+        path.to_string()
+    }
+}
+
+impl From<&VfsPath> for String {
+    fn from(path: &VfsPath) -> Self {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vfs_path() {
+        let drive = VfsPath::drive(PackageId::new("my-app", "template.os"), "data");
+        assert!(drive.is_drive_root());
+        assert_eq!(drive.parent(), None);
+
+        let path = drive.join("users/").join("/1.json");
+        assert_eq!(path.to_string(), "/my-app:template.os/data/users/1.json");
+        assert_eq!(path.drive_path(), "/my-app:template.os/data");
+        assert_eq!(path.parent().unwrap().file_name(), Some("users"));
+        assert_eq!(
+            "/my-app:template.os/data/users/1.json"
+                .parse::<VfsPath>()
+                .unwrap(),
+            path
+        );
+
+        assert!(drive.try_join("../other-app:template.os").is_err());
+        assert!(drive.try_join("a\\b").is_err());
+        assert!("my-app:template.os".parse::<VfsPath>().is_err());
+        assert!("/not a package/data".parse::<VfsPath>().is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use super::{
+    create_drive, create_file, open_dir, parse_response, vfs_request, Directory, File, VfsAction,
+    VfsError, VfsResponse,
+};
+use crate::our;
+
+//
+// Temporary files and directories, removed on drop
+//
+
+/// Name of the drive of the package holding temporary files.
+const TEMP_DRIVE: &str = "tmp";
+
+/// Create the `tmp` drive of this process's package if needed, returning its path,
+/// e.g. `/my-app:template.os/tmp`.
+pub fn temp_drive(timeout: Option<u64>) -> Result<String, VfsError> {
+    create_drive(our().package_id(), TEMP_DRIVE, timeout)
+}
+
+fn temp_path(timeout: Option<u64>, suffix: &str) -> Result<String, VfsError> {
+    Ok(unique_path(&temp_drive(timeout)?, suffix))
+}
+
+fn unique_path(drive: &str, suffix: &str) -> String {
+    format!(
+        "{}/{}-{:016x}{}",
+        drive,
+        our().process(),
+        rand::random::<u64>(),
+        suffix
+    )
+}
+
+/// A new, empty file with a unique name in the `tmp` drive, removed when dropped.
+/// Derefs to [`File`].
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::vfs::temp_file;
+///
+/// let file = temp_file(".json", None)?;
+/// file.write(b"{}")?;
+/// // ...
+/// // the file is removed here
+/// # Ok::<(), kinode_process_lib::vfs::VfsError>(())
+/// ```
+pub struct TempFile {
+    file: File,
+    keep: bool,
+}
+
+/// Create a [`TempFile`] whose name ends with `suffix`, e.g. an extension.
+pub fn temp_file(suffix: &str, timeout: Option<u64>) -> Result<TempFile, VfsError> {
+    let path = temp_path(timeout, suffix)?;
+    Ok(TempFile {
+        file: create_file(&path, timeout)?,
+        keep: false,
+    })
+}
+
+/// Create a [`TempFile`] whose name ends with `suffix` holding the blob of the
+/// message being handled, passed to the VFS by inheriting it, so that the blob
+/// is never loaded into the process.
+///
+/// The `tmp` drive must already exist: see [`temp_drive()`]. Call this before
+/// awaiting any other response, which would replace the blob.
+pub fn temp_file_from_blob(suffix: &str, timeout: Option<u64>) -> Result<TempFile, VfsError> {
+    let timeout = timeout.unwrap_or(5);
+    let path = unique_path(&format!("/{}/{}", our().package_id(), TEMP_DRIVE), suffix);
+    let message = vfs_request(&path, VfsAction::Write)
+        .inherit(true)
+        .send_and_await_response(timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+    match parse_response(message.body())? {
+        VfsResponse::Ok => Ok(TempFile {
+            file: File::new(path, timeout),
+            keep: false,
+        }),
+        VfsResponse::Err(e) => Err(e),
+        _ => Err(VfsError::ParseError {
+            error: "unexpected response".to_string(),
+            path,
+        }),
+    }
+}
+
+impl TempFile {
+    /// Keep the file instead of removing it on drop, returning it.
+    pub fn keep(mut self) -> File {
+        self.keep = true;
+        File::new(self.file.path.clone(), self.file.timeout)
+    }
+}
+
+impl std::ops::Deref for TempFile {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl std::ops::DerefMut for TempFile {
+    fn deref_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = vfs_request(&self.file.path, VfsAction::RemoveFile).send();
+        }
+    }
+}
+
+/// A new, empty directory with a unique name in the `tmp` drive, removed with
+/// everything in it when dropped. Derefs to [`Directory`].
+pub struct TempDir {
+    dir: Directory,
+    keep: bool,
+}
+
+/// Create a [`TempDir`].
+pub fn temp_dir(timeout: Option<u64>) -> Result<TempDir, VfsError> {
+    let path = temp_path(timeout, "")?;
+    Ok(TempDir {
+        dir: open_dir(&path, true, timeout)?,
+        keep: false,
+    })
+}
+
+impl TempDir {
+    pub fn path(&self) -> &str {
+        &self.dir.path
+    }
+
+    /// Keep the directory instead of removing it on drop, returning it.
+    pub fn keep(mut self) -> Directory {
+        self.keep = true;
+        Directory {
+            path: self.dir.path.clone(),
+            timeout: self.dir.timeout,
+        }
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = Directory;
+
+    fn deref(&self) -> &Directory {
+        &self.dir
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = vfs_request(&self.dir.path, VfsAction::RemoveDirAll).send();
+        }
+    }
+}
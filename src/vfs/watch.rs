@@ -0,0 +1,77 @@
+use super::{parse_response, vfs_request, VfsAction, VfsError, VfsResponse};
+use crate::Message;
+use serde::{Deserialize, Serialize};
+
+/// [`crate::Request`] sent by `vfs:distro:sys` to a process watching a path with
+/// [`watch()`], when the watched file, or an entry of the watched directory, changes.
+/// No response is expected.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VfsNotification {
+    /// The path given to [`watch()`].
+    pub watched: String,
+    /// The path that changed: the watched path itself, or an entry below it.
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed { new_path: String },
+}
+
+/// Watch a file or directory, recursively, receiving a [`VfsNotification`] request
+/// each time it changes, until [`unwatch()`] is called. Watching a path twice
+/// has no further effect.
+///
+/// The process must have read capability for the drive of `path`.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{await_message, vfs};
+///
+/// vfs::watch("/my-app:template.os/config", None)?;
+/// loop {
+///     let Ok(message) = await_message() else { continue };
+///     if let Some(notification) = vfs::parse_notification(&message) {
+///         println!("{} changed: {:?}", notification.path, notification.kind);
+///     }
+/// }
+/// # Ok::<(), vfs::VfsError>(())
+/// ```
+pub fn watch(path: &str, timeout: Option<u64>) -> Result<(), VfsError> {
+    watch_action(path, VfsAction::Watch, timeout)
+}
+
+/// Stop watching a path watched with [`watch()`].
+pub fn unwatch(path: &str, timeout: Option<u64>) -> Result<(), VfsError> {
+    watch_action(path, VfsAction::Unwatch, timeout)
+}
+
+fn watch_action(path: &str, action: VfsAction, timeout: Option<u64>) -> Result<(), VfsError> {
+    let timeout = timeout.unwrap_or(5);
+
+    let message = vfs_request(path, action)
+        .send_and_await_response(timeout)
+        .unwrap()
+        .map_err(|e| VfsError::SendError(e.kind))?;
+
+    match parse_response(message.body())? {
+        VfsResponse::Ok => Ok(()),
+        VfsResponse::Err(e) => Err(e),
+        _ => Err(VfsError::ParseError {
+            error: "unexpected response".to_string(),
+            path: path.to_string(),
+        }),
+    }
+}
+
+/// Parse `message` as a [`VfsNotification`], if it is one from the local VFS.
+pub fn parse_notification(message: &Message) -> Option<VfsNotification> {
+    if !message.is_request() || !message.is_local() || !message.is_process("vfs:distro:sys") {
+        return None;
+    }
+    serde_json::from_slice(message.body()).ok()
+}
@@ -0,0 +1,248 @@
+use crate::timer::set_timer;
+use crate::{Address, Message, Request};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+//
+// Heartbeats between supervised processes and their supervisor
+//
+
+/// Timer context used by [`Heartbeat`] to schedule the next beat.
+const HEARTBEAT_CONTEXT: &[u8] = b"watchdog:heartbeat";
+/// Timer context used by [`Watchdog`] to schedule the next check.
+const CHECK_CONTEXT: &[u8] = b"watchdog:check";
+
+/// IPC body format for heartbeat requests sent by a [`Heartbeat`] to its
+/// [`Watchdog`]. No response is expected.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WatchdogMessage {
+    /// The source process is alive. `seq` counts up from 0 with each beat.
+    Heartbeat { seq: u64 },
+}
+
+impl From<WatchdogMessage> for Vec<u8> {
+    fn from(message: WatchdogMessage) -> Self {
+        serde_json::to_vec(&message).unwrap()
+    }
+}
+
+fn is_timer_response(message: &Message, context: &[u8]) -> bool {
+    !message.is_request()
+        && message.is_process("timer:distro:sys")
+        && message.context() == Some(context)
+}
+
+/// The child side of a watchdog: sends a heartbeat to a supervising process
+/// every `interval`, driven by `timer:distro:sys`.
+///
+/// Example:
+/// ```no_run
+/// use kinode_process_lib::{await_message, watchdog::Heartbeat, Address};
+/// use std::time::Duration;
+///
+/// let parent: Address = "our@indexer:indexer:template.os".parse().unwrap();
+/// let mut heartbeat = Heartbeat::start(parent, Duration::from_secs(5));
+/// loop {
+///     let Ok(message) = await_message() else { continue };
+///     if heartbeat.handle_message(&message) {
+///         continue;
+///     }
+///     // handle message
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Heartbeat {
+    parent: Address,
+    interval: Duration,
+    seq: u64,
+}
+
+impl Heartbeat {
+    /// Send a first heartbeat to `parent` and schedule the next.
+    pub fn start(parent: Address, interval: Duration) -> Self {
+        let mut heartbeat = Heartbeat {
+            parent,
+            interval,
+            seq: 0,
+        };
+        heartbeat.beat();
+        heartbeat
+    }
+    fn beat(&mut self) {
+        Request::to(&self.parent)
+            .body(WatchdogMessage::Heartbeat { seq: self.seq })
+            .send()
+            .unwrap();
+        self.seq += 1;
+        set_timer(
+            self.interval.as_millis() as u64,
+            Some(HEARTBEAT_CONTEXT.to_vec()),
+        );
+    }
+    /// If `message` is the timer response scheduling the next heartbeat, send it
+    /// and return `true`. Otherwise, return `false` and leave the message to the caller.
+    pub fn handle_message(&mut self, message: &Message) -> bool {
+        if !is_timer_response(message, HEARTBEAT_CONTEXT) {
+            return false;
+        }
+        self.beat();
+        true
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Watched {
+    last_beat: Instant,
+    /// Whether the missed-beat callback has been called since the last beat.
+    reported: bool,
+}
+
+/// The parent side of a watchdog: tracks heartbeats from watched processes and
+/// calls `on_missed` with the address of any process that has not sent one for
+/// longer than `timeout`, along with how long it has been.
+///
+/// `on_missed` is called once per lapse: it is called again for the same process
+/// only after that process has resumed sending heartbeats and then lapsed again.
+/// Typical callbacks restart the process or raise an alert.
+///
+/// Checks are driven by `timer:distro:sys` every half `timeout`, so a lapse is
+/// detected within one and a half `timeout`s of the last heartbeat.
+pub struct Watchdog<F>
+where
+    F: FnMut(&Address, Duration),
+{
+    timeout: Duration,
+    watched: HashMap<Address, Watched>,
+    on_missed: F,
+}
+
+impl<F> Watchdog<F>
+where
+    F: FnMut(&Address, Duration),
+{
+    pub fn new(timeout: Duration, on_missed: F) -> Self {
+        Watchdog {
+            timeout,
+            watched: HashMap::new(),
+            on_missed,
+        }
+    }
+    /// Start the periodic checks. Call once, after creating the watchdog.
+    pub fn start(&self) {
+        self.schedule_check();
+    }
+    fn schedule_check(&self) {
+        set_timer(
+            (self.timeout.as_millis() as u64 / 2).max(1),
+            Some(CHECK_CONTEXT.to_vec()),
+        );
+    }
+    /// Watch a process, e.g. a freshly spawned worker. It is given one `timeout`
+    /// from now to send its first heartbeat.
+    pub fn watch(&mut self, process: Address) {
+        self.watch_at(process, Instant::now());
+    }
+    fn watch_at(&mut self, process: Address, now: Instant) {
+        self.watched.insert(
+            process,
+            Watched {
+                last_beat: now,
+                reported: false,
+            },
+        );
+    }
+    pub fn unwatch(&mut self, process: &Address) {
+        self.watched.remove(process);
+    }
+    /// The processes being watched.
+    pub fn watched(&self) -> impl Iterator<Item = &Address> {
+        self.watched.keys()
+    }
+    /// Time since the last heartbeat of a watched process.
+    pub fn since_last_beat(&self, process: &Address) -> Option<Duration> {
+        self.watched
+            .get(process)
+            .map(|watched| watched.last_beat.elapsed())
+    }
+    fn beat_at(&mut self, process: &Address, now: Instant) -> bool {
+        let Some(watched) = self.watched.get_mut(process) else {
+            return false;
+        };
+        watched.last_beat = now;
+        watched.reported = false;
+        true
+    }
+    /// Call `on_missed` for each watched process that has lapsed as of `now`.
+    fn check_at(&mut self, now: Instant) {
+        for (process, watched) in self.watched.iter_mut() {
+            let since = now.saturating_duration_since(watched.last_beat);
+            if since > self.timeout && !watched.reported {
+                watched.reported = true;
+                (self.on_missed)(process, since);
+            }
+        }
+    }
+    /// Handle a heartbeat from a watched process or a check timer, returning
+    /// `true` if `message` was one. Otherwise, return `false` and leave the
+    /// message to the caller.
+    ///
+    /// Heartbeats from processes that are not watched are ignored, but still consumed.
+    pub fn handle_message(&mut self, message: &Message) -> bool {
+        if is_timer_response(message, CHECK_CONTEXT) {
+            self.check_at(Instant::now());
+            self.schedule_check();
+            return true;
+        }
+        if !message.is_request() {
+            return false;
+        }
+        let Ok(WatchdogMessage::Heartbeat { .. }) =
+            serde_json::from_slice::<WatchdogMessage>(message.body())
+        else {
+            return false;
+        };
+        self.beat_at(message.source(), Instant::now());
+        true
+    }
+}
+
+impl<F> std::fmt::Debug for Watchdog<F>
+where
+    F: FnMut(&Address, Duration),
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("timeout", &self.timeout)
+            .field("watched", &self.watched)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missed_beats() {
+        let mut missed = vec![];
+        let worker = Address::new("node.os", ("worker", "pkg", "template.os"));
+        let now = Instant::now();
+        {
+            let mut watchdog = Watchdog::new(Duration::from_secs(10), |process, _since| {
+                missed.push(process.clone())
+            });
+            watchdog.watch_at(worker.clone(), now);
+            watchdog.check_at(now + Duration::from_secs(5));
+            assert!(watchdog.beat_at(&worker, now + Duration::from_secs(5)));
+            watchdog.check_at(now + Duration::from_secs(14));
+            // lapsed: reported once
+            watchdog.check_at(now + Duration::from_secs(16));
+            watchdog.check_at(now + Duration::from_secs(20));
+            // resumed, then lapsed again
+            watchdog.beat_at(&worker, now + Duration::from_secs(21));
+            watchdog.check_at(now + Duration::from_secs(32));
+        }
+        assert_eq!(missed, vec![worker.clone(), worker]);
+    }
+}